@@ -0,0 +1,27 @@
+fn main() {
+    #[cfg(feature = "ffi")]
+    generate_header();
+}
+
+/// Writes the C declarations of `src/ffi.rs` to `include/learning_record.h`.
+#[cfg(feature = "ffi")]
+fn generate_header() {
+    let dir = std::env::var("CARGO_MANIFEST_DIR").expect("cargo sets CARGO_MANIFEST_DIR");
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+
+    let mut config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        include_guard: Some("LEARNING_RECORD_H".to_string()),
+        usize_is_size_t: true,
+        ..Default::default()
+    };
+    // Only the items of src/ffi.rs belong to the C interface.
+    config.export.exclude.push("VERSION".to_string());
+
+    cbindgen::Builder::new()
+        .with_crate(&dir)
+        .with_config(config)
+        .generate()
+        .expect("src/ffi.rs declares valid C functions")
+        .write_to_file(format!("{dir}/include/learning_record.h"));
+}
@@ -0,0 +1,102 @@
+#ifndef LEARNING_RECORD_H
+#define LEARNING_RECORD_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * A parsed record file, freed with `lr_file_free`.
+ */
+typedef struct LrFile LrFile;
+
+/**
+ * Totals of one week, freed with `lr_report_free`.
+ */
+typedef struct LrReport LrReport;
+
+
+
+
+
+/**
+ * Message of the last failure on this thread, or NULL. Functions returning
+ * NULL or -1 on failure leave one. Valid until the next failure.
+ */
+const char *lr_last_error(void);
+
+/**
+ * Reads and parses the record file at `path`, NULL on failure. `@include`
+ * directives are not followed.
+ *
+ * # Safety
+ *
+ * `path` must be a valid NUL-terminated string.
+ */
+struct LrFile *lr_parse_file(const char *path);
+
+/**
+ * # Safety
+ *
+ * `file` must come from `lr_parse_file` and not be used afterwards.
+ */
+void lr_file_free(struct LrFile *file);
+
+/**
+ * Totals of the week containing `today`, a date and time such as
+ * `2024-09-15T21:00:00`, or NULL on failure.
+ *
+ * # Safety
+ *
+ * `file` must come from `lr_parse_file` and `today` must be a valid
+ * NUL-terminated string.
+ */
+struct LrReport *lr_weekly_report(const struct LrFile *file, const char *today);
+
+/**
+ * # Safety
+ *
+ * `report` must come from `lr_weekly_report` and not be used afterwards.
+ */
+void lr_report_free(struct LrReport *report);
+
+/**
+ * Total minutes of the week, -1 if `report` is NULL.
+ *
+ * # Safety
+ *
+ * `report` must come from `lr_weekly_report`.
+ */
+int64_t lr_report_total_minutes(const struct LrReport *report);
+
+/**
+ * Number of tags of the week, in order of first appearance.
+ *
+ * # Safety
+ *
+ * `report` must come from `lr_weekly_report`.
+ */
+size_t lr_report_tag_count(const struct LrReport *report);
+
+/**
+ * Name of the tag at `index`, or NULL when out of range. Valid as long as
+ * `report`.
+ *
+ * # Safety
+ *
+ * `report` must come from `lr_weekly_report`.
+ */
+const char *lr_report_tag_name(const struct LrReport *report, size_t index);
+
+/**
+ * Minutes of the tag at `index`, or -1 when out of range.
+ *
+ * # Safety
+ *
+ * `report` must come from `lr_weekly_report`.
+ */
+int64_t lr_report_tag_minutes(const struct LrReport *report, size_t index);
+
+#endif  /* LEARNING_RECORD_H */
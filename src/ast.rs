@@ -1,38 +1,213 @@
+use std::{ops::Range, path::PathBuf};
+
 use chrono::{NaiveDate, NaiveTime, TimeDelta};
+use serde::Deserialize as _;
 
-use crate::settings::Settings;
+use crate::{format, settings::Settings};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 pub struct File {
     pub settings: Option<Settings>,
     pub records: Vec<DayRecord>,
+    /// Settings blocks between day records, in file order.
+    pub changes: Vec<SettingsChange>,
+    /// `@include` directives, resolved by the caller.
+    pub includes: Vec<Include>,
+}
+
+impl File {
+    /// Settings in effect on `date`: those of the last block placed before
+    /// a record of `date` or earlier, else the front matter.
+    pub fn settings_at(&self, date: NaiveDate) -> Option<&Settings> {
+        self.changes
+            .iter()
+            .rev()
+            .find(|change| change.from <= date)
+            .map(|change| &change.settings)
+            .or(self.settings.as_ref())
+    }
+}
+
+/// `@include path`, a file whose day records are read as part of this one.
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub struct Include {
+    /// As written, relative to the including file.
+    pub path: PathBuf,
+    pub line: usize,
+}
+
+/// A settings block in the middle of the file. Its keys are merged over the
+/// settings in effect before it.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct SettingsChange {
+    /// Date of the first day record after the block.
+    pub from: NaiveDate,
+    /// Line of the opening `---`, starting at 1.
+    pub line: usize,
+    pub settings: Settings,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
 pub struct DayRecord {
     pub date: NaiveDate,
     pub events: Vec<Event>,
+    /// Line of the date header, starting at 1.
+    pub line: usize,
+    /// File the record was included from; `None` for the file itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<PathBuf>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
 pub struct Event {
     pub tags: Option<Tags>,
+    /// Time expected beforehand, written `~2h` before the sessions.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_optional_seconds",
+        deserialize_with = "deserialize_optional_seconds"
+    )]
+    #[schemars(with = "Option<i64>")]
+    pub estimate: Option<TimeDelta>,
     pub info: Vec<EventInfo>,
+    /// Line of the event, starting at 1.
+    pub line: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
 pub struct Tags {
     pub tags: Vec<Tag>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
 pub struct Tag {
     pub title: String,
     pub detail: Option<String>,
+    /// Position of the tag with its detail in the source, in characters.
+    pub span: Range<usize>,
+    /// Position of the title alone in the source, in characters. Under
+    /// `normalize_input` its length can differ from that of `title`.
+    pub title_span: Range<usize>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
 pub struct EventInfo {
-    pub time: NaiveTime,
+    /// Start of the session; `None` when only its duration was written,
+    /// as in `[anki] 20m`.
+    #[serde(default)]
+    pub time: Option<NaiveTime>,
+    #[serde(
+        serialize_with = "serialize_seconds",
+        deserialize_with = "deserialize_seconds"
+    )]
+    /// Negative for corrections, written like `!-30m`.
+    #[schemars(with = "i64")]
     pub duration: TimeDelta,
+    /// Number of pomodoros when written like `3p`; `duration` holds their
+    /// length.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pomodoros: Option<u32>,
+}
+
+impl EventInfo {
+    /// Whether this takes time off, as written like `!-30m`.
+    pub fn is_correction(&self) -> bool {
+        self.duration < TimeDelta::zero()
+    }
+}
+
+impl std::fmt::Display for DayRecord {
+    /// The date line followed by one line per event, without comments.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.date.format("%Y-%m-%d"))?;
+        for event in &self.events {
+            write!(f, "\n{event}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Event {
+    /// The event as it is written in a record file, e.g.
+    /// `[rust(ownership) book] ~2h 21:00 - 1h30m, 23:00 - 20m`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(tags) = &self.tags {
+            let tags: Vec<String> = tags
+                .tags
+                .iter()
+                .map(|tag| match &tag.detail {
+                    Some(detail) => format!("{}({})", tag.title, detail),
+                    None => tag.title.clone(),
+                })
+                .collect();
+            write!(f, "[{}] ", tags.join(" "))?;
+        }
+        if let Some(estimate) = self.estimate {
+            write!(f, "~{} ", format::duration(estimate))?;
+        }
+        for (index, info) in self.info.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            if let Some(time) = info.time {
+                write!(f, "{} - ", format::time(time))?;
+            }
+            match info.pomodoros {
+                Some(pomodoros) => write!(f, "{pomodoros}p")?,
+                None if info.is_correction() => write!(f, "!{}", format::duration(info.duration))?,
+                None => f.write_str(&format::duration(info.duration))?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn serialize_seconds<S: serde::Serializer>(
+    delta: &TimeDelta,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i64(delta.num_seconds())
+}
+
+fn deserialize_seconds<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<TimeDelta, D::Error> {
+    let seconds = i64::deserialize(deserializer)?;
+    TimeDelta::try_seconds(seconds).ok_or_else(|| serde::de::Error::custom("duration out of range"))
+}
+
+fn serialize_optional_seconds<S: serde::Serializer>(
+    delta: &Option<TimeDelta>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match delta {
+        Some(delta) => serialize_seconds(delta, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn deserialize_optional_seconds<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<TimeDelta>, D::Error> {
+    match Option::<i64>::deserialize(deserializer)? {
+        Some(seconds) => TimeDelta::try_seconds(seconds)
+            .map(Some)
+            .ok_or_else(|| serde::de::Error::custom("duration out of range")),
+        None => Ok(None),
+    }
 }
@@ -0,0 +1,59 @@
+use chrono::TimeDelta;
+
+use crate::format;
+
+const GREEN: &str = "#4c1";
+const ORANGE: &str = "#fe7d37";
+const BLUE: &str = "#007ec6";
+
+/// A shields.io style badge of the time of the week, against the goal if
+/// there is one: green once reached, orange before.
+pub fn svg(label: &str, total: TimeDelta, goal: Option<TimeDelta>) -> String {
+    let (value, color) = match goal {
+        Some(goal) => (
+            format!("{} / {}", format::duration(total), format::duration(goal)),
+            if total >= goal { GREEN } else { ORANGE },
+        ),
+        None => (format::duration(total), BLUE),
+    };
+    let label_width = text_width(label);
+    let value_width = text_width(&value);
+    let (label, value) = (escape(label), escape(&value));
+    let width = label_width + value_width;
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {value}">
+  <title>{label}: {value}</title>
+  <linearGradient id="s" x2="0" y2="100%">
+    <stop offset="0" stop-color="#bbb" stop-opacity=".1"/>
+    <stop offset="1" stop-opacity=".1"/>
+  </linearGradient>
+  <clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{value_width}" height="20" fill="{color}"/>
+    <rect width="{width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text>
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{value_x}" y="15" fill="#010101" fill-opacity=".3">{value}</text>
+    <text x="{value_x}" y="14">{value}</text>
+  </g>
+</svg>"##,
+        label_x = label_width as f64 / 2.0,
+        value_x = label_width as f64 + value_width as f64 / 2.0,
+    )
+}
+
+/// Rough width of `text` in 11px Verdana, with padding on both sides.
+fn text_width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
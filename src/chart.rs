@@ -0,0 +1,252 @@
+use std::{collections::BTreeMap, path::Path};
+
+use chrono::{NaiveDate, TimeDelta};
+use plotters::{coord::Shift, prelude::*};
+
+use crate::{processing::Burndown, settings::TagStyle};
+
+const SIZE: (u32, u32) = (1024, 640);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ChartKind {
+    /// Line of the weekly totals against the goal
+    WeeklyTrend,
+    /// Share of each tag in the total
+    TagPie,
+    /// Goal time left after each day of this week against an even pace
+    Burndown,
+}
+
+/// What a chart is drawn from.
+#[derive(Debug, Clone, Default)]
+pub struct Data {
+    /// Start and total of each week, oldest first.
+    pub weeks: Vec<(NaiveDate, TimeDelta)>,
+    pub goal: Option<TimeDelta>,
+    /// Time per tag over all the weeks.
+    pub tags: Vec<(String, TimeDelta)>,
+    /// This week, `None` without a goal.
+    pub burndown: Option<Burndown>,
+    /// Colors and icons from `[tags]`.
+    pub styles: BTreeMap<String, TagStyle>,
+}
+
+/// Draws a chart into `path`, as SVG if it ends in `.svg` and PNG otherwise.
+/// Tag icons are only drawn in SVG, as the fonts of PNG may lack them.
+pub fn draw(path: &Path, kind: ChartKind, data: &Data) -> Result<(), String> {
+    let svg = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"));
+    if svg {
+        draw_on(
+            SVGBackend::new(path, SIZE).into_drawing_area(),
+            kind,
+            data,
+            true,
+        )
+    } else {
+        draw_on(
+            BitMapBackend::new(path, SIZE).into_drawing_area(),
+            kind,
+            data,
+            false,
+        )
+    }
+}
+
+fn draw_on<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    kind: ChartKind,
+    data: &Data,
+    icons: bool,
+) -> Result<(), String> {
+    root.fill(&WHITE).map_err(|err| err.to_string())?;
+    match kind {
+        ChartKind::WeeklyTrend => weekly_trend(&root, data),
+        ChartKind::TagPie => tag_pie(&root, data, icons),
+        ChartKind::Burndown => burndown(&root, data),
+    }
+    .map_err(|err| err.to_string())?;
+
+    root.present().map_err(|err| err.to_string())
+}
+
+fn hours(duration: TimeDelta) -> f64 {
+    duration.num_minutes() as f64 / 60.0
+}
+
+fn weekly_trend<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    data: &Data,
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let top = data
+        .weeks
+        .iter()
+        .map(|(_, total)| hours(*total))
+        .chain(data.goal.map(hours))
+        .fold(1.0, f64::max)
+        * 1.1;
+    let last = data.weeks.len().saturating_sub(1).max(1);
+
+    let mut chart = ChartBuilder::on(root)
+        .caption("Weekly learning time", ("sans-serif", 32))
+        .margin(24)
+        .x_label_area_size(40)
+        .y_label_area_size(56)
+        .build_cartesian_2d(0..last, 0.0..top)?;
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_labels(data.weeks.len().min(12))
+        .x_label_formatter(&|index| {
+            data.weeks
+                .get(*index)
+                .map(|(start, _)| start.format("%m-%d").to_string())
+                .unwrap_or_default()
+        })
+        .y_desc("hours")
+        .draw()?;
+
+    let points = data
+        .weeks
+        .iter()
+        .enumerate()
+        .map(|(index, (_, total))| (index, hours(*total)));
+    chart
+        .draw_series(LineSeries::new(points.clone(), BLUE.stroke_width(3)))?
+        .label("total")
+        .legend(|(x, y)| PathElement::new([(x, y), (x + 20, y)], BLUE.stroke_width(3)));
+    chart.draw_series(points.map(|point| Circle::new(point, 4, BLUE.filled())))?;
+    if let Some(goal) = data.goal {
+        chart
+            .draw_series(LineSeries::new(
+                [(0, hours(goal)), (last, hours(goal))],
+                RED.stroke_width(2),
+            ))?
+            .label("goal")
+            .legend(|(x, y)| PathElement::new([(x, y), (x + 20, y)], RED.stroke_width(2)));
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    Ok(())
+}
+
+fn tag_pie<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    data: &Data,
+    icons: bool,
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let root = root.titled("Time per tag", ("sans-serif", 32))?;
+    let tags: Vec<&(String, TimeDelta)> = data
+        .tags
+        .iter()
+        .filter(|(_, duration)| *duration > TimeDelta::zero())
+        .collect();
+    let sizes: Vec<f64> = tags.iter().map(|(_, duration)| hours(*duration)).collect();
+    let labels: Vec<String> = tags
+        .iter()
+        .map(
+            |(tag, _)| match data.styles.get(tag).and_then(|style| style.icon.as_ref()) {
+                Some(icon) if icons => format!("{icon} {tag}"),
+                _ => tag.clone(),
+            },
+        )
+        .collect();
+    let colors: Vec<RGBColor> = tags
+        .iter()
+        .enumerate()
+        .map(
+            |(index, (tag, _))| match data.styles.get(tag).and_then(|style| style.color) {
+                Some(color) => {
+                    let [r, g, b] = color.0;
+                    RGBColor(r, g, b)
+                }
+                None => {
+                    let (r, g, b) = Palette99::pick(index).rgb();
+                    RGBColor(r, g, b)
+                }
+            },
+        )
+        .collect();
+
+    let (width, height) = root.dim_in_pixel();
+    let center = (width as i32 / 2, height as i32 / 2);
+    let radius = f64::from(width.min(height)) * 0.35;
+    let mut pie = Pie::new(&center, &radius, &sizes, &colors, &labels);
+    pie.start_angle(-90.0);
+    pie.label_style(("sans-serif", 20).into_font());
+    pie.percentages(("sans-serif", 16).into_font().color(&WHITE));
+    root.draw(&pie)
+}
+
+fn burndown<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    data: &Data,
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let Some(Burndown { goal, days }) = &data.burndown else {
+        return Ok(());
+    };
+    let goal = hours(*goal);
+    let top = days
+        .iter()
+        .filter_map(|day| day.remaining)
+        .map(hours)
+        .fold(goal.max(1.0), f64::max)
+        * 1.1;
+
+    // Day `index` ends at `index + 1`; 0 is the start of the week.
+    let mut chart = ChartBuilder::on(root)
+        .caption("Goal burn-down", ("sans-serif", 32))
+        .margin(24)
+        .x_label_area_size(40)
+        .y_label_area_size(56)
+        .build_cartesian_2d(0..days.len(), 0.0..top)?;
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_labels(days.len() + 1)
+        .x_label_formatter(&|index| {
+            days.get(index.wrapping_sub(1))
+                .map(|day| day.date.format("%a %m-%d").to_string())
+                .unwrap_or_default()
+        })
+        .y_desc("hours left")
+        .draw()?;
+
+    let ideal = std::iter::once((0, goal)).chain(
+        days.iter()
+            .enumerate()
+            .map(|(index, day)| (index + 1, hours(day.ideal))),
+    );
+    chart
+        .draw_series(LineSeries::new(ideal, BLACK.mix(0.5).stroke_width(2)))?
+        .label("ideal")
+        .legend(|(x, y)| PathElement::new([(x, y), (x + 20, y)], BLACK.mix(0.5).stroke_width(2)));
+    let actual: Vec<(usize, f64)> = std::iter::once((0, goal))
+        .chain(
+            days.iter()
+                .enumerate()
+                .filter_map(|(index, day)| Some((index + 1, hours(day.remaining?)))),
+        )
+        .collect();
+    chart
+        .draw_series(LineSeries::new(actual.clone(), BLUE.stroke_width(3)))?
+        .label("left")
+        .legend(|(x, y)| PathElement::new([(x, y), (x + 20, y)], BLUE.stroke_width(3)));
+    chart.draw_series(
+        actual
+            .into_iter()
+            .map(|point| Circle::new(point, 4, BLUE.filled())),
+    )?;
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, FixedOffset, TimeDelta};
+use chrono_tz::Tz;
+
+use crate::import::{self, Entry};
+
+/// A detailed report (`{"timeentries": [...]}`) or a list of time entries
+/// from the API.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Export {
+    Report {
+        #[serde(alias = "timeEntries")]
+        timeentries: Vec<TimeEntry>,
+    },
+    Entries(Vec<TimeEntry>),
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TimeEntry {
+    #[serde(default)]
+    description: Option<String>,
+    /// Set in reports.
+    #[serde(default)]
+    project_name: Option<String>,
+    /// Set in time entries fetched with `hydrated=true`.
+    #[serde(default)]
+    project: Option<Project>,
+    time_interval: TimeInterval,
+}
+
+#[derive(serde::Deserialize)]
+struct Project {
+    name: String,
+}
+
+#[derive(serde::Deserialize)]
+struct TimeInterval {
+    start: DateTime<FixedOffset>,
+    /// `None` while the timer runs.
+    #[serde(default)]
+    end: Option<DateTime<FixedOffset>>,
+}
+
+/// Reads the time entries of a Clockify JSON export, tagged with their
+/// project and its description as detail. Running timers are left out.
+pub fn entries(source: &str, timezone: Option<Tz>) -> Result<Vec<Entry>, String> {
+    let export: Export = serde_json::from_str(source).map_err(|err| err.to_string())?;
+    let (Export::Report {
+        timeentries: entries,
+    }
+    | Export::Entries(entries)) = export;
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| {
+            let end = entry.time_interval.end?;
+            let start = import::local(entry.time_interval.start, timezone);
+            let duration = import::local(end, timezone) - start;
+            if duration <= TimeDelta::zero() {
+                return None;
+            }
+            let project = entry
+                .project_name
+                .or(entry.project.map(|project| project.name))
+                .map(|project| import::tag(&project))
+                .filter(|project| !project.is_empty());
+            let description = entry.description.as_deref().and_then(import::detail);
+
+            Some(Entry {
+                date: start.date(),
+                time: start.time(),
+                duration,
+                tags: project
+                    .map(|project| vec![(project, description)])
+                    .unwrap_or_default(),
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,83 @@
+use std::{fs, path::Path, sync::Arc};
+
+use arrow_array::{
+    builder::{ListBuilder, StringBuilder},
+    ArrayRef, Date32Array, Int64Array, RecordBatch, TimestampMicrosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::Datelike as _;
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+
+use crate::{ast, processing::Filter};
+
+/// Days from 0001-01-01 to 1970-01-01, the epoch of Arrow dates.
+const UNIX_EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+/// Writes one row per session to a Parquet file at `path` and returns the
+/// number of rows: `date`, `start` (wall-clock time), `seconds`, `tags`,
+/// `details` (null where a tag has none) and `line`.
+pub fn write(path: &Path, file: &ast::File, filter: &Filter) -> Result<usize, String> {
+    let mut dates = Vec::new();
+    let mut starts = Vec::new();
+    let mut seconds = Vec::new();
+    let mut lines = Vec::new();
+    let mut tags = ListBuilder::new(StringBuilder::new());
+    let mut details = ListBuilder::new(StringBuilder::new());
+    for record in &file.records {
+        for event in &record.events {
+            for info in &event.info {
+                if !filter.matches_session(record, event, info) {
+                    continue;
+                }
+                dates.push(record.date.num_days_from_ce() - UNIX_EPOCH_DAYS_FROM_CE);
+                starts.push(
+                    info.time
+                        .map(|time| record.date.and_time(time).and_utc().timestamp_micros()),
+                );
+                seconds.push(info.duration.num_seconds());
+                lines.push(event.line as i64);
+                for tag in event.tags.iter().flat_map(|tags| &tags.tags) {
+                    tags.values().append_value(&tag.title);
+                    details.values().append_option(tag.detail.as_deref());
+                }
+                tags.append(true);
+                details.append(true);
+            }
+        }
+    }
+
+    let item = || Arc::new(Field::new("item", DataType::Utf8, true));
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("date", DataType::Date32, false),
+        Field::new(
+            "start",
+            DataType::Timestamp(TimeUnit::Microsecond, None),
+            true,
+        ),
+        Field::new("seconds", DataType::Int64, false),
+        Field::new("tags", DataType::List(item()), false),
+        Field::new("details", DataType::List(item()), false),
+        Field::new("line", DataType::Int64, false),
+    ]));
+    let rows = dates.len();
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Date32Array::from(dates)),
+        Arc::new(TimestampMicrosecondArray::from(starts)),
+        Arc::new(Int64Array::from(seconds)),
+        Arc::new(tags.finish()),
+        Arc::new(details.finish()),
+        Arc::new(Int64Array::from(lines)),
+    ];
+    let batch = RecordBatch::try_new(schema.clone(), columns).map_err(|err| err.to_string())?;
+
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let out = fs::File::create(path).map_err(|err| err.to_string())?;
+    let mut writer =
+        ArrowWriter::try_new(out, schema, Some(properties)).map_err(|err| err.to_string())?;
+    writer.write(&batch).map_err(|err| err.to_string())?;
+    writer.close().map_err(|err| err.to_string())?;
+
+    Ok(rows)
+}
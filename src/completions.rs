@@ -0,0 +1,94 @@
+use std::io::Write;
+
+use clap::CommandFactory as _;
+use clap_complete::Shell;
+
+use crate::{ast, Cli};
+
+const BIN: &str = "learning-record";
+
+/// Writes the completion script for `shell`, extended with completion of
+/// tag names read from the record file.
+pub fn generate(shell: Shell, out: &mut impl Write) -> std::io::Result<()> {
+    clap_complete::generate(shell, &mut Cli::command(), BIN, out);
+
+    let dynamic = match shell {
+        Shell::Bash => BASH,
+        Shell::Zsh => ZSH,
+        Shell::Fish => FISH,
+        _ => return Ok(()),
+    };
+    writeln!(out, "{}", dynamic)
+}
+
+/// All distinct tag names in order of first appearance.
+pub fn tag_names(file: &ast::File) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let titles = file
+        .records
+        .iter()
+        .flat_map(|record| &record.events)
+        .flat_map(|event| &event.tags)
+        .flat_map(|tags| &tags.tags)
+        .map(|tag| &tag.title);
+
+    for title in titles {
+        if !names.contains(title) {
+            names.push(title.clone());
+        }
+    }
+
+    names
+}
+
+const BASH: &str = r#"
+_learning-record_tags() {
+    local file="" i
+    for (( i = 1; i < ${#COMP_WORDS[@]}; i++ )); do
+        case "${COMP_WORDS[i]}" in
+            -f|--file) file="${COMP_WORDS[i+1]}" ;;
+        esac
+    done
+    learning-record ${file:+--file "$file"} complete-tags 2>/dev/null
+}
+
+_learning-record_dynamic() {
+    if [[ "${COMP_WORDS[COMP_CWORD-1]}" == "--tag" ]]; then
+        COMPREPLY=( $(compgen -W "$(_learning-record_tags)" -- "${COMP_WORDS[COMP_CWORD]}") )
+        return 0
+    fi
+    _learning-record "$@"
+}
+
+complete -F _learning-record_dynamic -o bashdefault -o default learning-record"#;
+
+const ZSH: &str = r#"
+_learning-record_dynamic() {
+    if [[ "${words[CURRENT-1]}" == "--tag" ]]; then
+        local file="" i
+        for (( i = 2; i < CURRENT; i++ )); do
+            [[ "${words[i]}" == (-f|--file) ]] && file="${words[i+1]}"
+        done
+        local -a tags
+        tags=(${(f)"$(learning-record ${file:+--file "$file"} complete-tags 2>/dev/null)"})
+        compadd -a tags
+        return
+    fi
+    _learning-record "$@"
+}
+
+compdef _learning-record_dynamic learning-record"#;
+
+const FISH: &str = r#"
+function __learning_record_tags
+    set -l tokens (commandline -opc)
+    set -l file
+    for i in (seq (count $tokens))
+        if contains -- $tokens[$i] -f --file
+            set file --file $tokens[(math $i + 1)]
+        end
+    end
+    learning-record $file complete-tags 2>/dev/null
+end
+
+complete -c learning-record -l tag -x -a '(__learning_record_tags)'"#;
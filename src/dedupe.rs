@@ -0,0 +1,64 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::NaiveDate;
+
+use crate::ast;
+
+/// An event written a second time on the same day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    pub date: NaiveDate,
+    /// Line of the duplicate, starting at 1.
+    pub line: usize,
+    /// Line of the event it repeats.
+    pub original: usize,
+    pub event: String,
+}
+
+/// Outcome of removing duplicated events from a record file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dedupe {
+    pub content: String,
+    /// Duplicates removed, in file order.
+    pub duplicates: Vec<Duplicate>,
+}
+
+/// Removes from `source`, which `file` was parsed from, the events with the
+/// same tags, details and sessions as an earlier event of the same date,
+/// even in another day record of that date. The first one is kept.
+///
+/// Records pulled in with `@include` are left alone.
+pub fn dedupe(source: &str, file: &ast::File) -> Dedupe {
+    let mut seen: BTreeMap<(NaiveDate, String), usize> = BTreeMap::new();
+    let mut duplicates = Vec::new();
+    for record in file.records.iter().filter(|record| record.origin.is_none()) {
+        for event in &record.events {
+            let text = event.to_string();
+            match seen.get(&(record.date, text.clone())) {
+                Some(original) => duplicates.push(Duplicate {
+                    date: record.date,
+                    line: event.line,
+                    original: *original,
+                    event: text,
+                }),
+                None => {
+                    seen.insert((record.date, text), event.line);
+                }
+            }
+        }
+    }
+    duplicates.sort_by_key(|duplicate| duplicate.line);
+
+    let lines: BTreeSet<usize> = duplicates.iter().map(|duplicate| duplicate.line).collect();
+    let mut content = String::new();
+    for (index, line) in source.split_inclusive('\n').enumerate() {
+        if !lines.contains(&(index + 1)) {
+            content += line;
+        }
+    }
+
+    Dedupe {
+        content,
+        duplicates,
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, TimeDelta};
+
+use crate::{ast, processing::PeriodReport};
+
+/// Which of the two inputs something is only in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    First,
+    Second,
+}
+
+/// A day record, or an event of a day in both, found in only one input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    pub date: NaiveDate,
+    pub side: Side,
+    /// The event as written; `None` for a whole day record.
+    pub event: Option<String>,
+}
+
+/// Days and events of `first` missing from `second` and the other way
+/// round, by date. Events are compared by what they mean, not how they are
+/// spaced or where they are in the day.
+pub fn files(first: &ast::File, second: &ast::File) -> Vec<Difference> {
+    let first = days(first);
+    let second = days(second);
+    let mut dates: Vec<NaiveDate> = first.keys().chain(second.keys()).copied().collect();
+    dates.sort();
+    dates.dedup();
+
+    let mut differences = Vec::new();
+    for date in dates {
+        let (ours, theirs) = match (first.get(&date), second.get(&date)) {
+            (Some(ours), Some(theirs)) => (ours, theirs),
+            (ours, _) => {
+                let side = if ours.is_some() {
+                    Side::First
+                } else {
+                    Side::Second
+                };
+                differences.push(Difference {
+                    date,
+                    side,
+                    event: None,
+                });
+                continue;
+            }
+        };
+        let mut theirs_left = theirs.clone();
+        for event in ours {
+            match theirs_left.iter().position(|other| other == event) {
+                Some(index) => {
+                    theirs_left.remove(index);
+                }
+                None => differences.push(Difference {
+                    date,
+                    side: Side::First,
+                    event: Some(event.clone()),
+                }),
+            }
+        }
+        differences.extend(theirs_left.into_iter().map(|event| Difference {
+            date,
+            side: Side::Second,
+            event: Some(event),
+        }));
+    }
+
+    differences
+}
+
+/// Events of each date as written, records of the same date together.
+fn days(file: &ast::File) -> BTreeMap<NaiveDate, Vec<String>> {
+    let mut days: BTreeMap<NaiveDate, Vec<String>> = BTreeMap::new();
+    for record in &file.records {
+        let events = days.entry(record.date).or_default();
+        events.extend(record.events.iter().map(|event| event.to_string()));
+    }
+
+    days
+}
+
+/// Time of each tag in `first` and `second`, tags of `first` first.
+pub fn tag_changes(
+    first: &PeriodReport,
+    second: &PeriodReport,
+) -> Vec<(String, TimeDelta, TimeDelta)> {
+    let mut changes: Vec<(String, TimeDelta, TimeDelta)> = first
+        .tags
+        .iter()
+        .map(|(tag, time)| (tag.clone(), *time, TimeDelta::zero()))
+        .collect();
+    for (tag, time) in &second.tags {
+        match changes.iter_mut().find(|(name, _, _)| name == tag) {
+            Some(change) => change.2 = *time,
+            None => changes.push((tag.clone(), TimeDelta::zero(), *time)),
+        }
+    }
+
+    changes
+}
@@ -0,0 +1,216 @@
+use std::{env, fs, path::Path};
+
+use chrono::{NaiveDateTime, TimeDelta};
+
+use crate::{ast, error::Error, settings::Remote, validate};
+
+/// Sessions longer than this are probably typos.
+const LONG_SESSION: TimeDelta = TimeDelta::hours(12);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// Result of one diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub severity: Severity,
+    pub message: String,
+    /// What to do about a warning or an error.
+    pub hint: Option<String>,
+}
+
+impl Check {
+    pub fn ok(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Ok,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    pub fn error(message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Checks the environment and the health of the record file at `path`.
+///
+/// `load` parses the file the same way other commands do, including
+/// `@include`s and `--settings`.
+pub fn diagnose(
+    path: Option<&Path>,
+    now: NaiveDateTime,
+    load: impl Fn(&Path) -> Result<ast::File, Error>,
+) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let Some(path) = path else {
+        checks.push(Check::error(
+            "no record file configured",
+            "pass --file or set LEARNING_RECORD_FILE",
+        ));
+        return checks;
+    };
+    let source = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            checks.push(Check::error(
+                format!("cannot read {}: {}", path.display(), err),
+                "check the path given with --file or LEARNING_RECORD_FILE",
+            ));
+            return checks;
+        }
+    };
+    checks.push(Check::ok(format!("record file: {}", path.display())));
+
+    let Ok(text) = String::from_utf8(source) else {
+        checks.push(Check::error(
+            "the file is not valid UTF-8",
+            "convert it, e.g. with `iconv -t UTF-8`",
+        ));
+        return checks;
+    };
+    checks.extend(check_text(&text));
+
+    let file = match load(path) {
+        Ok(file) => file,
+        Err(err) => {
+            checks.push(Check::error(
+                format!("the file does not parse: {}", err),
+                "run `help syntax` for the expected format",
+            ));
+            return checks;
+        }
+    };
+    checks.push(Check::ok(format!(
+        "parsed {} day records",
+        file.records.len()
+    )));
+
+    checks.extend(check_settings(file.settings.as_ref()));
+    checks.extend(check_records(&file, now));
+    checks.extend(validate::validate(&file));
+
+    checks
+}
+
+fn check_text(text: &str) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    if !text.is_empty() && !text.ends_with('\n') {
+        checks.push(Check::warning(
+            "the last line has no newline",
+            "add a newline at the end of the file",
+        ));
+    }
+
+    checks
+}
+
+fn check_settings(settings: Option<&crate::settings::Settings>) -> Vec<Check> {
+    let Some(settings) = settings else {
+        return vec![Check::warning(
+            "no settings",
+            "add front matter with at least [start] weekday and time; see `help syntax`",
+        )];
+    };
+
+    let mut checks = vec![Check::ok("settings are valid")];
+    if settings.timezone.is_none() {
+        checks.push(Check::warning(
+            "no timezone set, the system time zone is used",
+            "set e.g. timezone = \"Asia/Tokyo\" for stable weeks when travelling",
+        ));
+    }
+    if let Some(Remote::Webdav {
+        username: Some(_), ..
+    }) = &settings.sync
+    {
+        if env::var_os("LEARNING_RECORD_WEBDAV_PASSWORD").is_none() {
+            checks.push(Check::warning(
+                "LEARNING_RECORD_WEBDAV_PASSWORD is not set",
+                "export it before running `sync`",
+            ));
+        }
+    }
+    if let Some(Remote::S3 { .. }) = &settings.sync {
+        for var in ["AWS_ACCESS_KEY_ID", "AWS_SECRET_ACCESS_KEY"] {
+            if env::var_os(var).is_none() {
+                checks.push(Check::warning(
+                    format!("{} is not set", var),
+                    "export it before running `sync`",
+                ));
+            }
+        }
+    }
+
+    checks
+}
+
+fn check_records(file: &ast::File, now: NaiveDateTime) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let own = || file.records.iter().filter(|record| record.origin.is_none());
+
+    for pair in own().collect::<Vec<_>>().windows(2) {
+        if pair[1].date < pair[0].date {
+            checks.push(Check::warning(
+                format!(
+                    "line {}: {} comes after {}",
+                    pair[1].line, pair[1].date, pair[0].date
+                ),
+                "keep day records in chronological order",
+            ));
+        }
+    }
+
+    for record in own() {
+        for info in record.events.iter().flat_map(|event| &event.info) {
+            let session = match info.time {
+                Some(time) => format!("session at {}", time.format("%H:%M")),
+                None => "session without a start".to_string(),
+            };
+            // Zero durations and day totals are checked by `validate`.
+            if info.duration > LONG_SESSION {
+                checks.push(Check::warning(
+                    format!(
+                        "{}: {session} lasts {}",
+                        record.date,
+                        crate::format::duration(info.duration)
+                    ),
+                    "check the duration for a typo",
+                ));
+            }
+            let start = info
+                .time
+                .map_or(record.date.into(), |time| record.date.and_time(time));
+            if start > now {
+                checks.push(Check::warning(
+                    format!("{}: {session} is in the future", record.date),
+                    "check the date",
+                ));
+            }
+        }
+    }
+
+    if checks.is_empty() {
+        checks.push(Check::ok("no suspicious entries"));
+    }
+
+    checks
+}
@@ -0,0 +1,214 @@
+use std::{io, path::PathBuf};
+
+use chrono::TimeDelta;
+
+use crate::{
+    format,
+    parser::{ParseError, ParseErrorKind},
+    processing, store, sync,
+};
+
+pub mod exit_code {
+    pub const FAILURE: u8 = 1;
+    pub const PARSE: u8 = 3;
+    pub const IO: u8 = 4;
+    pub const VALIDATION: u8 = 5;
+    pub const GOAL_MISSED: u8 = 6;
+}
+
+#[derive(Debug)]
+pub enum Error {
+    MissingFile,
+    Io(io::Error),
+    /// An error in another file, such as one pulled in with `@include` or
+    /// given with `--settings`.
+    InFile {
+        path: PathBuf,
+        source: Box<Error>,
+    },
+    /// A file includes itself, directly or not.
+    IncludeCycle,
+    InvalidSettings(ParseErrorKind),
+    Parse(ParseError),
+    Processing(processing::Error),
+    Sync(sync::Error),
+    Store(store::Error),
+    NoRemote,
+    UnknownHelpTopic(String),
+    MissingTemplate,
+    /// No weekly `goal` in the settings.
+    MissingGoal,
+    MissingRecurring,
+    UnknownProfile(String),
+    /// The tag has no hourly rate in `[rates]`.
+    MissingRate(String),
+    Template(Box<handlebars::RenderError>),
+    /// The `report.script` failed or left `report` or `metrics` unusable.
+    Script(String),
+    /// No `learning-record-export-NAME` on `PATH`.
+    UnknownExporter(String),
+    ExporterFailed {
+        program: String,
+        /// `None` when killed by a signal.
+        code: Option<i32>,
+    },
+    /// `export chart` could not draw or write the image.
+    Chart(String),
+    /// `export parquet` could not write the file.
+    #[cfg(feature = "arrow")]
+    Parquet(String),
+    /// `serve` could not listen on its address.
+    Serve(String),
+    /// Sessions could not be read from another tool.
+    Import(String),
+    /// The desktop notification could not be shown.
+    Notify(String),
+    /// `search` found nothing.
+    NoMatch,
+    /// `split` cannot split the record file.
+    Split(String),
+    /// `diff` found differences.
+    Differ,
+    /// `doctor` found this many errors.
+    Unhealthy(usize),
+    /// `validate` found this many errors.
+    Invalid(usize),
+    GoalMissed {
+        total: TimeDelta,
+        goal: TimeDelta,
+    },
+}
+
+impl Error {
+    /// Exit status reported to the shell. Usage errors exit with 2 from clap.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::InFile { source, .. } => source.exit_code(),
+            Error::Parse(_) | Error::IncludeCycle | Error::InvalidSettings(_) => exit_code::PARSE,
+            Error::Io(_)
+            | Error::Store(store::Error::Io(_))
+            | Error::Sync(sync::Error::Io(_) | sync::Error::Store(store::Error::Io(_))) => {
+                exit_code::IO
+            }
+            Error::Processing(_)
+            | Error::MissingRate(_)
+            | Error::UnknownProfile(_)
+            | Error::Invalid(_) => exit_code::VALIDATION,
+            Error::GoalMissed { .. } => exit_code::GOAL_MISSED,
+            _ => exit_code::FAILURE,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::MissingFile => write!(
+                f,
+                "no record file given (pass it first, or use --file or LEARNING_RECORD_FILE)"
+            ),
+            Error::Io(err) => write!(f, "{}", err),
+            Error::InFile { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::IncludeCycle => write!(f, "include cycle"),
+            Error::InvalidSettings(kind) => write!(f, "{}", kind),
+            Error::Parse(err) => write!(f, "{}", err),
+            Error::Processing(err) => write!(f, "{:?}", err),
+            Error::Sync(err) => write!(f, "sync failed: {}", err),
+            Error::Store(err) => write!(f, "{}", err),
+            Error::NoRemote => write!(f, "no [sync] remote configured in the settings"),
+            Error::UnknownHelpTopic(topic) => write!(
+                f,
+                "no help topic or subcommand named `{}` (try `help syntax`)",
+                topic
+            ),
+            Error::MissingTemplate => write!(
+                f,
+                "the template format needs --template or report.template in the settings"
+            ),
+            Error::MissingGoal => write!(f, "no weekly goal in the settings"),
+            Error::MissingRecurring => write!(f, "no [[recurring]] sessions in the settings"),
+            Error::UnknownProfile(name) => write!(f, "no [profiles.{}] in the settings", name),
+            Error::MissingRate(tag) => write!(f, "no hourly rate for `{}` in [rates]", tag),
+            Error::Template(err) => write!(f, "template error: {}", err),
+            Error::Script(message) => write!(f, "script error: {}", message),
+            Error::UnknownExporter(name) => write!(
+                f,
+                "no exporter `{}`: {}{} is not on PATH",
+                name,
+                crate::plugin::EXPORTER_PREFIX,
+                name
+            ),
+            Error::ExporterFailed {
+                program,
+                code: Some(code),
+            } => write!(f, "{} exited with status {}", program, code),
+            Error::ExporterFailed {
+                program,
+                code: None,
+            } => write!(f, "{} was killed by a signal", program),
+            #[cfg(feature = "arrow")]
+            Error::Parquet(message) => write!(f, "could not write Parquet: {}", message),
+            Error::Serve(message) => write!(f, "cannot serve: {}", message),
+            Error::Import(message) => write!(f, "import failed: {}", message),
+            Error::Chart(message) => write!(f, "could not draw the chart: {}", message),
+            Error::Notify(message) => write!(f, "could not show a notification: {}", message),
+            Error::NoMatch => write!(f, "no matching events"),
+            Error::Split(message) => write!(f, "cannot split: {}", message),
+            Error::Differ => write!(f, "the files differ"),
+            Error::Unhealthy(count) => write!(
+                f,
+                "{} problem{} found",
+                count,
+                if *count == 1 { "" } else { "s" }
+            ),
+            Error::Invalid(count) => write!(
+                f,
+                "{} invalid entr{}",
+                count,
+                if *count == 1 { "y" } else { "ies" }
+            ),
+            Error::GoalMissed { total, goal } => write!(
+                f,
+                "weekly goal missed: {} of {}",
+                format::duration(*total),
+                format::duration(*goal)
+            ),
+        }
+    }
+}
+
+impl From<handlebars::RenderError> for Error {
+    fn from(value: handlebars::RenderError) -> Self {
+        Self::Template(Box::new(value))
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(value: ParseError) -> Self {
+        Self::Parse(value)
+    }
+}
+
+impl From<processing::Error> for Error {
+    fn from(value: processing::Error) -> Self {
+        Self::Processing(value)
+    }
+}
+
+impl From<sync::Error> for Error {
+    fn from(value: sync::Error) -> Self {
+        Self::Sync(value)
+    }
+}
+
+impl From<store::Error> for Error {
+    fn from(value: store::Error) -> Self {
+        Self::Store(value)
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString, NulError},
+    fs, ptr,
+};
+
+use chrono::NaiveDateTime;
+
+use crate::{
+    ast,
+    parser::Parser,
+    processing::{self, Filter},
+};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn fail(message: impl Into<String>) {
+    let message = CString::new(message.into().replace('\0', " ")).expect("NULs are replaced");
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// A parsed record file, freed with `lr_file_free`.
+pub struct LrFile(ast::File);
+
+/// Totals of one week, freed with `lr_report_free`.
+pub struct LrReport {
+    total_minutes: i64,
+    tags: Vec<(CString, i64)>,
+}
+
+/// Message of the last failure on this thread, or NULL. Functions returning
+/// NULL or -1 on failure leave one. Valid until the next failure.
+#[no_mangle]
+pub extern "C" fn lr_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |err| err.as_ptr())
+    })
+}
+
+/// Reads and parses the record file at `path`, NULL on failure. `@include`
+/// directives are not followed.
+///
+/// # Safety
+///
+/// `path` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn lr_parse_file(path: *const c_char) -> *mut LrFile {
+    if path.is_null() {
+        fail("path is NULL");
+        return ptr::null_mut();
+    }
+    let path = CStr::from_ptr(path).to_string_lossy().into_owned();
+    let source = match fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(err) => {
+            fail(format!("{path}: {err}"));
+            return ptr::null_mut();
+        }
+    };
+
+    match Parser::new(source.chars().collect()).parse_file() {
+        Ok(mut file) => {
+            processing::normalize_tags(&mut file);
+            Box::into_raw(Box::new(LrFile(file)))
+        }
+        Err(err) => {
+            fail(format!("{path}:{err}"));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+///
+/// `file` must come from `lr_parse_file` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn lr_file_free(file: *mut LrFile) {
+    if !file.is_null() {
+        drop(Box::from_raw(file));
+    }
+}
+
+/// Totals of the week containing `today`, a date and time such as
+/// `2024-09-15T21:00:00`, or NULL on failure.
+///
+/// # Safety
+///
+/// `file` must come from `lr_parse_file` and `today` must be a valid
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn lr_weekly_report(
+    file: *const LrFile,
+    today: *const c_char,
+) -> *mut LrReport {
+    let (Some(LrFile(file)), false) = (file.as_ref(), today.is_null()) else {
+        fail("file or today is NULL");
+        return ptr::null_mut();
+    };
+    let today = CStr::from_ptr(today).to_string_lossy();
+    let Ok(today) = today.parse::<NaiveDateTime>() else {
+        fail(format!("`{today}` is not a date and time"));
+        return ptr::null_mut();
+    };
+
+    let filter = Filter::default().ignoring(file.settings_at(today.date()));
+    let report = match processing::calc_weekly_records(file, today, &filter) {
+        Ok(report) => report,
+        Err(err) => {
+            fail(format!("{err:?}"));
+            return ptr::null_mut();
+        }
+    };
+
+    let tags = report
+        .tags
+        .iter()
+        .map(|(tag, duration)| Ok((CString::new(tag.as_str())?, duration.num_minutes())))
+        .collect::<Result<_, NulError>>();
+    let Ok(tags) = tags else {
+        fail("a tag contains a NUL character");
+        return ptr::null_mut();
+    };
+
+    Box::into_raw(Box::new(LrReport {
+        total_minutes: report.total.num_minutes(),
+        tags,
+    }))
+}
+
+/// # Safety
+///
+/// `report` must come from `lr_weekly_report` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn lr_report_free(report: *mut LrReport) {
+    if !report.is_null() {
+        drop(Box::from_raw(report));
+    }
+}
+
+/// Total minutes of the week, -1 if `report` is NULL.
+///
+/// # Safety
+///
+/// `report` must come from `lr_weekly_report`.
+#[no_mangle]
+pub unsafe extern "C" fn lr_report_total_minutes(report: *const LrReport) -> i64 {
+    report.as_ref().map_or(-1, |report| report.total_minutes)
+}
+
+/// Number of tags of the week, in order of first appearance.
+///
+/// # Safety
+///
+/// `report` must come from `lr_weekly_report`.
+#[no_mangle]
+pub unsafe extern "C" fn lr_report_tag_count(report: *const LrReport) -> usize {
+    report.as_ref().map_or(0, |report| report.tags.len())
+}
+
+/// Name of the tag at `index`, or NULL when out of range. Valid as long as
+/// `report`.
+///
+/// # Safety
+///
+/// `report` must come from `lr_weekly_report`.
+#[no_mangle]
+pub unsafe extern "C" fn lr_report_tag_name(
+    report: *const LrReport,
+    index: usize,
+) -> *const c_char {
+    report
+        .as_ref()
+        .and_then(|report| report.tags.get(index))
+        .map_or(ptr::null(), |(tag, _)| tag.as_ptr())
+}
+
+/// Minutes of the tag at `index`, or -1 when out of range.
+///
+/// # Safety
+///
+/// `report` must come from `lr_weekly_report`.
+#[no_mangle]
+pub unsafe extern "C" fn lr_report_tag_minutes(report: *const LrReport, index: usize) -> i64 {
+    report
+        .as_ref()
+        .and_then(|report| report.tags.get(index))
+        .map_or(-1, |(_, minutes)| *minutes)
+}
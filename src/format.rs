@@ -0,0 +1,81 @@
+use chrono::{NaiveTime, TimeDelta, Timelike as _};
+
+/// How durations are shown in output, set with `[display] duration_format`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    serde::Deserialize,
+    serde::Serialize,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum DurationFormat {
+    /// `1h30m`
+    #[default]
+    Units,
+    /// `1:30`
+    Clock,
+    /// `90m`
+    Minutes,
+    /// `1.5h`
+    Decimal,
+}
+
+/// Formats a start time the way it is written in record files: `21:00`,
+/// or `21:00:30` when it has seconds.
+pub fn time(time: NaiveTime) -> String {
+    if time.second() == 0 {
+        time.format("%H:%M").to_string()
+    } else {
+        time.format("%H:%M:%S").to_string()
+    }
+}
+
+/// Formats a duration the way it is written in record files, e.g. `1h30m`.
+pub fn duration(delta: TimeDelta) -> String {
+    duration_with_units(delta, ["h", "m", "s"])
+}
+
+/// Formats a duration with the given hour, minute and second suffixes,
+/// leaving out zero components.
+pub fn duration_with_units(delta: TimeDelta, [hours, minutes, seconds]: [&str; 3]) -> String {
+    let sign = if delta < TimeDelta::zero() { "-" } else { "" };
+    let total = delta.num_seconds().abs();
+    let (h, m, s) = (total / 3600, total / 60 % 60, total % 60);
+
+    let mut result = String::from(sign);
+    if h > 0 {
+        result += &format!("{h}{hours}");
+    }
+    if m > 0 || (h == 0 && s == 0) {
+        result += &format!("{m}{minutes}");
+    }
+    if s > 0 {
+        result += &format!("{s}{seconds}");
+    }
+
+    result
+}
+
+/// Formats a duration for output. `units` are the hour, minute and second
+/// suffixes of the locale.
+pub fn display(delta: TimeDelta, format: DurationFormat, units: [&str; 3]) -> String {
+    let sign = if delta < TimeDelta::zero() { "-" } else { "" };
+    let minutes = delta.num_minutes().abs();
+
+    match format {
+        DurationFormat::Units => duration_with_units(delta, units),
+        DurationFormat::Clock => format!("{sign}{}:{:02}", minutes / 60, minutes % 60),
+        DurationFormat::Minutes => format!("{sign}{minutes}{}", units[1]),
+        DurationFormat::Decimal => {
+            let hours = delta.num_seconds().abs() as f64 / 3600.0;
+            let hours = format!("{hours:.2}");
+            let hours = hours.trim_end_matches('0').trim_end_matches('.');
+            format!("{sign}{hours}{}", units[0])
+        }
+    }
+}
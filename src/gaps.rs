@@ -0,0 +1,61 @@
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+
+use crate::{ast, processing::Filter, search::DateRange};
+
+/// Idle time between two consecutive sessions of a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    pub date: NaiveDate,
+    /// End of the earlier session.
+    pub start: NaiveDateTime,
+    /// Start of the later one.
+    pub end: NaiveDateTime,
+}
+
+impl Gap {
+    pub fn length(&self) -> TimeDelta {
+        self.end - self.start
+    }
+}
+
+/// Gaps between the sessions of each day record in `range`, in file order.
+/// Overlapping sessions leave no gap.
+pub fn gaps(file: &ast::File, range: DateRange, filter: &Filter) -> Vec<Gap> {
+    let mut gaps = Vec::new();
+    for record in file
+        .records
+        .iter()
+        .filter(|record| range.contains(record.date))
+    {
+        let mut sessions: Vec<(NaiveDateTime, NaiveDateTime)> = record
+            .events
+            .iter()
+            .flat_map(|event| {
+                event
+                    .info
+                    .iter()
+                    .filter(|info| filter.matches_session(record, event, info))
+            })
+            .filter_map(|info| {
+                let start = record.date.and_time(info.time?);
+                Some((start, start + info.duration))
+            })
+            .collect();
+        sessions.sort();
+
+        let mut busy_until = None;
+        for (start, end) in sessions {
+            match busy_until {
+                Some(until) if start > until => gaps.push(Gap {
+                    date: record.date,
+                    start: until,
+                    end: start,
+                }),
+                _ => {}
+            }
+            busy_until = Some(busy_until.map_or(end, |until: NaiveDateTime| until.max(end)));
+        }
+    }
+
+    gaps
+}
@@ -0,0 +1,171 @@
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+use chrono_tz::Tz;
+
+use crate::import::{self, Entry};
+
+/// Downloads the iCalendar feed at `url`; `webcal://` is fetched over HTTPS.
+pub fn fetch(url: &str) -> Result<String, String> {
+    let url = match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{rest}"),
+        None => url.to_string(),
+    };
+    ureq::get(&url)
+        .call()
+        .map_err(|err| err.to_string())?
+        .into_string()
+        .map_err(|err| err.to_string())
+}
+
+/// A timed `VEVENT`, its start in the wall-clock time of the record file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Event {
+    summary: String,
+    start: NaiveDateTime,
+    duration: TimeDelta,
+}
+
+/// Events of the feed whose summary contains `pattern`, ignoring case, as
+/// entries tagged `tag`, or their summary without one. All-day and
+/// cancelled events are left out, and recurring events are only taken at
+/// their first occurrence.
+pub fn entries(ics: &str, pattern: &str, tag: Option<&str>, timezone: Option<Tz>) -> Vec<Entry> {
+    let pattern = pattern.to_lowercase();
+    events(ics, timezone)
+        .into_iter()
+        .filter(|event| event.summary.to_lowercase().contains(&pattern))
+        .filter_map(|event| {
+            let title = import::tag(tag.unwrap_or(&event.summary));
+            (!title.is_empty()).then(|| Entry {
+                date: event.start.date(),
+                time: event.start.time(),
+                duration: event.duration,
+                tags: vec![(title, None)],
+            })
+        })
+        .collect()
+}
+
+fn events(ics: &str, timezone: Option<Tz>) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut current: Option<Vec<(String, String)>> = None;
+    for line in unfold(ics) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        match (name, value) {
+            ("BEGIN", "VEVENT") => current = Some(Vec::new()),
+            ("END", "VEVENT") => {
+                if let Some(event) = current.take().and_then(|props| event(&props, timezone)) {
+                    events.push(event);
+                }
+            }
+            _ => {
+                if let Some(props) = &mut current {
+                    props.push((name.to_string(), value.to_string()));
+                }
+            }
+        }
+    }
+
+    events
+}
+
+/// Joins folded lines, which continue with a space or a tab.
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    lines
+}
+
+fn event(props: &[(String, String)], timezone: Option<Tz>) -> Option<Event> {
+    let prop = |name: &str| {
+        props.iter().find(|(key, _)| {
+            key.split(';')
+                .next()
+                .is_some_and(|key| key.eq_ignore_ascii_case(name))
+        })
+    };
+    if prop("STATUS").is_some_and(|(_, status)| status.eq_ignore_ascii_case("CANCELLED")) {
+        return None;
+    }
+
+    let (start_key, start) = prop("DTSTART")?;
+    let start = datetime(start_key, start, timezone)?;
+    let duration = match prop("DTEND") {
+        Some((key, end)) => datetime(key, end, timezone)? - start,
+        None => duration(&prop("DURATION")?.1)?,
+    };
+    let summary = prop("SUMMARY").map(|(_, summary)| unescape(summary))?;
+
+    (duration > TimeDelta::zero()).then_some(Event {
+        summary,
+        start,
+        duration,
+    })
+}
+
+/// A `DTSTART` or `DTEND` in wall-clock time of `timezone`, `None` for
+/// all-day dates. Times without a zone are taken as they are.
+fn datetime(key: &str, value: &str, timezone: Option<Tz>) -> Option<NaiveDateTime> {
+    if NaiveDate::parse_from_str(value, "%Y%m%d").is_ok() {
+        return None;
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let utc = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(import::local(utc.and_utc(), timezone));
+    }
+    let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let zone = key
+        .split(';')
+        .find_map(|param| param.strip_prefix("TZID="))
+        .and_then(|zone| zone.trim_matches('"').parse::<Tz>().ok());
+    match zone {
+        Some(zone) => {
+            let time = local.and_local_timezone(zone).earliest()?;
+            Some(import::local(time, timezone))
+        }
+        None => Some(local),
+    }
+}
+
+/// An iCalendar duration such as `PT1H30M` or `P1D`.
+fn duration(value: &str) -> Option<TimeDelta> {
+    let value = value.strip_prefix('P')?;
+    let mut total = TimeDelta::zero();
+    let mut number = String::new();
+    let mut time = false;
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => time = true,
+            _ => {
+                let amount: i64 = std::mem::take(&mut number).parse().ok()?;
+                total += match (c, time) {
+                    ('W', false) => TimeDelta::try_weeks(amount)?,
+                    ('D', false) => TimeDelta::try_days(amount)?,
+                    ('H', true) => TimeDelta::try_hours(amount)?,
+                    ('M', true) => TimeDelta::try_minutes(amount)?,
+                    ('S', true) => TimeDelta::try_seconds(amount)?,
+                    _ => return None,
+                };
+            }
+        }
+    }
+
+    Some(total)
+}
+
+/// Undoes the escaping of iCalendar text values.
+fn unescape(text: &str) -> String {
+    text.replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
@@ -0,0 +1,183 @@
+use chrono::Weekday;
+
+/// Language of the human readable output, set with `locale = "ja"`.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    serde::Deserialize,
+    serde::Serialize,
+    schemars::JsonSchema,
+)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "ja")]
+    Japanese,
+}
+
+impl Locale {
+    pub fn weekday(self, weekday: Weekday) -> &'static str {
+        let index = weekday.num_days_from_monday() as usize;
+        match self {
+            Locale::English => ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"][index],
+            Locale::Japanese => ["月", "火", "水", "木", "金", "土", "日"][index],
+        }
+    }
+
+    pub fn weekday_long(self, weekday: Weekday) -> &'static str {
+        let index = weekday.num_days_from_monday() as usize;
+        match self {
+            Locale::English => [
+                "Monday",
+                "Tuesday",
+                "Wednesday",
+                "Thursday",
+                "Friday",
+                "Saturday",
+                "Sunday",
+            ][index],
+            Locale::Japanese => [
+                "月曜日",
+                "火曜日",
+                "水曜日",
+                "木曜日",
+                "金曜日",
+                "土曜日",
+                "日曜日",
+            ][index],
+        }
+    }
+
+    /// Default output format of dates; `%a` is the localized weekday.
+    pub fn date_format(self) -> &'static str {
+        match self {
+            Locale::English => "%a %Y-%m-%d",
+            Locale::Japanese => "%Y-%m-%d(%a)",
+        }
+    }
+
+    /// Heading of a weekly report, given the formatted start date and time.
+    pub fn week_from(self, date: &str, time: &str) -> String {
+        match self {
+            Locale::English => format!("Week from {} {}", date, time),
+            Locale::Japanese => format!("{} {} からの週", date, time),
+        }
+    }
+
+    /// Heading of a period other than a week, ending on `last`.
+    pub fn period(self, date: &str, time: &str, last: &str) -> String {
+        match self {
+            Locale::English => format!("{} {} – {}", date, time, last),
+            Locale::Japanese => format!("{} {} 〜 {}", date, time, last),
+        }
+    }
+
+    /// Label of the mean total of the last `count` weeks, or periods of
+    /// another length.
+    pub fn average(self, count: usize, weeks: bool) -> String {
+        match self {
+            Locale::English if weeks => format!("{}-week average", count),
+            Locale::English => format!("{}-period average", count),
+            Locale::Japanese if weeks => format!("{}週平均", count),
+            Locale::Japanese => format!("{}期間平均", count),
+        }
+    }
+
+    pub fn total(self) -> &'static str {
+        match self {
+            Locale::English => "Total",
+            Locale::Japanese => "合計",
+        }
+    }
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::English => "Tag",
+            Locale::Japanese => "タグ",
+        }
+    }
+
+    pub fn time(self) -> &'static str {
+        match self {
+            Locale::English => "Time",
+            Locale::Japanese => "時間",
+        }
+    }
+
+    /// Hour, minute and second suffixes, e.g. for `1h30m` or `1時間30分`.
+    pub fn day(self) -> &'static str {
+        match self {
+            Locale::English => "Day",
+            Locale::Japanese => "日付",
+        }
+    }
+
+    pub fn pomodoros(self, count: u32) -> String {
+        match self {
+            Locale::English if count == 1 => "1 pomodoro".to_string(),
+            Locale::English => format!("{} pomodoros", count),
+            Locale::Japanese => format!("{}ポモドーロ", count),
+        }
+    }
+
+    pub fn corrections(self) -> &'static str {
+        match self {
+            Locale::English => "Corrections",
+            Locale::Japanese => "修正",
+        }
+    }
+
+    pub fn plan(self) -> &'static str {
+        match self {
+            Locale::English => "Plan",
+            Locale::Japanese => "予定",
+        }
+    }
+
+    pub fn variance(self) -> &'static str {
+        match self {
+            Locale::English => "Variance",
+            Locale::Japanese => "差",
+        }
+    }
+
+    pub fn left(self) -> &'static str {
+        match self {
+            Locale::English => "Left",
+            Locale::Japanese => "残り",
+        }
+    }
+
+    pub fn ideal(self) -> &'static str {
+        match self {
+            Locale::English => "Ideal",
+            Locale::Japanese => "理想",
+        }
+    }
+
+    pub fn earnings(self) -> &'static str {
+        match self {
+            Locale::English => "Earnings",
+            Locale::Japanese => "収入",
+        }
+    }
+
+    pub fn this_month(self) -> &'static str {
+        match self {
+            Locale::English => "this month",
+            Locale::Japanese => "今月",
+        }
+    }
+
+    pub fn duration_units(self) -> [&'static str; 3] {
+        match self {
+            Locale::English => ["h", "m", "s"],
+            Locale::Japanese => ["時間", "分", "秒"],
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{
+    DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Timelike as _,
+};
+use chrono_tz::Tz;
+
+use crate::{ast, format, parser, settings::Duration};
+
+/// A session read from another tool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub date: NaiveDate,
+    pub time: NaiveTime,
+    pub duration: TimeDelta,
+    /// Title and detail of each tag, written as they can be in a record
+    /// file (see [`tag`] and [`detail`]).
+    pub tags: Vec<(String, Option<String>)>,
+}
+
+impl Entry {
+    /// The entry as an event line such as `[rust(book)] 21:00 - 1h`.
+    pub fn line(&self) -> String {
+        let mut line = String::new();
+        if !self.tags.is_empty() {
+            let tags: Vec<String> = self
+                .tags
+                .iter()
+                .map(|(title, detail)| match detail {
+                    Some(detail) => format!("{title}({detail})"),
+                    None => title.clone(),
+                })
+                .collect();
+            line += &format!("[{}] ", tags.join(" "));
+        }
+        line += &format!(
+            "{} - {}",
+            format::time(self.time),
+            format::duration(self.duration)
+        );
+
+        line
+    }
+}
+
+/// Outcome of importing entries into a record file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Import {
+    pub content: String,
+    /// Entries added, by date and start.
+    pub added: Vec<Entry>,
+    /// Entries starting when a session was already recorded.
+    pub skipped: usize,
+}
+
+/// Turns `text` into a tag title: whitespace and the brackets that end a
+/// tag become hyphens.
+pub fn tag(text: &str) -> String {
+    let tag: String = text
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_whitespace() || matches!(c, '[' | ']' | '(' | ')') {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+    tag.trim_matches('-').to_string()
+}
+
+/// Turns `text` into a tag detail on one line, with parentheses, which
+/// would end it, turned into square brackets. `None` if nothing is left.
+pub fn detail(text: &str) -> Option<String> {
+    let detail = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('(', "[")
+        .replace(')', "]");
+    (!detail.is_empty()).then_some(detail)
+}
+
+/// `time` as wall-clock time in `timezone`, or in the system time zone.
+pub fn local<Z: TimeZone>(time: DateTime<Z>, timezone: Option<Tz>) -> NaiveDateTime {
+    match timezone {
+        Some(timezone) => time.with_timezone(&timezone).naive_local(),
+        None => time.with_timezone(&Local).naive_local(),
+    }
+}
+
+/// A session of the intermediate JSON format described by `help
+/// import-json`.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonEntry {
+    start: NaiveDateTime,
+    #[serde(default)]
+    duration: Option<Duration>,
+    #[serde(default)]
+    end: Option<NaiveDateTime>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    detail: Option<String>,
+}
+
+/// Reads an array of sessions in the intermediate JSON format.
+pub fn json(source: &str) -> Result<Vec<Entry>, String> {
+    let entries: Vec<JsonEntry> = serde_json::from_str(source).map_err(|err| err.to_string())?;
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let duration = match (entry.duration, entry.end) {
+                (Some(duration), _) => duration.0,
+                (None, Some(end)) => end - entry.start,
+                (None, None) => {
+                    return Err(format!(
+                        "entry {} has neither `duration` nor `end`",
+                        index + 1
+                    ))
+                }
+            };
+            Ok(self::entry(
+                entry.start,
+                duration,
+                &entry.tags,
+                entry.detail.as_deref(),
+            ))
+        })
+        .filter(|entry| {
+            !entry
+                .as_ref()
+                .is_ok_and(|entry| entry.duration <= TimeDelta::zero())
+        })
+        .collect()
+}
+
+/// A session logged remotely, by `POST /events` or the `add_event` tool of
+/// `mcp`.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NewEvent {
+    /// Wall-clock start; `duration` before now when absent, for logging a
+    /// session just finished.
+    #[serde(default)]
+    pub start: Option<NaiveDateTime>,
+    pub duration: Duration,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+impl NewEvent {
+    pub fn entry(&self, now: NaiveDateTime) -> Result<Entry, String> {
+        let duration = self.duration.0;
+        if duration <= TimeDelta::zero() {
+            return Err("the duration must be positive".to_string());
+        }
+        // Without a start, to the minute, as if typed in.
+        let start = match self.start {
+            Some(start) => start,
+            None => (now - duration)
+                .with_second(0)
+                .and_then(|start| start.with_nanosecond(0))
+                .unwrap_or(now - duration),
+        };
+
+        Ok(entry(start, duration, &self.tags, self.detail.as_deref()))
+    }
+}
+
+/// An entry tagged `tags`, turned into tag titles, with `detail` on the
+/// first one.
+pub fn entry(
+    start: NaiveDateTime,
+    duration: TimeDelta,
+    tags: &[String],
+    detail: Option<&str>,
+) -> Entry {
+    let mut tags: Vec<(String, Option<String>)> = tags
+        .iter()
+        .map(|title| (tag(title), None))
+        .filter(|(title, _)| !title.is_empty())
+        .collect();
+    if let Some(first) = tags.first_mut() {
+        first.1 = detail.and_then(self::detail);
+    }
+
+    Entry {
+        date: start.date(),
+        time: start.time().with_nanosecond(0).unwrap_or(start.time()),
+        duration,
+        tags,
+    }
+}
+
+/// Adds `entries` to `source`, which `file` was parsed from: after the last
+/// event of the day record of their date, or in new day records after the
+/// last one.
+///
+/// Entries starting at the same time as a session already recorded that
+/// day, or as an earlier entry, are skipped.
+pub fn insert(source: &str, file: &ast::File, entries: &[Entry]) -> Import {
+    let mut starts: BTreeSet<(NaiveDate, NaiveTime)> = file
+        .records
+        .iter()
+        .flat_map(|record| {
+            record
+                .events
+                .iter()
+                .flat_map(|event| &event.info)
+                .filter_map(|info| Some((record.date, info.time?)))
+        })
+        .collect();
+    let mut entries: Vec<&Entry> = entries.iter().collect();
+    entries.sort_by_key(|entry| (entry.date, entry.time));
+    let mut added = Vec::new();
+    let mut skipped = 0;
+    for entry in entries {
+        if !starts.insert((entry.date, entry.time)) {
+            skipped += 1;
+            continue;
+        }
+        added.push(entry.clone());
+    }
+    let lines: Vec<(NaiveDate, String)> = added
+        .iter()
+        .map(|entry| (entry.date, entry.line()))
+        .collect();
+    let content = insert_lines(source, file, &lines);
+
+    Import {
+        content,
+        added,
+        skipped,
+    }
+}
+
+/// Adds event `lines` to `source`, which `file` was parsed from: after the
+/// last event of the day record of their date, or in new day records after
+/// the last one, keeping their order within a day.
+pub fn insert_lines(source: &str, file: &ast::File, lines: &[(NaiveDate, String)]) -> String {
+    // Line after which to insert events of each day, numbered from 1.
+    let anchors: BTreeMap<NaiveDate, usize> = file
+        .records
+        .iter()
+        .filter(|record| record.origin.is_none())
+        .map(|record| {
+            let last = record.events.iter().map(|event| event.line).max();
+            (record.date, last.unwrap_or(record.line))
+        })
+        .collect();
+
+    let mut after_line: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    let mut new_days: BTreeMap<NaiveDate, Vec<String>> = BTreeMap::new();
+    for (date, line) in lines {
+        match anchors.get(date) {
+            Some(anchor) => after_line.entry(*anchor).or_default().push(line.clone()),
+            None => new_days.entry(*date).or_default().push(line.clone()),
+        }
+    }
+
+    // A new day goes after the last day record before it, or before the
+    // first one after it, so that dates stay in order. In Markdown both are
+    // inside a fenced block; without any record it goes at the end of the
+    // last block, or of the file.
+    let headers: BTreeMap<NaiveDate, usize> = file
+        .records
+        .iter()
+        .filter(|record| record.origin.is_none())
+        .map(|record| (record.date, record.line))
+        .collect();
+    let mut days = Vec::new();
+    for (date, events) in new_days {
+        let mut day = vec![date.format("%Y-%m-%d").to_string()];
+        day.extend(events);
+        if let Some((_, anchor)) = anchors.range(..date).next_back() {
+            day.insert(0, String::new());
+            after_line.entry(*anchor).or_default().extend(day);
+        } else if let Some((_, header)) = headers.range(date..).next() {
+            day.push(String::new());
+            after_line.entry(header - 1).or_default().extend(day);
+        } else {
+            if !days.is_empty() {
+                days.push(String::new());
+            }
+            days.extend(day);
+        }
+    }
+
+    if let Some(end) = parser::markdown_block_end(source).filter(|_| !days.is_empty()) {
+        let previous = source.lines().nth(end - 2).unwrap_or_default().trim();
+        if !previous.is_empty() && !previous.starts_with(['`', '~']) {
+            days.insert(0, String::new());
+        }
+        after_line.entry(end - 1).or_default().append(&mut days);
+    }
+
+    let mut content = String::new();
+    for event in after_line.get(&0).into_iter().flatten() {
+        content += event;
+        content.push('\n');
+    }
+    for (index, line) in source.lines().enumerate() {
+        content += line;
+        content.push('\n');
+        for event in after_line.get(&(index + 1)).into_iter().flatten() {
+            content += event;
+            content.push('\n');
+        }
+    }
+    if !source.ends_with('\n') && !source.is_empty() && days.is_empty() {
+        content.pop();
+    }
+    if !days.is_empty() && !content.is_empty() {
+        days.insert(0, String::new());
+    }
+    for line in days {
+        content += &line;
+        content.push('\n');
+    }
+
+    content
+}
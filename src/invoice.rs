@@ -0,0 +1,238 @@
+use std::fmt::Write as _;
+
+use chrono::{NaiveDate, TimeDelta};
+
+use crate::{
+    ast,
+    processing::{round, Filter},
+    render::Renderer,
+    settings::{Rounding, RoundingScope},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum InvoiceFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+/// Time billed to a client tag, one item per day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Invoice {
+    pub tag: String,
+    pub from: NaiveDate,
+    /// Inclusive last day.
+    pub to: NaiveDate,
+    pub rate: f64,
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub date: NaiveDate,
+    /// Details of the client tag and the other tags of the day's events.
+    pub description: String,
+    pub duration: TimeDelta,
+}
+
+impl Invoice {
+    /// Collects the sessions tagged `tag` between `from` and `to`, both
+    /// inclusive, that pass `filter`, applying the rounding of the settings.
+    pub fn build(
+        file: &ast::File,
+        tag: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        rate: f64,
+        filter: &Filter,
+    ) -> Self {
+        let mut items = Vec::new();
+        for record in &file.records {
+            if record.date < from || record.date > to {
+                continue;
+            }
+
+            let rounding = file
+                .settings_at(record.date)
+                .and_then(|settings| settings.rounding);
+            let mut duration = TimeDelta::zero();
+            let mut notes: Vec<String> = Vec::new();
+            for event in &record.events {
+                let tags = event.tags.iter().flat_map(|tags| &tags.tags);
+                if !tags.clone().any(|t| t.title == tag) {
+                    continue;
+                }
+
+                let mut billed = false;
+                for info in &event.info {
+                    if !filter.matches_session(record, event, info) {
+                        continue;
+                    }
+                    billed = true;
+                    duration += match rounding {
+                        Some(rounding) if rounding.scope == RoundingScope::Session => {
+                            round(info.duration, rounding)
+                        }
+                        _ => info.duration,
+                    };
+                }
+                if !billed {
+                    continue;
+                }
+                for note in tags.filter_map(|t| describe(t, tag)) {
+                    if !notes.contains(&note) {
+                        notes.push(note);
+                    }
+                }
+            }
+
+            if duration.is_zero() {
+                continue;
+            }
+            if let Some(rounding) = rounding.filter(|r: &Rounding| r.scope == RoundingScope::Day) {
+                duration = round(duration, rounding);
+            }
+            items.push(Item {
+                date: record.date,
+                description: notes.join(", "),
+                duration,
+            });
+        }
+        items.sort_by_key(|item| item.date);
+
+        Self {
+            tag: tag.to_string(),
+            from,
+            to,
+            rate,
+            items,
+        }
+    }
+
+    pub fn total(&self) -> TimeDelta {
+        self.items.iter().map(|item| item.duration).sum()
+    }
+
+    pub fn amount(&self, duration: TimeDelta) -> f64 {
+        hours(duration) * self.rate
+    }
+
+    pub fn render(&self, format: InvoiceFormat, renderer: &Renderer) -> String {
+        match format {
+            InvoiceFormat::Markdown => self.markdown(renderer),
+            InvoiceFormat::Html => self.html(renderer),
+        }
+    }
+
+    fn markdown(&self, renderer: &Renderer) -> String {
+        let mut out = String::new();
+
+        writeln!(out, "# Invoice: {}", self.tag).unwrap();
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "{} – {}",
+            renderer.date(self.from),
+            renderer.date(self.to)
+        )
+        .unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "| Date | Description | Hours | Rate | Amount |").unwrap();
+        writeln!(out, "| --- | --- | ---: | ---: | ---: |").unwrap();
+        for item in &self.items {
+            writeln!(
+                out,
+                "| {} | {} | {:.2} | {} | {} |",
+                renderer.date(item.date),
+                item.description,
+                hours(item.duration),
+                renderer.money(self.rate),
+                renderer.money(self.amount(item.duration))
+            )
+            .unwrap();
+        }
+        writeln!(out).unwrap();
+        write!(
+            out,
+            "**Total:** {:.2} h, {}",
+            hours(self.total()),
+            renderer.money(self.amount(self.total()))
+        )
+        .unwrap();
+
+        out
+    }
+
+    fn html(&self, renderer: &Renderer) -> String {
+        let mut out = String::new();
+
+        writeln!(out, "<!DOCTYPE html>").unwrap();
+        writeln!(out, "<html>").unwrap();
+        writeln!(
+            out,
+            "<head><meta charset=\"utf-8\"><title>Invoice: {}</title></head>",
+            escape(&self.tag)
+        )
+        .unwrap();
+        writeln!(out, "<body>").unwrap();
+        writeln!(out, "<h1>Invoice: {}</h1>", escape(&self.tag)).unwrap();
+        writeln!(
+            out,
+            "<p>{} – {}</p>",
+            escape(&renderer.date(self.from)),
+            escape(&renderer.date(self.to))
+        )
+        .unwrap();
+        writeln!(out, "<table>").unwrap();
+        writeln!(
+            out,
+            "<tr><th>Date</th><th>Description</th><th>Hours</th><th>Rate</th><th>Amount</th></tr>"
+        )
+        .unwrap();
+        for item in &self.items {
+            writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{:.2}</td><td>{}</td><td>{}</td></tr>",
+                escape(&renderer.date(item.date)),
+                escape(&item.description),
+                hours(item.duration),
+                escape(&renderer.money(self.rate)),
+                escape(&renderer.money(self.amount(item.duration)))
+            )
+            .unwrap();
+        }
+        writeln!(
+            out,
+            "<tr><th colspan=\"2\">Total</th><th>{:.2}</th><th></th><th>{}</th></tr>",
+            hours(self.total()),
+            escape(&renderer.money(self.amount(self.total())))
+        )
+        .unwrap();
+        writeln!(out, "</table>").unwrap();
+        writeln!(out, "</body>").unwrap();
+        write!(out, "</html>").unwrap();
+
+        out
+    }
+}
+
+/// The detail of the client tag, or another tag with its detail.
+fn describe(tag: &ast::Tag, client: &str) -> Option<String> {
+    match (&tag.detail, tag.title == client) {
+        (Some(detail), true) => Some(detail.clone()),
+        (None, true) => None,
+        (Some(detail), false) => Some(format!("{}({})", tag.title, detail)),
+        (None, false) => Some(tag.title.clone()),
+    }
+}
+
+fn hours(duration: TimeDelta) -> f64 {
+    duration.num_seconds() as f64 / 3600.0
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,13 @@
+pub mod ast;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod format;
+pub mod i18n;
+pub mod parser;
+pub mod processing;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod query;
+pub mod settings;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -1,52 +1,2007 @@
-use std::fs;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, IsTerminal as _},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
-use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
-use clap::Parser as _;
-use parser::Parser;
-use processing::calc_weekly_records;
+use chart::ChartKind;
+use chrono::{Datelike as _, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use clap::{ColorChoice, CommandFactory as _, Parser as _};
+use error::Error;
+use invoice::{Invoice, InvoiceFormat};
+use learning_record::{ast, format, i18n, parser, processing, query, settings};
+use parser::{ParseErrorKind, Strictness};
+use pivot::Granularity;
+use processing::{calc_monthly_records, calc_weekly_records, Filter, PeriodReport, TimeWindow};
+use query::Query;
+use render::{Extras, Renderer, AVERAGE_WEEKS};
+use serde::Deserialize as _;
+use settings::{ReportFormat, Settings};
+use today::Today;
+use tracing::Level;
 
-mod ast;
-mod parser;
-mod processing;
-mod settings;
+mod badge;
+mod chart;
+mod clockify;
+#[cfg(feature = "arrow")]
+mod columnar;
+mod completions;
+mod dedupe;
+mod diff;
+mod doctor;
+mod error;
+mod gaps;
+mod gcal;
+mod import;
+mod invoice;
+mod manual;
+mod mcp;
+mod merge;
+mod migrate;
+mod org;
+mod overview;
+mod pager;
+#[cfg(feature = "pdf")]
+mod pdf;
+mod pivot;
+mod plugin;
+mod remind;
+mod render;
+mod retag;
+mod script;
+mod search;
+mod serve;
+mod split;
+mod stats;
+mod store;
+mod summary;
+mod sync;
+mod template;
+mod today;
+mod validate;
+mod xlsx;
+
+const EXIT_STATUS: &str = "\
+Exit status:
+  0  success
+  1  other failure
+  2  invalid command line
+  3  the record file could not be parsed
+  4  a file could not be read or written
+  5  the records are inconsistent
+  6  the weekly goal was missed (report --fail-under-goal)";
 
 #[derive(Debug, Clone, clap::Parser)]
+#[clap(
+    name = "learning-record",
+    disable_help_subcommand = true,
+    after_long_help = EXIT_STATUS
+)]
 struct Cli {
-    path: String,
-    #[clap(long)]
-    today: Option<NaiveDateTime>,
+    /// Record file to operate on
+    #[clap(short, long, global = true, env = "LEARNING_RECORD_FILE")]
+    file: Option<PathBuf>,
+    /// Pretend it is this day: 2024-09-15, 2024-09-15T21:00:00, yesterday,
+    /// -3d, +1w or a weekday such as monday
+    #[clap(long, global = true, allow_hyphen_values = true)]
+    today: Option<Today>,
+    /// Show more log output (-v info, -vv debug, -vvv trace)
+    #[clap(short, long, global = true, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+    /// Only log errors
+    #[clap(short, long, global = true)]
+    quiet: bool,
+    /// Output without colors, emoji or box drawing characters, e.g. for CI
+    /// logs and screen readers
+    #[clap(
+        long,
+        global = true,
+        env = "LEARNING_RECORD_PLAIN",
+        value_parser = clap::builder::FalseyValueParser::new()
+    )]
+    plain: bool,
+    /// When to use colors in the output
+    #[clap(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+    /// Read settings from this TOML or YAML file, merged over those in the
+    /// record file
+    #[clap(
+        long,
+        global = true,
+        env = "LEARNING_RECORD_SETTINGS",
+        value_name = "PATH"
+    )]
+    settings: Option<PathBuf>,
+    /// Ignore the settings blocks of the record file and use only --settings
+    #[clap(long, global = true, requires = "settings")]
+    replace_settings: bool,
+    /// Accept minor formatting issues, such as blank lines inside day
+    /// records or trailing spaces, with a warning for each
+    #[clap(long, global = true, conflicts_with = "strict")]
+    lenient: bool,
+    /// Reject minor formatting issues, such as trailing spaces or a
+    /// missing newline at the end, as parse errors
+    #[clap(long, global = true)]
+    strict: bool,
+    /// Use the week start, goal and tags of `[profiles.<NAME>]`
+    #[clap(
+        long,
+        global = true,
+        env = "LEARNING_RECORD_PROFILE",
+        value_name = "NAME"
+    )]
+    profile: Option<String>,
+    /// Print long listings directly instead of through `$PAGER`
+    #[clap(long, global = true)]
+    no_pager: bool,
+    /// Record file, as in `learning-record records.lr`; runs `report` when
+    /// no command is given
+    #[clap(value_name = "FILE", conflicts_with = "file")]
+    path: Option<PathBuf>,
+    #[clap(subcommand)]
+    command: Option<Command>,
 }
 
-fn main() {
-    let cli = Cli::parse();
+#[derive(Debug, Clone, clap::Subcommand)]
+enum Command {
+    /// Show the total learning time of the current week
+    Report {
+        /// Only count events with this tag (repeatable)
+        #[clap(long = "tag")]
+        tags: Vec<String>,
+        /// Only count sessions matching this query (see `help query`)
+        #[clap(long, value_name = "QUERY", allow_hyphen_values = true)]
+        filter: Option<Query>,
+        /// Only count sessions starting within a time of day, e.g.
+        /// `05:00..12:00`, or morning, afternoon, evening or night
+        #[clap(long, value_name = "WINDOW")]
+        between: Option<TimeWindow>,
+        /// Only count Monday to Friday
+        #[clap(long, conflicts_with_all = ["weekends", "days"])]
+        weekdays: bool,
+        /// Only count Saturday and Sunday
+        #[clap(long, conflicts_with = "days")]
+        weekends: bool,
+        /// Only count these days, e.g. `mon,wed,fri`
+        #[clap(long, value_delimiter = ',', value_name = "DAYS", value_parser = parse_weekday)]
+        days: Vec<Weekday>,
+        /// Count the events tagged with one of `ignore_tags` too
+        #[clap(long)]
+        include_ignored: bool,
+        /// Show a sparkline of the totals of this many weeks up to this one
+        #[clap(long, value_name = "WEEKS")]
+        trend: Option<usize>,
+        /// Report the whole year, starting in the `year_start` month
+        #[clap(long, conflicts_with_all = ["trend", "year_to_date", "commit_summary"])]
+        year: bool,
+        /// Report the year up to now, starting in the `year_start` month
+        #[clap(long, conflicts_with_all = ["trend", "commit_summary"])]
+        year_to_date: bool,
+        /// Exit with status 6 when the weekly goal has not been reached
+        #[clap(long)]
+        fail_under_goal: bool,
+        #[clap(long, value_enum)]
+        format: Option<ReportFormat>,
+        /// Render with this Handlebars template (implies --format template)
+        #[clap(long)]
+        template: Option<PathBuf>,
+        /// Write the report to this file; `-` is stdout
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+        /// Append a comment block summarizing the last completed week to the
+        /// record file, under its last day
+        #[clap(long)]
+        commit_summary: bool,
+    },
+    /// Dump the parsed syntax tree
+    Ast {
+        #[clap(long, value_enum, default_value_t = AstFormat::Debug)]
+        format: AstFormat,
+        /// Write the dump to this file; `-` is stdout
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Export the records in other formats
+    ///
+    /// `export FORMAT [ARGS]...` with any other format runs
+    /// `learning-record-export-FORMAT [ARGS]...` from PATH, giving it the
+    /// syntax tree as JSON (see `schema ast`) on its standard input.
+    Export {
+        #[clap(subcommand)]
+        target: Export,
+    },
+    /// Serve a dashboard at `/`, this week's report as JSON at `/report`
+    /// and accept sessions at `POST /events`
+    ///
+    /// `/days` lists the time of every day of the last 26 weeks.
+    /// A WebSocket connected to `/live` gets the report at once and again
+    /// whenever the record file changes.
+    ///
+    /// POST a body such as `{"duration": "45m", "tags": ["rust"]}` with
+    /// `Authorization: Bearer TOKEN`; `start` defaults to the duration
+    /// before now, and `detail` applies to the first tag.
+    Serve {
+        /// Address to listen on
+        #[clap(long, default_value = "127.0.0.1:8080")]
+        address: String,
+        /// Token that POST requests must send; without one they are refused
+        #[clap(long, env = "LEARNING_RECORD_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+    },
+    /// Answer Model Context Protocol requests on stdin/stdout, so that
+    /// assistants can read the weekly report and search events
+    Mcp {
+        /// Also offer the `add_event` tool, whose writes `undo` can revert
+        #[clap(long)]
+        allow_write: bool,
+    },
+    /// Remove events written twice on the same day with the same tags,
+    /// details and sessions, as left by a botched merge or import
+    Dedupe {
+        /// List the duplicates without changing the file
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Move the day records into a file per month or year, included from
+    /// the record file so that reports still see all of them. Comments in
+    /// day records are not kept.
+    Split {
+        #[clap(long, value_enum, default_value_t = split::Period::Month)]
+        by: split::Period,
+        /// Directory of the new files, relative to the record file; named
+        /// after the record file by default
+        #[clap(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Show the days and events in only one of two record files, or with
+    /// `--week` twice, how the time per tag changed from one week to another
+    Diff {
+        #[clap(
+            value_name = "FILE",
+            num_args = 2,
+            required_unless_present = "week",
+            conflicts_with = "week"
+        )]
+        files: Vec<PathBuf>,
+        /// Week of the record file to compare, by offset from this one: 0
+        /// is this week and -1 the last; give it twice
+        #[clap(long, allow_negative_numbers = true)]
+        week: Vec<i64>,
+    },
+    /// Combine two record files, e.g. kept on two machines without `sync`:
+    /// day records of either are kept and events of the same day merged.
+    /// Events of the second file starting when one of the first does are
+    /// reported as conflicts and left out.
+    Merge {
+        first: PathBuf,
+        second: PathBuf,
+        /// File to write; standard output by default
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Add sessions from other tools to the record file
+    Import {
+        #[clap(subcommand)]
+        source: ImportSource,
+    },
+    /// Push/pull the record file to the remote configured in `[sync]`
+    Sync {
+        /// Force a direction instead of detecting which side changed
+        #[clap(long, value_enum)]
+        direction: Option<sync::Direction>,
+    },
+    /// Check the environment and the record file for problems
+    Doctor,
+    /// Find events whose tags or details contain a word, or that match a
+    /// query such as `tag:rust dur>1h` (see `help query`)
+    Search {
+        #[clap(allow_hyphen_values = true)]
+        query: Query,
+        /// First day to search, YYYY-MM-DD
+        #[clap(long)]
+        from: Option<NaiveDate>,
+        /// Last day to search (inclusive), YYYY-MM-DD
+        #[clap(long)]
+        to: Option<NaiveDate>,
+        /// Lines of context to show around each event
+        #[clap(short = 'C', long, default_value_t = 0)]
+        context: usize,
+        /// Show at most this many events, the earliest first
+        #[clap(short = 'n', long, value_name = "N")]
+        limit: Option<usize>,
+    },
+    /// List the events newest first with their date, start, tags, time and
+    /// details
+    Log {
+        /// Only list events with this tag (repeatable)
+        #[clap(long = "tag")]
+        tags: Vec<String>,
+        /// List at most this many events
+        #[clap(short = 'n', long, value_name = "N")]
+        limit: Option<usize>,
+    },
+    /// List every tag with its number of events, total time, first and last
+    /// use and details
+    Tags {
+        #[clap(long, value_enum, default_value_t = TagSort::Total)]
+        sort: TagSort,
+        /// Print JSON instead of a table
+        #[clap(long)]
+        json: bool,
+        /// List at most this many tags, in the order of --sort
+        #[clap(short = 'n', long, value_name = "N")]
+        limit: Option<usize>,
+    },
+    /// Show a desktop notification if nothing was recorded today or the
+    /// weekly goal is at risk; meant to be run from cron or a timer
+    Remind {
+        /// Print the reminders instead of showing a notification
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Show the weekly goal time left after each day of the week against
+    /// an even pace
+    Burndown,
+    /// Show how long sessions tend to be by the hour they start in, over
+    /// the whole file
+    Productivity,
+    /// Show the all-time total, median and 90th percentile of session
+    /// lengths, overall and per tag, and whether they are mostly short or
+    /// long
+    Stats {
+        /// Only count events with this tag (repeatable)
+        #[clap(long = "tag")]
+        tags: Vec<String>,
+        /// Show how many sessions are under 15m, 15–30m, 30m–1h, 1–2h and
+        /// over 2h instead
+        #[clap(long)]
+        histogram: bool,
+    },
+    /// List the idle gaps between the sessions of each day, with their
+    /// average and the longest one
+    Gaps {
+        /// First day, YYYY-MM-DD
+        #[clap(long)]
+        from: Option<NaiveDate>,
+        /// Last day (inclusive), YYYY-MM-DD
+        #[clap(long)]
+        to: Option<NaiveDate>,
+    },
+    /// Show which `[[recurring]]` sessions of this week were done
+    Plan,
+    /// Compare the estimates of events (`[tag] ~2h 21:00 - 1h20m`) with
+    /// the time they took, per tag
+    Estimates {
+        /// Break the comparison down by month
+        #[clap(long)]
+        monthly: bool,
+    },
+    /// Show the current and longest run of consecutive days with a day
+    /// record, counting records without events as days off logged on
+    /// purpose
+    Streak,
+    /// Show the dates covered by the record file, how many days have
+    /// events, the share of calendar days they make up, and the number of
+    /// tags and file size
+    Summary,
+    /// Show a month as a grid of the minutes recorded on each day
+    Calendar {
+        /// Month to show, YYYY-MM; the current one by default
+        #[clap(value_parser = parse_month)]
+        month: Option<NaiveDate>,
+    },
+    /// Check the records against the rules in `[validate]`
+    Validate,
+    /// Rewrite tags throughout the record file
+    Tag {
+        #[clap(subcommand)]
+        action: TagAction,
+    },
+    /// Print the JSON Schema of a JSON output
+    Schema {
+        #[clap(value_enum)]
+        output: SchemaOutput,
+    },
+    /// Upgrade the settings of the record file to the current schema version
+    Migrate {
+        /// Print the migrated file instead of writing it
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Revert the most recent change made to the record file by this tool
+    Undo {
+        /// Undo even if the file was edited by hand afterwards
+        #[clap(long)]
+        force: bool,
+    },
+    /// Print a shell completion script
+    Completions {
+        #[clap(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Generate man pages
+    Man {
+        /// Write one page per subcommand into this directory instead of
+        /// printing the main page
+        #[clap(long)]
+        output_dir: Option<PathBuf>,
+    },
+    /// Print help for a subcommand or a topic (`syntax`, `query`,
+    /// `import-json`)
+    Help { topic: Option<String> },
+    /// Print the tag names used in the record file, one per line
+    #[clap(hide = true)]
+    CompleteTags,
+}
+
+#[derive(Debug, Clone, clap::Subcommand)]
+enum ImportSource {
+    /// Timed events of an iCalendar feed, such as the private address of a
+    /// Google Calendar
+    Gcal {
+        /// Address of the feed; `webcal://` works too
+        #[clap(long)]
+        url: String,
+        /// Only import events whose title contains this, ignoring case
+        #[clap(long = "match", value_name = "TEXT")]
+        pattern: String,
+        /// Tag the sessions with this instead of the event title
+        #[clap(long)]
+        tag: Option<String>,
+        /// Print the sessions that would be added without changing the file
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Time entries of a Clockify JSON export or API response, tagged with
+    /// their project and described by their description
+    Clockify {
+        /// JSON file to read; `-` is stdin
+        input: PathBuf,
+        /// Print the sessions that would be added without changing the file
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Closed `CLOCK:` lines of an Org document, tagged with the tags of
+    /// their headline, or its title without any
+    Org {
+        /// Org file to read; `-` is stdin
+        input: PathBuf,
+        /// Print the sessions that would be added without changing the file
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Sessions in the JSON format of `help import-json`, for tools without
+    /// an importer of their own
+    Json {
+        /// JSON file to read; `-` is stdin
+        input: PathBuf,
+        /// Print the sessions that would be added without changing the file
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Clone, clap::Subcommand)]
+enum Export {
+    /// Itemized invoice of the time spent on a client tag, billed at its
+    /// hourly rate from `[rates]`
+    Invoice {
+        /// Client tag to bill
+        #[clap(long)]
+        tag: String,
+        /// First day, YYYY-MM-DD
+        #[clap(long)]
+        from: NaiveDate,
+        /// Last day (inclusive), YYYY-MM-DD
+        #[clap(long)]
+        to: NaiveDate,
+        /// Only bill sessions matching this query (see `help query`)
+        #[clap(long, value_name = "QUERY", allow_hyphen_values = true)]
+        filter: Option<Query>,
+        #[clap(long, value_enum, default_value_t = InvoiceFormat::Markdown)]
+        format: InvoiceFormat,
+        /// Write the invoice to this file; `-` is stdout
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// SVG badge of this week's time against the goal, e.g. for a README
+    Badge {
+        /// Text on the left side of the badge
+        #[clap(long, default_value = "learning")]
+        label: String,
+        /// Write the badge to this file; `-` is stdout
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// PNG or SVG chart of the last weeks
+    Chart {
+        #[clap(long, value_enum)]
+        kind: ChartKind,
+        /// Number of weeks up to this one to chart
+        #[clap(long, default_value_t = 12)]
+        weeks: usize,
+        /// Image to write; SVG if it ends in `.svg`, PNG otherwise
+        #[clap(short, long)]
+        output: PathBuf,
+    },
+    /// CSV of the hours per tag (rows) and week (columns)
+    Pivot {
+        /// Number of weeks up to this one to include
+        #[clap(long, default_value_t = 12)]
+        weeks: usize,
+        /// Write the CSV to this file; `-` is stdout
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Excel workbook with a sheet of sessions and a sheet of the hours per
+    /// tag and week
+    Xlsx {
+        /// Number of weeks up to this one in the weekly sheet
+        #[clap(long, default_value_t = 12)]
+        weeks: usize,
+        /// Workbook to write
+        #[clap(short, long)]
+        output: PathBuf,
+    },
+    /// CSV of the hours of one tag per day or week, from the first day
+    /// record to today, for plotting
+    Timeseries {
+        #[clap(long)]
+        tag: String,
+        #[clap(long, value_enum, default_value_t = Granularity::Day)]
+        granularity: Granularity,
+        /// Write the CSV to this file; `-` is stdout
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// PDF of this week's report, or this month's, with bars per tag and
+    /// day, for printing or archiving
+    #[cfg(feature = "pdf")]
+    Pdf {
+        /// Report the current month instead
+        #[clap(long)]
+        month: bool,
+        /// File to write
+        #[clap(short, long)]
+        output: PathBuf,
+    },
+    /// Parquet file with one row per session, for DuckDB, Polars and the
+    /// like
+    #[cfg(feature = "arrow")]
+    Parquet {
+        /// File to write
+        #[clap(short, long)]
+        output: PathBuf,
+    },
+    #[clap(external_subcommand)]
+    External(Vec<String>),
+}
+
+#[derive(Debug, Clone, clap::Subcommand)]
+enum TagAction {
+    /// Rename a tag everywhere, keeping its details
+    Rename {
+        old: String,
+        #[clap(value_parser = parse_tag_name)]
+        new: String,
+        /// Show the affected lines instead of writing the file
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Replace several tags with one, e.g. `tag merge rs rustlang --into rust`
+    Merge {
+        #[clap(required = true)]
+        tags: Vec<String>,
+        #[clap(long, value_parser = parse_tag_name)]
+        into: String,
+        /// Show the affected lines instead of writing the file
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
 
-    let user_today = cli.today.unwrap_or(Local::now().naive_local());
-    let user_today: DateTime<Local> = Local.from_local_datetime(&user_today).unwrap();
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum SchemaOutput {
+    /// `report --format json`
+    Report,
+    /// `tags --json`
+    Tags,
+    /// `ast --format json` or `pretty`
+    Ast,
+}
 
-    let path = cli.path;
-    let _today = Local::now();
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum TagSort {
+    /// Most time first
+    Total,
+    /// Most events first
+    Count,
+    /// Alphabetically
+    Name,
+    /// Most recently used first
+    Last,
+}
 
-    let source = match fs::read_to_string(path) {
-        Ok(source) => source,
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum AstFormat {
+    /// Rust debug representation
+    Debug,
+    /// Compact JSON
+    Json,
+    /// Indented JSON
+    Pretty,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if let (None, Some(path)) = (&cli.command, &cli.path) {
+        if !path.exists() {
+            // More likely a mistyped command than a missing file.
+            Cli::command()
+                .error(
+                    clap::error::ErrorKind::InvalidSubcommand,
+                    format!("`{}` is neither a command nor a file", path.display()),
+                )
+                .exit();
+        }
+    }
+    let renderer = Renderer::new(cli.color, cli.plain);
+    init_logging(cli.verbose, cli.quiet, cli.color, cli.plain);
+
+    match run(cli, renderer.clone()) {
+        Ok(()) => ExitCode::SUCCESS,
         Err(err) => {
-            println!("🛑 {:?}", err);
-            return;
+            eprintln!("{}", renderer.error(&err));
+            ExitCode::from(err.exit_code())
         }
+    }
+}
+
+fn init_logging(verbose: u8, quiet: bool, color: ColorChoice, plain: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => Level::ERROR,
+        (false, 0) => Level::WARN,
+        (false, 1) => Level::INFO,
+        (false, 2) => Level::DEBUG,
+        (false, _) => Level::TRACE,
     };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_ansi(!plain && render::color_enabled(color, io::stderr().is_terminal()))
+        .without_time()
+        .init();
+}
 
-    let mut parser = Parser::new(source.chars().collect());
-    let result = parser.parse_file();
-    let ast = match result {
-        Ok(ast) => ast,
-        Err(err) => {
-            println!("🛑 {:?}", err);
-            return;
+fn run(cli: Cli, renderer: Renderer) -> Result<(), Error> {
+    let user_today = |settings: Option<&Settings>| {
+        let now = today::now(settings.and_then(|settings| settings.timezone));
+        cli.today.map_or(now, |today| {
+            today.resolve(now).unwrap_or_else(|| {
+                Cli::command()
+                    .error(
+                        clap::error::ErrorKind::ValueValidation,
+                        "--today is too far from the current date",
+                    )
+                    .exit()
+            })
+        })
+    };
+
+    let file = cli.file.or(cli.path);
+    let path = || file.clone().ok_or(Error::MissingFile);
+    let strictness = if cli.lenient {
+        Strictness::Lenient
+    } else if cli.strict {
+        Strictness::Strict
+    } else {
+        Strictness::Normal
+    };
+    // Tags as written, for commands that rewrite them.
+    let load_raw = |path: &Path| {
+        let mut file = load(path, strictness)?;
+        if let Some(settings) = &cli.settings {
+            apply_settings_file(&mut file, settings, cli.replace_settings)?;
         }
+        Ok::<_, Error>(file)
     };
+    let load = |path: &Path| {
+        let mut file = load_raw(path)?;
+        processing::normalize_tags(&mut file);
+        Ok::<_, Error>(file)
+    };
+
+    match cli.command.unwrap_or_else(default_command) {
+        Command::Report {
+            tags,
+            filter,
+            between,
+            weekdays,
+            weekends,
+            days,
+            include_ignored,
+            trend,
+            year,
+            year_to_date,
+            fail_under_goal,
+            format,
+            template,
+            output,
+            commit_summary,
+        } => {
+            let path = path()?;
+            let mut ast = load(&path)?;
+            let profile_tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+            let user_today = user_today(ast.settings.as_ref());
+            let settings = ast.settings_at(user_today.date());
+            let mut filter = Filter {
+                tags: if tags.is_empty() { profile_tags } else { tags },
+                query: filter,
+                between,
+                days: if weekdays {
+                    processing::WEEKDAYS.to_vec()
+                } else if weekends {
+                    processing::WEEKENDS.to_vec()
+                } else {
+                    days
+                },
+                ..Filter::default()
+            }
+            .ignoring(settings);
+            if include_ignored {
+                filter.ignore_tags.clear();
+            }
+            let yearly = year || year_to_date;
+            let mut report = if yearly {
+                processing::calc_yearly_records(&ast, user_today, &filter, year_to_date)?
+            } else {
+                calc_weekly_records(&ast, user_today, &filter)?
+            };
+            let defaults = settings
+                .and_then(|settings| settings.report.clone())
+                .unwrap_or_default();
+            let dir = path.parent().unwrap_or(Path::new("."));
+            let metrics = match defaults.script {
+                Some(script) => {
+                    let script = dir.join(script);
+                    script::run(&script, &ast, &filter, &mut report).map_err(|message| {
+                        Error::InFile {
+                            path: script,
+                            source: Box::new(Error::Script(message)),
+                        }
+                    })?
+                }
+                None => BTreeMap::new(),
+            };
+            // Goals, plans and earnings are per week or month.
+            let extras = if yearly {
+                Extras {
+                    metrics,
+                    ..Extras::default()
+                }
+            } else {
+                Extras {
+                    metrics,
+                    ..week_extras(
+                        &ast,
+                        &report,
+                        settings,
+                        user_today,
+                        trend.or(defaults.trend).unwrap_or_default(),
+                        &filter,
+                    )?
+                }
+            };
+            let format = match template {
+                Some(_) => ReportFormat::Template,
+                None => format.or(defaults.format).unwrap_or_default(),
+            };
+            let output = output.or_else(|| defaults.output.map(|output| dir.join(output)));
+            let renderer = renderer.with_settings(settings);
 
-    fs::write("out.txt", format!("{:#?}", ast)).unwrap();
-    match calc_weekly_records(&ast, user_today) {
-        Ok(duration) => println!("{:#?}", duration),
-        Err(err) => println!("🛑 {:?}", err),
+            if format == ReportFormat::Template {
+                let template = template
+                    .or_else(|| defaults.template.map(|template| dir.join(template)))
+                    .ok_or(Error::MissingTemplate)?;
+                let source = fs::read_to_string(template)?;
+                let content = template::render(&source, &renderer, &report, &extras)?;
+                write_output(output.as_deref(), renderer, |_| content)?;
+            } else {
+                write_output(output.as_deref(), renderer, |renderer| {
+                    renderer.report(format, &report, &extras)
+                })?;
+            }
+
+            if commit_summary {
+                let previous_week = processing::shift_period(&ast, user_today, -1);
+                let previous_settings = ast.settings_at(previous_week.date());
+                let filter = Filter::default().ignoring(previous_settings);
+                let completed = calc_weekly_records(&ast, previous_week, &filter)?;
+                let goal = previous_settings.and_then(|settings| {
+                    let goal = settings.goal?.0;
+                    Some(processing::prorate_goal(goal, &completed, settings))
+                });
+                let source = fs::read_to_string(&path)?;
+                match summary::commit(&source, &ast, &completed, goal) {
+                    Some(content) => store::write(&path, "report --commit-summary", &content)?,
+                    None => {
+                        tracing::warn!("no records in the last completed week, nothing to commit")
+                    }
+                }
+            }
+
+            if let Some(goal) = extras
+                .goal
+                .filter(|goal| fail_under_goal && report.total < *goal)
+            {
+                return Err(Error::GoalMissed {
+                    total: report.total,
+                    goal,
+                });
+            }
+        }
+        Command::Ast { format, output } => {
+            let ast = load(&path()?)?;
+            write_output(output.as_deref(), renderer, |_| match format {
+                AstFormat::Debug => format!("{:#?}", ast),
+                AstFormat::Json => serde_json::to_string(&ast).expect("AST is serializable"),
+                AstFormat::Pretty => {
+                    serde_json::to_string_pretty(&ast).expect("AST is serializable")
+                }
+            })?;
+        }
+        Command::Export {
+            target:
+                Export::Invoice {
+                    tag,
+                    from,
+                    to,
+                    filter,
+                    format,
+                    output,
+                },
+        } => {
+            let ast = load(&path()?)?;
+            let settings = ast.settings_at(to);
+            let filter = Filter {
+                tags: vec![tag],
+                query: filter,
+                ..Filter::default()
+            }
+            .ignoring(settings);
+            let tag = &filter.tags[0];
+            let rate = settings
+                .and_then(|settings| settings.rates.get(tag))
+                .copied()
+                .ok_or_else(|| Error::MissingRate(tag.clone()))?;
+            let invoice = Invoice::build(&ast, tag, from, to, rate, &filter);
+            let renderer = renderer.with_settings(settings);
+            write_output(output.as_deref(), renderer, |renderer| {
+                invoice.render(format, &renderer)
+            })?;
+        }
+        Command::Export {
+            target: Export::Badge { label, output },
+        } => {
+            let mut ast = load(&path()?)?;
+            let tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+            let user_today = user_today(ast.settings.as_ref());
+            let settings = ast.settings_at(user_today.date());
+            let filter = Filter {
+                tags,
+                ..Filter::default()
+            }
+            .ignoring(settings);
+            let report = calc_weekly_records(&ast, user_today, &filter)?;
+            let goal = settings.and_then(|settings| {
+                let goal = settings.goal?.0;
+                Some(processing::prorate_goal(goal, &report, settings))
+            });
+            write_output(output.as_deref(), renderer, |_| {
+                badge::svg(&label, report.total, goal)
+            })?;
+        }
+        Command::Export {
+            target:
+                Export::Chart {
+                    kind,
+                    weeks,
+                    output,
+                },
+        } => {
+            let mut ast = load(&path()?)?;
+            let tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+            let user_today = user_today(ast.settings.as_ref());
+            let settings = ast.settings_at(user_today.date());
+            let filter = Filter {
+                tags,
+                ..Filter::default()
+            }
+            .ignoring(settings);
+            let reports = last_weeks(&ast, user_today, weeks.max(1), &filter)?;
+            let (first, last) = (&reports[0], &reports[reports.len() - 1]);
+            let data = chart::Data {
+                weeks: reports
+                    .iter()
+                    .map(|report| (report.start.date(), report.total))
+                    .collect(),
+                goal: settings
+                    .and_then(|settings| settings.goal)
+                    .map(|goal| goal.0),
+                burndown: settings.and_then(|settings| {
+                    let goal = settings.goal?.0;
+                    Some(processing::burndown(
+                        last,
+                        settings,
+                        goal,
+                        user_today.date(),
+                    ))
+                }),
+                tags: processing::calc_period(&ast, first.start, last.end, &filter)?.tags,
+                styles: settings
+                    .map(|settings| settings.tags.clone())
+                    .unwrap_or_default(),
+            };
+            if kind == ChartKind::Burndown && data.burndown.is_none() {
+                return Err(Error::MissingGoal);
+            }
+            chart::draw(&output, kind, &data).map_err(|message| Error::InFile {
+                path: output.clone(),
+                source: Box::new(Error::Chart(message)),
+            })?;
+        }
+        Command::Export {
+            target: Export::Pivot { weeks, output },
+        } => {
+            let mut ast = load(&path()?)?;
+            let tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+            let user_today = user_today(ast.settings.as_ref());
+            let filter = Filter {
+                tags,
+                ..Filter::default()
+            }
+            .ignoring(ast.settings_at(user_today.date()));
+            let reports = last_weeks(&ast, user_today, weeks, &filter)?;
+            write_output(output.as_deref(), renderer, |_| pivot::csv(&reports))?;
+        }
+        Command::Export {
+            target: Export::Xlsx { weeks, output },
+        } => {
+            let mut ast = load(&path()?)?;
+            let tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+            let user_today = user_today(ast.settings.as_ref());
+            let filter = Filter {
+                tags,
+                ..Filter::default()
+            }
+            .ignoring(ast.settings_at(user_today.date()));
+            let reports = last_weeks(&ast, user_today, weeks, &filter)?;
+            fs::write(&output, xlsx::workbook(&ast, &filter, &reports))?;
+        }
+        Command::Export {
+            target:
+                Export::Timeseries {
+                    tag,
+                    granularity,
+                    output,
+                },
+        } => {
+            let ast = load(&path()?)?;
+            let user_today = user_today(ast.settings.as_ref());
+            let settings = ast.settings_at(user_today.date());
+            let filter = Filter {
+                tags: vec![tag],
+                ..Filter::default()
+            }
+            .ignoring(settings);
+            let dates = ast.records.iter().map(|record| record.date);
+            let first = dates.clone().min();
+            // Up to today, or the last day record if it is later.
+            let last = dates.chain([user_today.date()]).max().unwrap();
+            let end = last.succ_opt().unwrap().and_time(NaiveTime::MIN);
+            let rows = match (first, granularity) {
+                (None, _) => Vec::new(),
+                (Some(first), Granularity::Day) => {
+                    let report = processing::calc_period(
+                        &ast,
+                        first.and_time(NaiveTime::MIN),
+                        end,
+                        &filter,
+                    )?;
+                    let mut days: BTreeMap<NaiveDate, chrono::TimeDelta> = BTreeMap::new();
+                    for (date, total) in report.days {
+                        *days.entry(date).or_default() += total;
+                    }
+                    first
+                        .iter_days()
+                        .take_while(|date| *date <= last)
+                        .map(|date| (date, days.get(&date).copied().unwrap_or_default()))
+                        .collect()
+                }
+                (Some(first), Granularity::Week) => {
+                    let mut rows = Vec::new();
+                    let mut day = first.and_time(NaiveTime::MIN);
+                    while day < end {
+                        let report = calc_weekly_records(&ast, day, &filter)?;
+                        rows.push((report.start.date(), report.total));
+                        day = report.end;
+                    }
+                    rows
+                }
+            };
+            write_output(output.as_deref(), renderer, |_| pivot::timeseries(&rows))?;
+        }
+        #[cfg(feature = "pdf")]
+        Command::Export {
+            target: Export::Pdf { month, output },
+        } => {
+            let mut ast = load(&path()?)?;
+            let tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+            let user_today = user_today(ast.settings.as_ref());
+            let settings = ast.settings_at(user_today.date());
+            let filter = Filter {
+                tags,
+                ..Filter::default()
+            }
+            .ignoring(settings);
+            let (report, extras) = if month {
+                let report = calc_monthly_records(&ast, user_today, &filter)?;
+                (report, Extras::default())
+            } else {
+                let report = calc_weekly_records(&ast, user_today, &filter)?;
+                let extras = week_extras(&ast, &report, settings, user_today, 0, &filter)?;
+                (report, extras)
+            };
+            let renderer = renderer.with_settings(settings);
+            fs::write(&output, pdf::report(&renderer, &report, &extras))?;
+        }
+        #[cfg(feature = "arrow")]
+        Command::Export {
+            target: Export::Parquet { output },
+        } => {
+            let mut ast = load(&path()?)?;
+            let tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+            let user_today = user_today(ast.settings.as_ref());
+            let filter = Filter {
+                tags,
+                ..Filter::default()
+            }
+            .ignoring(ast.settings_at(user_today.date()));
+            let rows =
+                columnar::write(&output, &ast, &filter).map_err(|message| Error::InFile {
+                    path: output.clone(),
+                    source: Box::new(Error::Parquet(message)),
+                })?;
+            println!("wrote {} session{}", rows, if rows == 1 { "" } else { "s" });
+        }
+        Command::Export {
+            target: Export::External(args),
+        } => {
+            let path = path()?;
+            let ast = load(&path)?;
+            let (name, args) = args.split_first().expect("clap passes the subcommand name");
+            plugin::export(name, args, &path, &ast)?;
+        }
+        Command::Sync { direction } => {
+            let remote = load(&path()?)?
+                .settings
+                .and_then(|settings| settings.sync)
+                .ok_or(Error::NoRemote)?;
+            let outcome = sync::sync(&path()?, &remote, direction)?;
+            println!("{}", outcome);
+        }
+        Command::Doctor => {
+            let now = user_today(None);
+            let checks = doctor::diagnose(file.as_deref(), now, load);
+            for check in &checks {
+                println!("{}", renderer.check(check));
+            }
+            let errors = checks
+                .iter()
+                .filter(|check| check.severity == doctor::Severity::Error)
+                .count();
+            if errors > 0 {
+                return Err(Error::Unhealthy(errors));
+            }
+        }
+        Command::Remind { dry_run } => {
+            let mut ast = load(&path()?)?;
+            let tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+            let user_today = user_today(ast.settings.as_ref());
+            let filter = Filter {
+                tags,
+                ..Filter::default()
+            }
+            .ignoring(ast.settings_at(user_today.date()));
+            let reminders = remind::check(&ast, user_today, &filter)?;
+            if dry_run {
+                for reminder in &reminders {
+                    println!("{}", reminder);
+                }
+            } else if !reminders.is_empty() {
+                remind::notify(&reminders).map_err(|err| Error::Notify(err.to_string()))?;
+            }
+        }
+        Command::Burndown => {
+            let mut ast = load(&path()?)?;
+            let tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+            let user_today = user_today(ast.settings.as_ref());
+            let settings = ast.settings_at(user_today.date());
+            let filter = Filter {
+                tags,
+                ..Filter::default()
+            }
+            .ignoring(settings);
+            let (settings, goal) = settings
+                .and_then(|settings| Some((settings, settings.goal?.0)))
+                .ok_or(Error::MissingGoal)?;
+            let report = calc_weekly_records(&ast, user_today, &filter)?;
+            let burndown = processing::burndown(&report, settings, goal, user_today.date());
+            let renderer = renderer.with_settings(Some(settings));
+            println!("{}", renderer.burndown(report.start, &burndown));
+        }
+        Command::Plan => {
+            let mut ast = load(&path()?)?;
+            let tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+            let user_today = user_today(ast.settings.as_ref());
+            let settings = ast.settings_at(user_today.date());
+            let filter = Filter {
+                tags,
+                ..Filter::default()
+            }
+            .ignoring(settings);
+            let settings = settings
+                .filter(|settings| !settings.recurring.is_empty())
+                .ok_or(Error::MissingRecurring)?;
+            let report = calc_weekly_records(&ast, user_today, &filter)?;
+            let slots = processing::planned_slots(&ast, &report, settings, &filter);
+            let renderer = renderer.with_settings(Some(settings));
+            println!(
+                "{}",
+                renderer.planned_slots(report.start, &slots, user_today.date())
+            );
+        }
+        Command::Productivity => {
+            let ast = load(&path()?)?;
+            let user_today = user_today(ast.settings.as_ref());
+            let settings = ast.settings_at(user_today.date());
+            let filter = Filter::default().ignoring(settings);
+            let sessions = processing::sessions(&ast, &filter);
+            let stats = processing::hour_stats(&sessions);
+            let correlation = processing::hour_correlation(&sessions);
+            let longest = sessions.iter().max_by_key(|session| session.duration);
+            if !stats.is_empty() {
+                let renderer = renderer.with_settings(settings);
+                println!("{}", renderer.hour_stats(&stats, correlation, longest));
+            }
+        }
+        Command::Stats { tags, histogram } => {
+            let mut ast = load(&path()?)?;
+            let profile_tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+            let user_today = user_today(ast.settings.as_ref());
+            let settings = ast.settings_at(user_today.date());
+            let filter = Filter {
+                tags: if tags.is_empty() { profile_tags } else { tags },
+                ..Filter::default()
+            }
+            .ignoring(settings);
+            let lengths = stats::lengths(&ast, &filter);
+            if lengths[0].sessions() > 0 {
+                let renderer = renderer.with_settings(settings);
+                if histogram {
+                    println!("{}", renderer.histogram(&lengths));
+                } else {
+                    println!("{}", renderer.session_lengths(&lengths));
+                }
+            }
+        }
+        Command::Gaps { from, to } => {
+            let ast = load(&path()?)?;
+            let user_today = user_today(ast.settings.as_ref());
+            let settings = ast.settings_at(user_today.date());
+            let filter = Filter::default().ignoring(settings);
+            let gaps = gaps::gaps(&ast, search::DateRange { from, to }, &filter);
+            let renderer = renderer.with_settings(settings);
+            println!("{}", renderer.gaps(&gaps));
+        }
+        Command::Estimates { monthly } => {
+            let ast = load(&path()?)?;
+            let user_today = user_today(ast.settings.as_ref());
+            let settings = ast.settings_at(user_today.date());
+            let filter = Filter::default().ignoring(settings);
+            let stats = processing::estimate_stats(&ast, &filter, monthly);
+            if !stats.is_empty() {
+                let renderer = renderer.with_settings(settings);
+                println!("{}", renderer.estimate_stats(&stats));
+            }
+        }
+        Command::Streak => {
+            let ast = load(&path()?)?;
+            let user_today = user_today(ast.settings.as_ref());
+            let settings = ast.settings_at(user_today.date());
+            let filter = Filter::default().ignoring(settings);
+            let streaks = processing::streaks(&ast, user_today.date(), &filter);
+            println!("{}", renderer.with_settings(settings).streaks(&streaks));
+        }
+        Command::Summary => {
+            let path = path()?;
+            let ast = load(&path)?;
+            let bytes = fs::metadata(&path)?.len();
+            let overview = overview::overview(&ast, bytes);
+            println!(
+                "{}",
+                renderer
+                    .with_settings(ast.settings.as_ref())
+                    .overview(&overview)
+            );
+        }
+        Command::Calendar { month } => {
+            let ast = load(&path()?)?;
+            let user_today = user_today(ast.settings.as_ref());
+            let month = month.unwrap_or_else(|| user_today.date().with_day(1).unwrap());
+            let settings = ast.settings_at(month);
+            let filter = Filter::default().ignoring(settings);
+            let report = calc_monthly_records(&ast, month.and_time(NaiveTime::MIN), &filter)?;
+            let week_start = settings.map_or(Weekday::Mon, |settings| settings.start.weekday);
+            let renderer = renderer.with_settings(settings);
+            println!("{}", renderer.calendar(month, week_start, &report.days));
+        }
+        Command::Validate => {
+            let ast = load(&path()?)?;
+            let mut checks = validate::validate(&ast);
+            if checks.is_empty() {
+                checks.push(doctor::Check::ok("no problems found"));
+            }
+            for check in &checks {
+                println!("{}", renderer.check(check));
+            }
+            let errors = checks
+                .iter()
+                .filter(|check| check.severity == doctor::Severity::Error)
+                .count();
+            if errors > 0 {
+                return Err(Error::Invalid(errors));
+            }
+        }
+        Command::Search {
+            query,
+            from,
+            to,
+            context,
+            limit,
+        } => {
+            let path = path()?;
+            let ast = load(&path)?;
+            let renderer = renderer.with_settings(ast.settings.as_ref());
+            let mut hits = search::search(&ast, &query, search::DateRange { from, to });
+            if hits.is_empty() {
+                return Err(Error::NoMatch);
+            }
+            hits.truncate(limit.unwrap_or(usize::MAX));
+            let mut sources: BTreeMap<PathBuf, String> = BTreeMap::new();
+            let mut out = Vec::new();
+            for hit in &hits {
+                let file = hit.record.origin.clone().unwrap_or_else(|| path.clone());
+                if !sources.contains_key(&file) {
+                    let source = fs::read_to_string(&file)?;
+                    sources.insert(file.clone(), source);
+                }
+                out.push(renderer.search_hit(hit, &file, &sources[&file], &query.words(), context));
+            }
+            let separator = if context > 0 { "\n\n" } else { "\n" };
+            pager::page(&out.join(separator), !cli.no_pager)?;
+        }
+        Command::Log { tags, limit } => {
+            let mut ast = load(&path()?)?;
+            let profile_tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+            let user_today = user_today(ast.settings.as_ref());
+            let settings = ast.settings_at(user_today.date());
+            let filter = Filter {
+                tags: if tags.is_empty() { profile_tags } else { tags },
+                ..Filter::default()
+            }
+            .ignoring(settings);
+            let hits = search::recent(&ast, &filter, limit);
+            if !hits.is_empty() {
+                pager::page(&renderer.with_settings(settings).log(&hits), !cli.no_pager)?;
+            }
+        }
+        Command::Tags { sort, json, limit } => {
+            let ast = load(&path()?)?;
+            let renderer = renderer.with_settings(ast.settings.as_ref());
+            let mut stats = processing::tag_stats(&ast);
+            match sort {
+                TagSort::Total => stats.sort_by_key(|stats| std::cmp::Reverse(stats.total)),
+                TagSort::Count => stats.sort_by_key(|stats| std::cmp::Reverse(stats.events)),
+                TagSort::Name => stats.sort_by(|a, b| a.name.cmp(&b.name)),
+                TagSort::Last => stats.sort_by_key(|stats| std::cmp::Reverse(stats.last)),
+            }
+            stats.truncate(limit.unwrap_or(usize::MAX));
+            if json {
+                let json = serde_json::to_string_pretty(&stats).expect("stats are serializable");
+                println!("{}", json);
+            } else if !stats.is_empty() {
+                pager::page(&renderer.tag_stats(&stats), !cli.no_pager)?;
+            }
+        }
+        Command::Serve { address, token } => {
+            let path = path()?;
+            let load_week = || {
+                let mut ast = load(&path)?;
+                let tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+                let user_today = user_today(ast.settings.as_ref());
+                Ok::<_, Error>((ast, tags, user_today))
+            };
+            let routes = serve::Routes {
+                file: &path,
+                report: Box::new(|| {
+                    let (ast, tags, user_today) = load_week()?;
+                    week_json(&ast, tags, user_today, &renderer)
+                }),
+                days: Box::new(|| {
+                    let (ast, tags, user_today) = load_week()?;
+                    let filter = Filter {
+                        tags,
+                        ..Filter::default()
+                    }
+                    .ignoring(ast.settings_at(user_today.date()));
+                    let weeks = last_weeks(&ast, user_today, serve::DAYS_WEEKS, &filter)?;
+                    let days: Vec<serde_json::Value> = weeks
+                        .iter()
+                        .flat_map(|week| {
+                            week.dates().map(|date| {
+                                let total: chrono::TimeDelta = week
+                                    .days
+                                    .iter()
+                                    .filter(|(day, _)| *day == date)
+                                    .map(|(_, total)| *total)
+                                    .sum();
+                                serde_json::json!({
+                                    "date": date,
+                                    "minutes": total.num_minutes(),
+                                })
+                            })
+                        })
+                        .collect();
+                    Ok(serde_json::Value::from(days).to_string())
+                }),
+                now: Box::new(|| Ok(user_today(load(&path)?.settings.as_ref()))),
+                add: Box::new(|entry| {
+                    let source = fs::read_to_string(&path)?;
+                    add_entry(&path, &source, &load(&path)?, entry, "serve POST /events")
+                }),
+            };
+            serve::run(&address, token.as_deref(), &routes).map_err(Error::Serve)?;
+        }
+        Command::Mcp { allow_write } => {
+            let path = path()?;
+            let add = |entry| {
+                let source = fs::read_to_string(&path)?;
+                add_entry(&path, &source, &load(&path)?, entry, "mcp add_event")
+            };
+            let tools = mcp::Tools {
+                report: Box::new(|date| {
+                    let mut ast = load(&path)?;
+                    let tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+                    let mut user_today = user_today(ast.settings.as_ref());
+                    if let Some(date) = date {
+                        user_today = date.and_time(user_today.time());
+                    }
+                    week_json(&ast, tags, user_today, &renderer)
+                }),
+                search: Box::new(|query, range| {
+                    let ast = load(&path)?;
+                    let hits: Vec<serde_json::Value> = search::search(&ast, query, range)
+                        .iter()
+                        .map(|hit| serde_json::json!({ "date": hit.record.date, "event": hit.event }))
+                        .collect();
+                    Ok(hits.into())
+                }),
+                now: Box::new(|| Ok(user_today(load(&path)?.settings.as_ref()))),
+                add: allow_write.then(|| Box::new(add) as Box<dyn Fn(_) -> _>),
+            };
+            mcp::run(&tools, io::stdin().lock(), io::stdout().lock())?;
+        }
+        Command::Dedupe { dry_run } => {
+            let path = path()?;
+            let source = fs::read_to_string(&path)?;
+            let dedupe = dedupe::dedupe(&source, &load_raw(&path)?);
+            if dry_run {
+                for duplicate in &dedupe.duplicates {
+                    println!(
+                        "{}: {} {} (same as line {})",
+                        duplicate.line,
+                        duplicate.date.format("%Y-%m-%d"),
+                        duplicate.event,
+                        duplicate.original
+                    );
+                }
+            } else if !dedupe.duplicates.is_empty() {
+                store::write(&path, "dedupe", &dedupe.content)?;
+            }
+            let count = dedupe.duplicates.len();
+            println!(
+                "{} {} duplicate event{}",
+                if dry_run { "would remove" } else { "removed" },
+                count,
+                if count == 1 { "" } else { "s" }
+            );
+        }
+        Command::Split { by, dir } => {
+            let path = path()?;
+            let extension = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("lr");
+            if matches!(extension, "md" | "markdown") {
+                return Err(Error::Split("cannot split a Markdown file".to_string()));
+            }
+            let dir = dir.unwrap_or_else(|| PathBuf::from(path.file_stem().unwrap_or_default()));
+            let source = fs::read_to_string(&path)?;
+            let split = split::split(&source, &load_raw(&path)?, by, &dir, extension)
+                .map_err(Error::Split)?;
+            if split.parts.is_empty() {
+                return Err(Error::Split("no day records to split".to_string()));
+            }
+            let base = path.parent().unwrap_or(Path::new(""));
+            for (part, _) in &split.parts {
+                if base.join(part).exists() {
+                    return Err(Error::Split(format!("{} already exists", part)));
+                }
+            }
+            fs::create_dir_all(base.join(&dir))?;
+            for (part, content) in &split.parts {
+                fs::write(base.join(part), content)?;
+            }
+            store::write(&path, "split", &split.index)?;
+            println!(
+                "moved the day records into {} file{} in {}",
+                split.parts.len(),
+                if split.parts.len() == 1 { "" } else { "s" },
+                base.join(&dir).display()
+            );
+        }
+        Command::Diff { files, week } => {
+            if let [first, second] = files.as_slice() {
+                let differences = diff::files(&load(first)?, &load(second)?);
+                if differences.is_empty() {
+                    return Ok(());
+                }
+                println!("{}", renderer.file_diff(&differences));
+                return Err(Error::Differ);
+            }
+            let [first, second] = week[..] else {
+                Cli::command()
+                    .error(
+                        clap::error::ErrorKind::WrongNumberOfValues,
+                        "--week must be given twice, e.g. --week -1 --week -2",
+                    )
+                    .exit();
+            };
+            let mut ast = load(&path()?)?;
+            let tags = apply_profile(&mut ast, cli.profile.as_deref())?;
+            let user_today = user_today(ast.settings.as_ref());
+            let renderer = renderer.with_settings(ast.settings.as_ref());
+            let filter = Filter {
+                tags,
+                ..Filter::default()
+            }
+            .ignoring(ast.settings_at(user_today.date()));
+            let week = |offset| {
+                let today = processing::shift_period(&ast, user_today, offset);
+                calc_weekly_records(&ast, today, &filter)
+            };
+            let (first, second) = (week(first)?, week(second)?);
+            let changes = diff::tag_changes(&first, &second);
+            println!("{}", renderer.period_diff(&first, &second, &changes));
+        }
+        Command::Merge {
+            first,
+            second,
+            output,
+        } => {
+            let read = |path: &Path| {
+                let source = fs::read_to_string(path).map_err(|err| Error::InFile {
+                    path: path.to_path_buf(),
+                    source: Box::new(err.into()),
+                })?;
+                Ok::<_, Error>((source, load_raw(path)?))
+            };
+            let (ours_source, ours) = read(&first)?;
+            let (theirs_source, theirs) = read(&second)?;
+            let merge = merge::merge(&ours_source, &ours, &theirs_source, &theirs);
+            match &output {
+                Some(output) => fs::write(output, &merge.content)?,
+                None => print!("{}", merge.content),
+            }
+            if merge.settings_differ {
+                eprintln!("settings differ; kept those of {}", first.display());
+            }
+            for conflict in &merge.conflicts {
+                eprintln!(
+                    "conflict on {} at {}: kept `{}`, left out `{}`",
+                    conflict.date.format("%Y-%m-%d"),
+                    conflict.time.format("%H:%M"),
+                    conflict.ours,
+                    conflict.theirs
+                );
+            }
+            eprintln!(
+                "added {} event{} of {}, {} conflict{}",
+                merge.added,
+                if merge.added == 1 { "" } else { "s" },
+                second.display(),
+                merge.conflicts.len(),
+                if merge.conflicts.len() == 1 { "" } else { "s" }
+            );
+        }
+        Command::Import { source } => {
+            let path = path()?;
+            let content = fs::read_to_string(&path)?;
+            let ast = load(&path)?;
+            let timezone = ast.settings.as_ref().and_then(|settings| settings.timezone);
+            let read = |input: &Path| -> Result<String, Error> {
+                if input == Path::new("-") {
+                    Ok(io::read_to_string(io::stdin())?)
+                } else {
+                    fs::read_to_string(input).map_err(|err| Error::InFile {
+                        path: input.to_path_buf(),
+                        source: Box::new(err.into()),
+                    })
+                }
+            };
+            let (command, entries, dry_run) = match source {
+                ImportSource::Gcal {
+                    url,
+                    pattern,
+                    tag,
+                    dry_run,
+                } => {
+                    let ics = gcal::fetch(&url).map_err(Error::Import)?;
+                    let entries = gcal::entries(&ics, &pattern, tag.as_deref(), timezone);
+                    ("import gcal", entries, dry_run)
+                }
+                ImportSource::Clockify { input, dry_run } => {
+                    let entries = clockify::entries(&read(&input)?, timezone);
+                    ("import clockify", entries.map_err(Error::Import)?, dry_run)
+                }
+                ImportSource::Org { input, dry_run } => {
+                    ("import org", org::entries(&read(&input)?), dry_run)
+                }
+                ImportSource::Json { input, dry_run } => {
+                    let entries = import::json(&read(&input)?);
+                    ("import json", entries.map_err(Error::Import)?, dry_run)
+                }
+            };
+            let import = import::insert(&content, &ast, &entries);
+            if dry_run {
+                for entry in &import.added {
+                    println!("{} {}", entry.date.format("%Y-%m-%d"), entry.line());
+                }
+            } else {
+                store::write(&path, command, &import.content)?;
+            }
+            println!(
+                "{} {} session{}, skipped {} already recorded",
+                if dry_run { "would add" } else { "added" },
+                import.added.len(),
+                if import.added.len() == 1 { "" } else { "s" },
+                import.skipped
+            );
+        }
+        Command::Tag { action } => {
+            let path = path()?;
+            let source = fs::read_to_string(&path)?;
+            let ast = load_raw(&path)?;
+            let (command, rewrite, dry_run) = match &action {
+                TagAction::Rename { old, new, dry_run } => (
+                    "tag rename",
+                    retag::rename(&source, &ast, old, new),
+                    *dry_run,
+                ),
+                TagAction::Merge {
+                    tags,
+                    into,
+                    dry_run,
+                } => {
+                    let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+                    (
+                        "tag merge",
+                        retag::merge(&source, &ast, &tags, into),
+                        *dry_run,
+                    )
+                }
+            };
+            if rewrite.tags == 0 {
+                return Err(Error::NoMatch);
+            }
+            if dry_run {
+                for line in &rewrite.lines {
+                    println!("{}: {}", line.number, line.before);
+                    println!(
+                        "{}  {}",
+                        " ".repeat(line.number.to_string().len()),
+                        line.after
+                    );
+                }
+            } else {
+                store::write(&path, command, &rewrite.content)?;
+            }
+            println!(
+                "{} {} tag{} in {} event{}",
+                if dry_run { "would change" } else { "changed" },
+                rewrite.tags,
+                if rewrite.tags == 1 { "" } else { "s" },
+                rewrite.events,
+                if rewrite.events == 1 { "" } else { "s" },
+            );
+        }
+        Command::Schema { output } => {
+            let schema = match output {
+                SchemaOutput::Report => render::report_schema(),
+                SchemaOutput::Tags => schemars::schema_for!(Vec<processing::TagStats>),
+                SchemaOutput::Ast => schemars::schema_for!(ast::File),
+            };
+            let json = serde_json::to_string_pretty(&schema).expect("schemas are serializable");
+            println!("{}", json);
+        }
+        Command::Migrate { dry_run } => {
+            let path = path()?;
+            let source = fs::read_to_string(&path)?;
+            match migrate::migrate(&source).map_err(Error::InvalidSettings)? {
+                None => println!("already at settings version {}", settings::VERSION),
+                Some(migration) if dry_run => print!("{}", migration.content),
+                Some(migration) => {
+                    store::write(&path, "migrate", &migration.content)?;
+                    if migration.from == settings::VERSION {
+                        println!("recorded settings version {}", settings::VERSION);
+                    } else {
+                        println!(
+                            "migrated settings from version {} to {}",
+                            migration.from,
+                            settings::VERSION
+                        );
+                    }
+                }
+            }
+        }
+        Command::Undo { force } => {
+            let entry = store::undo(&path()?, force)?;
+            println!(
+                "undid `{}` from {}",
+                entry.command,
+                entry.at.format("%Y-%m-%d %H:%M:%S")
+            );
+        }
+        Command::Completions { shell } => completions::generate(shell, &mut io::stdout())?,
+        Command::Man { output_dir } => match output_dir {
+            Some(dir) => manual::write_all(&dir)?,
+            None => manual::render(&mut io::stdout())?,
+        },
+        Command::Help { topic } => help(topic.as_deref())?,
+        Command::CompleteTags => {
+            for name in completions::tag_names(&load(&path()?)?) {
+                println!("{}", name);
+            }
+        }
     }
+
+    Ok(())
+}
+
+fn parse_weekday(s: &str) -> Result<Weekday, String> {
+    s.parse()
+        .map_err(|_| format!("`{s}` is not a weekday (mon, tue, ...)"))
+}
+
+fn parse_month(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(&format!("{s}-01"), "%Y-%m-%d")
+        .map_err(|_| format!("`{s}` is not a month (YYYY-MM)"))
+}
+
+fn parse_tag_name(s: &str) -> Result<String, String> {
+    if s.is_empty() || s.contains(|c: char| c.is_whitespace() || "[]()".contains(c)) {
+        Err(format!(
+            "`{s}` is not a valid tag name (no spaces or brackets)"
+        ))
+    } else {
+        Ok(s.to_string())
+    }
+}
+
+fn help(topic: Option<&str>) -> Result<(), Error> {
+    let mut command = Cli::command();
+    match topic {
+        None => command.print_long_help()?,
+        Some("syntax") => print!("{}", manual::SYNTAX),
+        Some("query") => print!("{}", manual::QUERY),
+        Some("import-json") => print!("{}", manual::IMPORT_JSON),
+        Some(name) => match command.find_subcommand_mut(name) {
+            Some(subcommand) => subcommand.print_long_help()?,
+            None => return Err(Error::UnknownHelpTopic(name.to_string())),
+        },
+    }
+
+    Ok(())
+}
+
+/// `report` with its defaults, for invocations without a command such as
+/// `learning-record records.lr`, the only form of the first versions.
+fn default_command() -> Command {
+    Command::Report {
+        tags: Vec::new(),
+        filter: None,
+        between: None,
+        weekdays: false,
+        weekends: false,
+        days: Vec::new(),
+        include_ignored: false,
+        trend: None,
+        year: false,
+        year_to_date: false,
+        fail_under_goal: false,
+        format: None,
+        template: None,
+        output: None,
+        commit_summary: false,
+    }
+}
+
+/// The report of the week containing `today` as JSON, with the trend of
+/// `report.trend` weeks.
+fn week_json(
+    file: &ast::File,
+    tags: Vec<String>,
+    today: NaiveDateTime,
+    renderer: &Renderer,
+) -> Result<String, Error> {
+    let settings = file.settings_at(today.date());
+    let filter = Filter {
+        tags,
+        ..Filter::default()
+    }
+    .ignoring(settings);
+    let report = calc_weekly_records(file, today, &filter)?;
+    let trend = settings
+        .and_then(|settings| settings.report.as_ref()?.trend)
+        .unwrap_or_default();
+    let extras = week_extras(file, &report, settings, today, trend, &filter)?;
+    let renderer = renderer.clone().with_settings(settings);
+
+    Ok(renderer.report(ReportFormat::Json, &report, &extras))
+}
+
+/// Adds `entry` to the record file at `path`, whose content is `source`,
+/// unless a session already starts at its time.
+fn add_entry(
+    path: &Path,
+    source: &str,
+    file: &ast::File,
+    entry: import::Entry,
+    command: &str,
+) -> Result<import::Import, Error> {
+    let import = import::insert(source, file, &[entry]);
+    if !import.added.is_empty() {
+        store::write(path, command, &import.content)?;
+    }
+
+    Ok(import)
+}
+
+/// Goal, plan, earnings and trend of the weekly `report` of `today`;
+/// everything but the script metrics.
+fn week_extras(
+    file: &ast::File,
+    report: &PeriodReport,
+    settings: Option<&Settings>,
+    today: NaiveDateTime,
+    trend: usize,
+    filter: &Filter,
+) -> Result<Extras, Error> {
+    let earnings = match settings {
+        Some(settings) if !settings.rates.is_empty() => {
+            let month = calc_monthly_records(file, today, filter)?;
+            Some(processing::earnings(report, &month, &settings.rates))
+        }
+        _ => None,
+    };
+    let goal = settings.and_then(|settings| {
+        let goal = settings.goal?.0;
+        Some(processing::prorate_goal(goal, report, settings))
+    });
+    let schedule = settings
+        .and_then(|settings| {
+            let plan = settings.plan?;
+            Some(processing::schedule(report, settings, &plan, today.date()))
+        })
+        .unwrap_or_default();
+
+    Ok(Extras {
+        goal,
+        tag_goals: settings
+            .map(|settings| processing::tag_goals(report, settings))
+            .unwrap_or_default(),
+        earnings,
+        schedule,
+        metrics: BTreeMap::new(),
+        trend: last_weeks(file, today, trend, filter)?
+            .iter()
+            .map(|week| week.total)
+            .collect(),
+        average: {
+            let weeks = completed_weeks(file, today, filter)?;
+            processing::average_total(file, &weeks).map(|average| (average, weeks.len()))
+        },
+    })
+}
+
+/// Up to `AVERAGE_WEEKS` weeks before the one containing `today`, leaving
+/// out those that ended before the first day record.
+fn completed_weeks(
+    file: &ast::File,
+    today: NaiveDateTime,
+    filter: &Filter,
+) -> Result<Vec<PeriodReport>, Error> {
+    let Some(first) = file.records.iter().map(|record| record.date).min() else {
+        return Ok(Vec::new());
+    };
+    let mut weeks = last_weeks(file, today, AVERAGE_WEEKS + 1, filter)?;
+    weeks.pop();
+    weeks.retain(|week| week.end.date() > first);
+
+    Ok(weeks)
+}
+
+/// Reports of the `count` weeks up to the one containing `today`, oldest
+/// first.
+fn last_weeks(
+    file: &ast::File,
+    today: NaiveDateTime,
+    count: usize,
+    filter: &Filter,
+) -> Result<Vec<PeriodReport>, Error> {
+    (0..count)
+        .rev()
+        .map(|weeks| {
+            let day = processing::shift_period(file, today, -(weeks as i64));
+            Ok(calc_weekly_records(file, day, filter)?)
+        })
+        .collect()
+}
+
+/// Writes to `output`, or prints when it is absent or `-`. Colors are only
+/// used when printing.
+fn write_output(
+    output: Option<&Path>,
+    renderer: Renderer,
+    render: impl FnOnce(Renderer) -> String,
+) -> Result<(), Error> {
+    match output.filter(|output| *output != Path::new("-")) {
+        Some(output) => {
+            let content = render(renderer.without_color());
+            fs::write(output, content + "\n")?;
+        }
+        None => println!("{}", render(renderer)),
+    }
+
+    Ok(())
+}
+
+/// Applies the profile `name` to the settings of `file` and returns the tags
+/// it is restricted to.
+fn apply_profile(file: &mut ast::File, name: Option<&str>) -> Result<Vec<String>, Error> {
+    let Some(name) = name else {
+        return Ok(Vec::new());
+    };
+    let blocks = file
+        .settings
+        .iter_mut()
+        .chain(file.changes.iter_mut().map(|change| &mut change.settings));
+    let mut tags = None;
+    for settings in blocks {
+        if let Some(profile) = settings.profiles.get(name).cloned() {
+            settings.apply_profile(&profile);
+            tags = Some(profile.tags);
+        }
+    }
+    tracing::debug!(name, "using profile");
+
+    tags.ok_or_else(|| Error::UnknownProfile(name.to_string()))
+}
+
+/// Applies a standalone settings file to every settings block of `file`.
+fn apply_settings_file(file: &mut ast::File, path: &Path, replace: bool) -> Result<(), Error> {
+    let in_file = |err: Error| Error::InFile {
+        path: path.to_path_buf(),
+        source: Box::new(err),
+    };
+    let invalid = |err| in_file(Error::InvalidSettings(ParseErrorKind::TomlError(err)));
+
+    let source = fs::read_to_string(path).map_err(|err| in_file(err.into()))?;
+    let table = parser::parse_settings_table(&source)
+        .map_err(|kind| in_file(Error::InvalidSettings(kind)))?;
+
+    if replace {
+        file.changes.clear();
+    }
+    match &mut file.settings {
+        Some(settings) if !replace => *settings = settings.merged(&table).map_err(invalid)?,
+        settings => *settings = Some(Settings::deserialize(table.clone()).map_err(invalid)?),
+    }
+    for change in &mut file.changes {
+        change.settings = change.settings.merged(&table).map_err(invalid)?;
+    }
+
+    Ok(())
+}
+
+fn load(path: &Path, strictness: Strictness) -> Result<ast::File, Error> {
+    load_including(path, strictness, &mut Vec::new())
+}
+
+/// Parses `path` and appends the day records of the files it `@include`s,
+/// resolved relative to it. `stack` holds the files being loaded.
+fn load_including(
+    path: &Path,
+    strictness: Strictness,
+    stack: &mut Vec<PathBuf>,
+) -> Result<ast::File, Error> {
+    let source = fs::read_to_string(path)?;
+    let canonical = path.canonicalize()?;
+    if stack.contains(&canonical) {
+        return Err(Error::IncludeCycle);
+    }
+
+    let mut file = parse(path, &source, strictness)?;
+
+    stack.push(canonical);
+    let dir = path.parent().unwrap_or(Path::new("."));
+    for include in &file.includes {
+        let included_path = dir.join(&include.path);
+        let included =
+            load_including(&included_path, strictness, stack).map_err(|err| Error::InFile {
+                path: included_path.clone(),
+                source: Box::new(err),
+            })?;
+        file.records
+            .extend(included.records.into_iter().map(|mut record| {
+                record.origin.get_or_insert_with(|| included_path.clone());
+                record
+            }));
+    }
+    stack.pop();
+
+    Ok(file)
+}
+
+/// Parses `source`, the content of `path`, reading only the fenced record
+/// blocks of a Markdown file. Warnings of a lenient parse are logged.
+fn parse(
+    path: &Path,
+    source: &str,
+    strictness: Strictness,
+) -> Result<ast::File, parser::ParseError> {
+    let markdown = path
+        .extension()
+        .is_some_and(|extension| extension == "md" || extension == "markdown");
+    // The blank lines and spacing of a Markdown document are its own.
+    let strictness = if markdown && strictness == Strictness::Strict {
+        Strictness::Normal
+    } else {
+        strictness
+    };
+    let source = if markdown {
+        parser::extract_markdown(source)
+    } else {
+        source.to_string()
+    };
+    let mut parser = parser::Parser::new(source.chars().collect()).with_strictness(strictness);
+    let file = parser.parse_file()?;
+    for warning in parser.warnings() {
+        tracing::warn!("{}:{}", path.display(), warning);
+    }
+
+    Ok(file)
 }
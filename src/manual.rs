@@ -0,0 +1,353 @@
+use std::{fs, io, path::Path};
+
+use clap::CommandFactory as _;
+
+use crate::Cli;
+
+/// Description of the record file format shown by `help syntax` and in the
+/// FILE FORMAT section of the man page.
+pub const SYNTAX: &str = "\
+A record file consists of optional settings followed by day records.
+It is UTF-8, with or without a byte order mark, and lines may end in LF
+or CRLF.
+
+SETTINGS
+    TOML between two `---` lines at the very top of the file.
+
+        ---
+        version = 1             # settings schema, see `migrate`
+        goal = \"10h\"            # weekly target, optional
+        timezone = \"Asia/Tokyo\" # defaults to the system time zone
+        locale = \"ja\"           # report language: en (default) or ja
+        [start]
+        weekday = \"Mon\"         # first day of the reporting week
+        time = \"06:00:00\"       # time of day the week starts
+        ---
+
+    YAML front matter, as used by Jekyll and other Markdown tools, is
+    accepted too and maps to the same keys:
+
+        ---
+        goal: 10h
+        start:
+          weekday: Mon
+          time: \"06:00:00\"
+        ---
+
+    A reporting period can span several weeks, e.g. for a fortnightly
+    review. `from` is a day of the first period; the goal is then for the
+    whole period:
+
+        [start]
+        weekday = \"Mon\"
+        time = \"06:00:00\"
+        weeks = 2
+        from = 2024-09-02
+
+    Cycles of any number of days start at `time` every `days` days from
+    `from`, without a weekday:
+
+        [start]
+        time = \"06:00:00\"
+        days = 10
+        from = 2024-09-01
+
+    `report --year` and `--year-to-date` count from January, or from the
+    month given as `year_start`, e.g. for a Japanese school year:
+
+        year_start = 4
+
+    Settings can also come from a separate file given with --settings,
+    merged over those of the record file, or replacing them with
+    --replace-settings.
+
+    Output can be tuned without affecting how the file is read:
+
+        [display]
+        date_format = \"%Y/%m/%d (%a)\"  # %a/%A are localized weekday names
+        weekdays = [\"月\", \"火\", \"水\", \"木\", \"金\", \"土\", \"日\"]
+        duration_format = \"clock\"      # units (1h30m), clock (1:30),
+                                       # minutes (90m) or decimal (1.5h)
+
+    Tags can have weekly targets of their own, shown with a progress bar
+    next to their time in reports:
+
+        [tag_goals]
+        rust = \"5h\"
+        math = \"2h\"
+
+    Tags can have a color and an icon, used in terminal reports, the
+    `serve` dashboard and charts:
+
+        [tags.rust]
+        color = \"orange\"   # a CSS color name or \"#rrggbb\"
+        icon = \"🦀\"
+
+    Durations can be rounded before they are summed, e.g. for invoicing:
+
+        [rounding]
+        granularity = \"15m\"
+        mode = \"up\"        # up (default), down or nearest
+        scope = \"session\"  # session (default) or day
+
+    Sessions only a short break apart can count as one in session
+    statistics such as `productivity`, without changing any total:
+
+        merge_gap = \"5m\"
+
+    Hourly rates turn time into money in reports, with weekly and
+    month-to-date earnings. An event with several rated tags is paid once
+    per tag:
+
+        currency = \"$\"
+        [rates]
+        mentoring = 60
+        consulting = 85.5
+
+    A plan of the time intended for each weekday adds planned vs actual
+    time with the variance of every day so far to reports:
+
+        [plan]
+        mon = \"2h\"
+        sat = \"4h\"   # days left out are planned as zero
+
+    Recurring sessions plan time on a tag for the same days of every week.
+    `plan` shows which of this week were done, partially done or missed:
+
+        [[recurring]]
+        tag = \"rust\"
+        every = \"weekday\"   # or \"day\", \"weekend\", [\"mon\", \"thu\"]
+        duration = \"1h\"
+
+    Holidays and vacations reduce the weekly goal by their share of the
+    week, have nothing planned, do not break a streak and weigh less in
+    the average of recent weeks:
+
+        holidays = [
+            { from = \"2024-12-28\", to = \"2025-01-03\", name = \"New Year\" },
+            { from = \"2025-05-05\" },  # a single day
+        ]
+
+    Some activities can be recorded without ever counting towards totals,
+    unless `report --include-ignored` is given:
+
+        ignore_tags = [\"break\", \"commute\"]
+
+    A Rhai script can compute extra metrics and adjust the report before
+    it is rendered. It gets `events`, the sessions of the week as maps of
+    date, time, minutes, tags, details and line, and `report`, with
+    total_minutes, tags and days that it may change. Whatever it puts in
+    `metrics` is shown below the total:
+
+        [report]
+        script = \"metrics.rhai\"   # relative to the record file
+
+        // metrics.rhai
+        metrics.sessions = events.len();
+        report.tags = report.tags.filter(|t| t.tag != \"admin\");
+
+    `validate` checks the records against some rules:
+
+        [validate]
+        require_tags = true   # every event needs a tag, or one of
+                              # a list: [\"work\", \"study\"]
+        max_per_day = \"12h\"   # more in a day is likely a typo;
+                              # 24 hours when absent
+
+    It also warns about events with a zero duration, like `[rust] 0m`.
+
+    Profiles selected with --profile override the week start and the goal
+    and restrict reports to some tags:
+
+        [profiles.work]
+        goal = \"5h\"
+        tags = [\"kubernetes\", \"go\"]
+        [profiles.work.start]
+        weekday = \"Sun\"
+        time = \"00:00:00\"
+
+    Another settings block between day records changes the settings from
+    the next record on. Its keys are merged over the settings before it:
+
+        2024-03-01
+        [rust] 21:00 - 1h
+
+        ---
+        goal = \"12h\"
+        ---
+
+        2024-03-04
+        [rust] 21:00 - 2h
+
+DAY RECORDS
+    A date in YYYY-MM-DD form on its own line, followed by one event per
+    line. Day records are separated by an empty line.
+
+    Dates may also be separated with slashes or dots, as in 2024/09/15 or
+    2024.09.15, or follow the `strftime` format of the `date_format`
+    setting, e.g. `date_format = \"%d/%m/%Y\"`.
+
+        2024-09-15
+        [rust(ownership) book] 21:00 - 1h30m, 23:00 - 20m
+        07:30 - 45m
+
+    A date without events records a day off on purpose: it counts as a
+    logged day with no time in `streak` and keeps `remind` quiet.
+
+    Events may be indented with spaces or tabs, and spaces may pad tags,
+    hyphens and commas to line up columns:
+
+        2024-09-16
+            [rust]      21:00 -  1h30m,  23:00 - 20m
+            [go book]   07:00 -    30m
+
+    With --lenient, blank lines inside a day record, spaces at the end of
+    lines and other slips are accepted with a warning; with --strict they
+    are all errors, even those accepted by default.
+
+EVENTS
+    An event is an optional tag list followed by one or more sessions
+    separated by commas.
+
+    An estimate of the time the event will take may follow the tags, as a
+    duration after a tilde: [rust] ~2h 21:00 - 1h20m. `estimates` compares
+    estimates with the actual time per tag.
+
+TAGS
+    Space separated names in square brackets. A tag may carry a detail in
+    parentheses directly after its name: [rust(borrowck) anki].
+
+    Tags are case-sensitive. With `normalize_tags = true` in the settings,
+    tags are converted to NFKC form and lowercase, so that Rust, rust and
+    full-width Ｒｕｓｔ count as one tag, shown as `rust`. Write the keys
+    of [rates] in that form.
+
+    With `normalize_input = true`, day records may be typed with a Japanese
+    input method: full-width digits, letters and punctuation are read as
+    ASCII and tags are converted to NFKC form, keeping their case:
+
+        ２０２４－０９－１５
+        ［ｒｕｓｔ］　２１：００　－　１ｈ３０ｍ
+
+SESSIONS
+    A start time HH:MM, a hyphen and a duration: 21:00 - 1h30m. The start
+    may have seconds, as imported from precise trackers: 21:00:30 - 1h.
+
+    The start may be on the 12-hour clock with am or pm, in either case
+    and with or without dots: 9:30pm - 1h, 7:05 a.m. - 20m.
+
+    When only the duration is known, the start can be left out, with or
+    without the hyphen: [anki] - 20m or [anki] 20m. Such sessions count for
+    their day, but not for `--between`, `gaps` or exports by start time.
+
+    A correction takes time off without editing earlier days: [rust] !-30m
+    on a later day subtracts 30 minutes from rust and from the total of
+    the period it is in. Reports list the corrections they applied.
+
+DURATIONS
+    Any of hours (h), minutes (m) and seconds (s), in that order, without
+    spaces: 2h, 45m, 1h30m, 1h5s.
+
+    A session can also be given in pomodoros, 25 minutes each unless the
+    settings say `pomodoro = \"30m\"`: 21:00 - 3p. Reports count them.
+
+INCLUDES
+    `@include PATH` on its own line between day records reads the day
+    records of another file, relative to the including one. Settings of
+    included files are ignored.
+
+        @include archive/2023.lr
+
+MARKDOWN
+    A file ending in .md or .markdown, such as an Obsidian daily note, is
+    read as Markdown: only the content of its ```learning-record fenced
+    blocks counts, taken together as one record file.
+
+COMMENTS
+    Lines starting with # are ignored, both between and inside day records.
+    `report --commit-summary` writes its weekly summaries as comments.
+";
+
+/// Query language of `search`, `report --filter` and `export invoice
+/// --filter`, shown by `help query`.
+pub const QUERY: &str = "\
+A query selects sessions. Terms separated by spaces must all match, `or`
+separates alternatives and a leading `-` negates a term:
+
+    tag:rust after:2024-01-01 dur>1h
+    tag:rust or tag:go
+    -tag:anki time<09:00
+
+TERMS
+    word           a tag or a detail contains the word, ignoring case
+    tag:NAME       the event has the tag NAME
+    detail:WORD    a detail contains WORD
+    after:DATE     on DATE (YYYY-MM-DD) or later
+    before:DATE    before DATE
+    on:DATE        on DATE
+    day:WEEKDAY    on a weekday such as mon or saturday
+    dur>DURATION   sessions longer than DURATION, e.g. dur>=1h30m
+    time<HH:MM     sessions starting before HH:MM, e.g. time>=18:00
+
+    dur and time accept <, <=, =, >= and >. Use double quotes around values
+    containing spaces.
+";
+
+/// Format read by `import json`, shown by `help import-json`.
+pub const IMPORT_JSON: &str = "\
+`import json` reads an array of sessions, one object each:
+
+    [
+      { \"start\": \"2024-09-15T21:00:00\", \"duration\": \"1h30m\",
+        \"tags\": [\"rust\", \"book\"], \"detail\": \"ownership\" },
+      { \"start\": \"2024-09-16T07:30:00\", \"end\": \"2024-09-16T08:15:00\" }
+    ]
+
+FIELDS
+    start       wall-clock date and time the session started
+    duration    length as in record files, e.g. \"45m\"; or give `end`
+    end         wall-clock date and time the session ended
+    tags        tag names; spaces and brackets become hyphens (optional)
+    detail      detail of the first tag (optional)
+
+    Sessions starting when one is already recorded on that day are skipped,
+    so the same export can be imported again.
+";
+
+/// Writes the man page of the top-level command to `out`.
+pub fn render(out: &mut impl io::Write) -> io::Result<()> {
+    let man = clap_mangen::Man::new(Cli::command());
+    man.render(out)?;
+    render_syntax_section(out)
+}
+
+/// Writes `learning-record.1` and one page per subcommand into `dir`.
+pub fn write_all(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let command = Cli::command();
+    let mut main_page = fs::File::create(dir.join(format!("{}.1", command.get_name())))?;
+    render(&mut main_page)?;
+
+    for subcommand in command.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        let name = format!("{}-{}", command.get_name(), subcommand.get_name());
+        let subcommand = subcommand.clone().display_name(&name).bin_name(format!(
+            "{} {}",
+            command.get_name(),
+            subcommand.get_name()
+        ));
+        let man = clap_mangen::Man::new(subcommand);
+        man.render(&mut fs::File::create(dir.join(format!("{name}.1")))?)?;
+    }
+
+    Ok(())
+}
+
+fn render_syntax_section(out: &mut impl io::Write) -> io::Result<()> {
+    writeln!(out, ".SH \"FILE FORMAT\"")?;
+    writeln!(out, ".nf")?;
+    for line in SYNTAX.lines() {
+        writeln!(out, "\\&{}", line)?;
+    }
+    writeln!(out, ".fi")
+}
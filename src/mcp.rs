@@ -0,0 +1,222 @@
+use std::io::{self, BufRead, Write};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde_json::{json, Value};
+
+use crate::{
+    error::Error,
+    import::{Entry, Import, NewEvent},
+    query::Query,
+    search::DateRange,
+};
+
+/// Protocol revision answered when the client asks for none.
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+type Search<'a> = dyn Fn(&Query, DateRange) -> Result<Value, Error> + 'a;
+type Add<'a> = dyn Fn(Entry) -> Result<Import, Error> + 'a;
+
+/// What the tools do. Each call reads the record file again.
+pub struct Tools<'a> {
+    /// Report of the week containing the date, today without one, as JSON.
+    pub report: Box<dyn Fn(Option<NaiveDate>) -> Result<String, Error> + 'a>,
+    /// Events with a session matching the query, as JSON.
+    pub search: Box<Search<'a>>,
+    /// Current wall-clock time of the record file.
+    pub now: Box<dyn Fn() -> Result<NaiveDateTime, Error> + 'a>,
+    /// Adds a session; `None` when the server is read-only.
+    pub add: Option<Box<Add<'a>>>,
+}
+
+/// Answers Model Context Protocol requests, one JSON-RPC message per line
+/// of `input`, until it ends.
+pub fn run(tools: &Tools, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle(tools, &message),
+            Err(err) => Some(json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": -32700, "message": err.to_string() },
+            })),
+        };
+        if let Some(response) = response {
+            writeln!(output, "{response}")?;
+            output.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The response to `message`, `None` for notifications.
+fn handle(tools: &Tools, message: &Value) -> Option<Value> {
+    let id = message.get("id")?.clone();
+    let method = message["method"].as_str().unwrap_or_default();
+    let params = &message["params"];
+    tracing::info!(method, "MCP request");
+
+    let result = match method {
+        "initialize" => Ok(json!({
+            "protocolVersion": params["protocolVersion"].as_str().unwrap_or(PROTOCOL_VERSION),
+            "capabilities": { "tools": {} },
+            "serverInfo": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": list(tools) })),
+        "tools/call" => Ok(call(
+            tools,
+            params["name"].as_str().unwrap_or_default(),
+            &params["arguments"],
+        )),
+        _ => Err((-32601, format!("unknown method `{method}`"))),
+    };
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }),
+    })
+}
+
+fn list(tools: &Tools) -> Vec<Value> {
+    let date = json!({ "type": "string", "format": "date" });
+    let mut list = vec![
+        json!({
+            "name": "get_weekly_report",
+            "description": "Time spent per tag in the week containing a date, \
+                with the weekly goal and plan when set.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "date": {
+                        "type": "string",
+                        "format": "date",
+                        "description": "Day in the week, YYYY-MM-DD; today when absent",
+                    },
+                },
+            },
+        }),
+        json!({
+            "name": "search_events",
+            "description": "Events with a session matching a query such as \
+                `tag:rust after:2024-01-01 dur>1h`; terms must all match, `or` \
+                separates alternatives and `-` negates a term.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "from": date,
+                    "to": date,
+                },
+                "required": ["query"],
+            },
+        }),
+    ];
+    if tools.add.is_some() {
+        list.push(json!({
+            "name": "add_event",
+            "description": "Record a learning session. It can be undone with \
+                `learning-record undo`.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "duration": {
+                        "type": "string",
+                        "description": "Length such as 1h30m or 45m",
+                    },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "detail": {
+                        "type": "string",
+                        "description": "Detail of the first tag",
+                    },
+                    "start": {
+                        "type": "string",
+                        "description": "Local start as YYYY-MM-DDTHH:MM:SS; \
+                            the duration before now when absent",
+                    },
+                },
+                "required": ["duration"],
+            },
+        }));
+    }
+
+    list
+}
+
+/// Runs a tool; failures are reported to the model as tool errors.
+fn call(tools: &Tools, name: &str, arguments: &Value) -> Value {
+    let result = match name {
+        "get_weekly_report" => report(tools, arguments),
+        "search_events" => search(tools, arguments),
+        "add_event" => add(tools, arguments),
+        _ => Err(format!("unknown tool `{name}`")),
+    };
+    let (text, is_error) = match result {
+        Ok(text) => (text, false),
+        Err(message) => (message, true),
+    };
+
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error,
+    })
+}
+
+fn report(tools: &Tools, arguments: &Value) -> Result<String, String> {
+    let date = date(arguments, "date")?;
+    (tools.report)(date).map_err(|err| err.to_string())
+}
+
+fn search(tools: &Tools, arguments: &Value) -> Result<String, String> {
+    let query: Query = arguments["query"]
+        .as_str()
+        .ok_or("`query` is required")?
+        .parse()
+        .map_err(|err: crate::query::Error| err.to_string())?;
+    let range = DateRange {
+        from: date(arguments, "from")?,
+        to: date(arguments, "to")?,
+    };
+    let hits = (tools.search)(&query, range).map_err(|err| err.to_string())?;
+
+    Ok(hits.to_string())
+}
+
+fn add(tools: &Tools, arguments: &Value) -> Result<String, String> {
+    let add = tools.add.as_ref().ok_or("the server is read-only")?;
+    let event: NewEvent =
+        serde_json::from_value(arguments.clone()).map_err(|err| err.to_string())?;
+    let now = (tools.now)().map_err(|err| err.to_string())?;
+    let import = add(event.entry(now)?).map_err(|err| err.to_string())?;
+
+    match import.added.first() {
+        Some(entry) => Ok(format!(
+            "added `{}` on {}",
+            entry.line(),
+            entry.date.format("%Y-%m-%d")
+        )),
+        None => Err("a session already starts at that time".to_string()),
+    }
+}
+
+fn date(arguments: &Value, key: &str) -> Result<Option<NaiveDate>, String> {
+    match &arguments[key] {
+        Value::Null => Ok(None),
+        Value::String(date) => date
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("`{key}` must be a date like 2024-09-15")),
+        _ => Err(format!("`{key}` must be a string")),
+    }
+}
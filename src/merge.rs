@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::{ast, import};
+
+/// Sessions of both files starting at the same time in different events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub date: NaiveDate,
+    pub time: NaiveTime,
+    /// Event line kept, from the first file.
+    pub ours: String,
+    /// Event line left out, from the second file.
+    pub theirs: String,
+}
+
+/// Outcome of merging a record file into another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merge {
+    pub content: String,
+    /// Events of the second file added to the first.
+    pub added: usize,
+    pub conflicts: Vec<Conflict>,
+    /// Whether the front matters differ; those of the first file are kept.
+    pub settings_differ: bool,
+}
+
+/// Adds the events of `theirs` to `ours`, parsed from `ours_source` and
+/// `theirs_source`. Day records of dates only in `theirs` are added whole
+/// and events of dates in both are merged: events written the same way in
+/// both are kept once, and those with a session starting when one of
+/// `ours` does are left out as conflicts.
+///
+/// Records included with `@include` are left as they are.
+pub fn merge(
+    ours_source: &str,
+    ours: &ast::File,
+    theirs_source: &str,
+    theirs: &ast::File,
+) -> Merge {
+    let ours_lines: Vec<&str> = ours_source.lines().collect();
+    let theirs_lines: Vec<&str> = theirs_source.lines().collect();
+    // Text and session starts of the events of each day.
+    let mut days: BTreeMap<NaiveDate, Vec<(String, Vec<NaiveTime>)>> = BTreeMap::new();
+    for record in ours.records.iter().filter(|record| record.origin.is_none()) {
+        let events = days.entry(record.date).or_default();
+        for event in &record.events {
+            events.push((line(&ours_lines, event), starts(event)));
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut conflicts = Vec::new();
+    for record in theirs
+        .records
+        .iter()
+        .filter(|record| record.origin.is_none())
+    {
+        let events = days.entry(record.date).or_default();
+        for event in &record.events {
+            let text = line(&theirs_lines, event);
+            if events.iter().any(|(ours, _)| *ours == text) {
+                continue;
+            }
+            let starts = starts(event);
+            let clash = events.iter().find_map(|(ours, times)| {
+                let time = starts.iter().find(|time| times.contains(time))?;
+                Some((ours, *time))
+            });
+            if let Some((ours, time)) = clash {
+                conflicts.push(Conflict {
+                    date: record.date,
+                    time,
+                    ours: ours.clone(),
+                    theirs: text,
+                });
+                continue;
+            }
+            lines.push((record.date, text.clone()));
+            events.push((text, starts));
+        }
+    }
+
+    Merge {
+        content: import::insert_lines(ours_source, ours, &lines),
+        added: lines.len(),
+        conflicts,
+        settings_differ: ours.settings != theirs.settings,
+    }
+}
+
+/// The line of `event`, without surrounding whitespace.
+fn line(lines: &[&str], event: &ast::Event) -> String {
+    lines
+        .get(event.line - 1)
+        .map_or("", |line| line.trim())
+        .to_string()
+}
+
+fn starts(event: &ast::Event) -> Vec<NaiveTime> {
+    event.info.iter().filter_map(|info| info.time).collect()
+}
@@ -0,0 +1,114 @@
+use serde::Deserialize as _;
+
+use crate::{
+    parser::{self, ParseErrorKind},
+    settings::{Settings, VERSION},
+};
+
+/// Upgrades a settings table by one version; the entry at index `i` takes
+/// version `i + 1` to `i + 2`.
+const MIGRATIONS: &[fn(&mut toml::Table)] = &[];
+
+/// Outcome of migrating a record file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    pub from: u32,
+    pub content: String,
+}
+
+/// Brings every settings block of `source` to the current schema version and
+/// stamps the version into the front matter.
+///
+/// Returns `None` when the file has no front matter or is already current.
+pub fn migrate(source: &str) -> Result<Option<Migration>, ParseErrorKind> {
+    let mut lines: Vec<String> = source.lines().map(str::to_string).collect();
+    let blocks = blocks(&lines);
+    let Some(&(front_start, front_end)) = blocks
+        .first()
+        .filter(|(start, _)| lines[..*start].iter().all(|line| line.trim().is_empty()))
+    else {
+        return Ok(None);
+    };
+
+    let front = parser::parse_settings_table(&lines[front_start + 1..front_end].join("\n"))?;
+    let from = match front.get("version") {
+        // Rejects versions newer than this build.
+        Some(_) => Settings::deserialize(front.clone())
+            .map_err(ParseErrorKind::TomlError)?
+            .version
+            .unwrap_or(1)
+            .max(1),
+        None => 1,
+    };
+    let stamped = front.contains_key("version");
+    if from == VERSION && stamped {
+        return Ok(None);
+    }
+
+    // Later blocks first, so that earlier line numbers stay valid.
+    for &(start, end) in blocks.iter().rev() {
+        let body = lines[start + 1..end].join("\n");
+        let yaml = parser::is_yaml(&body);
+        let mut table = parser::parse_settings_table(&body)?;
+        let pending = &MIGRATIONS[(from as usize - 1).min(MIGRATIONS.len())..];
+        let is_front = start == front_start;
+
+        let mut replacement: Vec<String> = if pending.is_empty() {
+            lines[start + 1..end].to_vec()
+        } else {
+            for migration in pending {
+                migration(&mut table);
+            }
+            table.remove("version");
+            serialize(&table, yaml)
+                .lines()
+                .map(str::to_string)
+                .collect()
+        };
+        if is_front {
+            replacement.retain(|line| !is_version_line(line));
+            let version = if yaml {
+                format!("version: {VERSION}")
+            } else {
+                format!("version = {VERSION}")
+            };
+            replacement.insert(0, version);
+        }
+        lines.splice(start + 1..end, replacement);
+    }
+
+    let mut content = lines.join("\n");
+    if source.ends_with('\n') {
+        content.push('\n');
+    }
+
+    Ok(Some(Migration { from, content }))
+}
+
+/// Line ranges of the `---` fences of each settings block.
+fn blocks(lines: &[String]) -> Vec<(usize, usize)> {
+    let fences: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.as_str() == "---")
+        .map(|(index, _)| index)
+        .collect();
+
+    fences
+        .chunks_exact(2)
+        .map(|pair| (pair[0], pair[1]))
+        .collect()
+}
+
+fn is_version_line(line: &str) -> bool {
+    line.strip_prefix("version")
+        .is_some_and(|rest| matches!(rest.trim_start().chars().next(), Some('=' | ':')))
+}
+
+fn serialize(table: &toml::Table, yaml: bool) -> String {
+    if yaml {
+        serde_yaml::to_string(table).expect("settings are always serializable")
+    } else {
+        toml::to_string(table).expect("settings are always serializable")
+    }
+}
@@ -0,0 +1,125 @@
+use chrono::{NaiveDateTime, TimeDelta};
+
+use crate::import::{self, Entry};
+
+/// A headline with its level and tags.
+struct Headline {
+    level: usize,
+    title: String,
+    tags: Vec<String>,
+}
+
+/// Reads the closed `CLOCK:` lines of an Org document. Sessions are tagged
+/// with the tags of their headline and its ancestors, or the title of their
+/// headline when there are none.
+pub fn entries(source: &str) -> Vec<Entry> {
+    let mut headlines: Vec<Headline> = Vec::new();
+    let mut entries = Vec::new();
+    for line in source.lines() {
+        if let Some(headline) = headline(line) {
+            while headlines
+                .last()
+                .is_some_and(|last| last.level >= headline.level)
+            {
+                headlines.pop();
+            }
+            headlines.push(headline);
+            continue;
+        }
+
+        let Some((start, end)) = clock(line) else {
+            continue;
+        };
+        let duration = end - start;
+        if duration <= TimeDelta::zero() {
+            continue;
+        }
+        let mut tags: Vec<(String, Option<String>)> = Vec::new();
+        for tag in headlines.iter().flat_map(|headline| &headline.tags) {
+            let tag = import::tag(tag);
+            if !tag.is_empty() && !tags.iter().any(|(title, _)| *title == tag) {
+                tags.push((tag, None));
+            }
+        }
+        if tags.is_empty() {
+            let title = headlines
+                .last()
+                .map(|headline| import::tag(&headline.title))
+                .unwrap_or_default();
+            if !title.is_empty() {
+                tags.push((title, None));
+            }
+        }
+
+        entries.push(Entry {
+            date: start.date(),
+            time: start.time(),
+            duration,
+            tags,
+        });
+    }
+
+    entries
+}
+
+/// `** TODO Read chapter 4 :rust:book:`, dropping the keyword and priority.
+fn headline(line: &str) -> Option<Headline> {
+    let level = line.chars().take_while(|c| *c == '*').count();
+    let rest = line[level..].strip_prefix(' ')?;
+    if level == 0 {
+        return None;
+    }
+
+    let mut title = rest.trim();
+    let mut tags = Vec::new();
+    if let Some((before, last)) = title.rsplit_once(char::is_whitespace) {
+        if last.len() > 1 && last.starts_with(':') && last.ends_with(':') {
+            tags = last
+                .split(':')
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect();
+            title = before.trim_end();
+        }
+    }
+    let mut words = title.split_whitespace().peekable();
+    if words
+        .peek()
+        .is_some_and(|word| word.len() > 1 && word.chars().all(|c| c.is_ascii_uppercase()))
+    {
+        words.next();
+    }
+    if words
+        .peek()
+        .is_some_and(|word| word.starts_with("[#") && word.ends_with(']'))
+    {
+        words.next();
+    }
+
+    Some(Headline {
+        level,
+        title: words.collect::<Vec<_>>().join(" "),
+        tags,
+    })
+}
+
+/// Start and end of `CLOCK: [2024-09-10 Tue 21:00]--[2024-09-10 Tue 22:30] =>  1:30`.
+fn clock(line: &str) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let rest = line.trim_start().strip_prefix("CLOCK:")?.trim_start();
+    let (start, rest) = timestamp(rest)?;
+    let (end, _) = timestamp(rest.strip_prefix("--")?)?;
+
+    Some((start, end))
+}
+
+/// An inactive timestamp such as `[2024-09-10 Tue 21:00]` and what follows.
+fn timestamp(text: &str) -> Option<(NaiveDateTime, &str)> {
+    let (inner, rest) = text.strip_prefix('[')?.split_once(']')?;
+    let mut parts = inner.split_whitespace();
+    let date = parts.next()?;
+    let time = parts.find(|part| part.contains(':'))?;
+    let datetime =
+        NaiveDateTime::parse_from_str(&format!("{date} {time}"), "%Y-%m-%d %H:%M").ok()?;
+
+    Some((datetime, rest))
+}
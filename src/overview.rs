@@ -0,0 +1,66 @@
+use std::collections::BTreeSet;
+
+use chrono::NaiveDate;
+
+use crate::ast;
+
+/// What a record file holds, for `summary`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overview {
+    /// Dates of the first and last day record.
+    pub first: Option<NaiveDate>,
+    pub last: Option<NaiveDate>,
+    pub records: usize,
+    /// Days with at least one event.
+    pub days: usize,
+    /// Distinct tags.
+    pub tags: usize,
+    /// Size of the record file itself, without included files.
+    pub bytes: u64,
+}
+
+impl Overview {
+    /// Calendar days from the first day record to the last.
+    pub fn span(&self) -> i64 {
+        match (self.first, self.last) {
+            (Some(first), Some(last)) => (last - first).num_days() + 1,
+            _ => 0,
+        }
+    }
+
+    /// Share of the days of `span` with events, in percent.
+    pub fn coverage(&self) -> f64 {
+        match self.span() {
+            0 => 0.0,
+            span => self.days as f64 * 100.0 / span as f64,
+        }
+    }
+}
+
+/// Overview of `file`, whose record file is `bytes` long. Included records
+/// count too.
+pub fn overview(file: &ast::File, bytes: u64) -> Overview {
+    let dates = file.records.iter().map(|record| record.date);
+    let days: BTreeSet<NaiveDate> = file
+        .records
+        .iter()
+        .filter(|record| !record.events.is_empty())
+        .map(|record| record.date)
+        .collect();
+    let tags: BTreeSet<&str> = file
+        .records
+        .iter()
+        .flat_map(|record| &record.events)
+        .flat_map(|event| event.tags.iter().flat_map(|tags| &tags.tags))
+        .map(|tag| tag.title.as_str())
+        .collect();
+
+    Overview {
+        first: dates.clone().min(),
+        last: dates.max(),
+        records: file.records.len(),
+        days: days.len(),
+        tags: tags.len(),
+        bytes,
+    }
+}
@@ -0,0 +1,53 @@
+use std::{
+    io::{self, IsTerminal as _, Write as _},
+    process::{Command, Stdio},
+};
+
+use crate::error::Error;
+
+/// Pager run when `PAGER` is not set.
+const DEFAULT_PAGER: &str = "less";
+
+/// Prints `text` to standard output, through `$PAGER` when `enabled` and
+/// standard output is a terminal.
+///
+/// Like git, `LESS` defaults to `FRX` so that less quits by itself when the
+/// text fits on one screen and keeps the colors. An empty `PAGER` or `cat`
+/// prints directly, as does a pager that cannot be started.
+pub fn page(text: &str, enabled: bool) -> Result<(), Error> {
+    if !enabled || !io::stdout().is_terminal() {
+        println!("{text}");
+        return Ok(());
+    }
+
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.to_string());
+    let mut words = pager.split_whitespace();
+    let Some(program) = words.next().filter(|program| *program != "cat") else {
+        println!("{text}");
+        return Ok(());
+    };
+    let mut command = Command::new(program);
+    command.args(words).stdin(Stdio::piped());
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(err) => {
+            tracing::warn!(%err, pager, "could not start the pager");
+            println!("{text}");
+            return Ok(());
+        }
+    };
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    match writeln!(stdin, "{text}") {
+        // The pager was quit before reading everything.
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {}
+        result => result?,
+    }
+    drop(stdin);
+    child.wait()?;
+
+    Ok(())
+}
@@ -1,8 +1,11 @@
 use chrono::{NaiveDate, NaiveTime, TimeDelta};
 
+use serde::Deserialize as _;
+use unicode_normalization::UnicodeNormalization as _;
+
 use crate::{
-    ast::{DayRecord, Event, EventInfo, File, Tag, Tags},
-    settings::Settings,
+    ast::{DayRecord, Event, EventInfo, File, Include, SettingsChange, Tag, Tags},
+    settings::{self, Settings},
 };
 
 pub type Result<T> = std::result::Result<T, ParseError>;
@@ -22,11 +25,186 @@ impl ParseError {
 
 #[derive(Debug, Clone)]
 pub enum ParseErrorKind {
-    ExpectedChars { expected: Vec<char>, found: char },
+    ExpectedChars {
+        expected: Vec<char>,
+        found: char,
+    },
     UnexpectedEof,
     InvalidDate,
     InvalidDurationFormat,
+    InvalidTime,
+    MissingIncludePath,
+    /// Spaces at the end of a line, or on a line of their own.
+    TrailingWhitespace,
+    /// An empty line between the events of a day record.
+    BlankLineInRecord,
+    /// More than one empty line between day records.
+    ExtraBlankLines,
+    /// The last line does not end with a newline.
+    MissingFinalNewline,
     TomlError(toml::de::Error),
+    YamlError(String),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.kind)
+    }
+}
+
+impl std::fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::ExpectedChars { expected, found } => {
+                write!(f, "expected one of {:?}, found {:?}", expected, found)
+            }
+            ParseErrorKind::UnexpectedEof => write!(f, "unexpected end of file"),
+            ParseErrorKind::InvalidDate => write!(f, "invalid date"),
+            ParseErrorKind::InvalidDurationFormat => write!(f, "invalid duration format"),
+            ParseErrorKind::InvalidTime => write!(f, "invalid time"),
+            ParseErrorKind::MissingIncludePath => write!(f, "expected a path after @include"),
+            ParseErrorKind::TrailingWhitespace => write!(f, "trailing whitespace"),
+            ParseErrorKind::BlankLineInRecord => write!(f, "blank line inside a day record"),
+            ParseErrorKind::ExtraBlankLines => write!(f, "more than one blank line"),
+            ParseErrorKind::MissingFinalNewline => write!(f, "no newline at the end of the file"),
+            ParseErrorKind::TomlError(err) => write!(f, "invalid settings: {}", err),
+            ParseErrorKind::YamlError(err) => write!(f, "invalid settings: {}", err),
+        }
+    }
+}
+
+/// Parses a standalone duration such as `1h30m`, e.g. from settings.
+pub fn parse_duration(source: &str) -> Result<TimeDelta> {
+    let mut parser = Parser::new(source.trim().chars().collect());
+    let duration = parser.parse_duration()?;
+
+    if parser.peek().is_some() {
+        return Err(parser.make_error(ParseErrorKind::InvalidDurationFormat));
+    }
+
+    Ok(duration)
+}
+
+/// Parses settings in TOML or YAML, e.g. from a standalone settings file.
+pub fn parse_settings_table(source: &str) -> std::result::Result<toml::Table, ParseErrorKind> {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    if is_yaml(source) {
+        serde_yaml::from_str::<Option<toml::Table>>(source)
+            .map(Option::unwrap_or_default)
+            .map_err(|e| ParseErrorKind::YamlError(e.to_string()))
+    } else {
+        source.parse().map_err(ParseErrorKind::TomlError)
+    }
+}
+
+/// Keeps the content of the ```` ```learning-record ```` fenced blocks of a
+/// Markdown document, such as a daily note, and turns every other line
+/// into a comment of the same length, so that lines and positions still
+/// point into the document.
+pub fn extract_markdown(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    for (line, fence) in markdown_lines(source) {
+        if fence == Fence::Inside {
+            out += line;
+        } else {
+            let content = line.trim_end_matches(['\n', '\r']);
+            let length = content.chars().count();
+            if length > 0 {
+                out.push('#');
+                out.extend(std::iter::repeat_n(' ', length - 1));
+            }
+            out += &line[content.len()..];
+        }
+    }
+
+    out
+}
+
+/// Line of the fence closing the last ```` ```learning-record ```` block
+/// of a Markdown document, numbered from 1; `None` without a closed block.
+pub fn markdown_block_end(source: &str) -> Option<usize> {
+    markdown_lines(source)
+        .enumerate()
+        .filter(|(_, (_, fence))| *fence == Fence::Close)
+        .map(|(index, _)| index + 1)
+        .last()
+}
+
+/// Where a line of a Markdown document is relative to the record blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fence {
+    Outside,
+    Inside,
+    /// The fence ending a record block.
+    Close,
+}
+
+/// Each line of `source` with its line ending.
+fn markdown_lines(source: &str) -> impl Iterator<Item = (&str, Fence)> {
+    // Character and length of the fence of the block we are in.
+    let mut fence: Option<(char, usize)> = None;
+    source.split_inclusive('\n').map(move |line| {
+        let trimmed = line.trim();
+        let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        let length = marker.map_or(0, |marker| {
+            trimmed.chars().take_while(|c| *c == marker).count()
+        });
+        let position = match (fence, marker) {
+            (Some((open, open_length)), Some(marker))
+                if marker == open && length >= open_length && length == trimmed.len() =>
+            {
+                fence = None;
+                Fence::Close
+            }
+            (Some(_), _) => Fence::Inside,
+            (None, Some(marker)) => {
+                if length >= 3 && trimmed[length..].trim() == "learning-record" {
+                    fence = Some((marker, length));
+                }
+                Fence::Outside
+            }
+            (None, None) => Fence::Outside,
+        };
+
+        (line, position)
+    })
+}
+
+/// The ASCII form of a full-width character such as `２` or `：`, and a
+/// space for the ideographic space.
+fn fold_width(c: char) -> char {
+    match c {
+        '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+}
+
+/// Front matter is YAML when its first key is followed by `:` rather than `=`.
+pub fn is_yaml(source: &str) -> bool {
+    source
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .is_some_and(|line| {
+            !line.starts_with('[')
+                && line
+                    .find(':')
+                    .is_some_and(|colon| line.find('=').is_none_or(|equals| colon < equals))
+        })
+}
+
+/// How minor formatting issues, such as trailing spaces, are treated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Accepts all of them, recording warnings.
+    Lenient,
+    /// Accepts those that do not change how the file reads, silently, and
+    /// rejects blank lines inside day records.
+    #[default]
+    Normal,
+    /// Rejects all of them.
+    Strict,
 }
 
 #[derive(Debug, Clone)]
@@ -38,98 +216,265 @@ pub struct Parser {
 
     line: usize,
     column: usize,
+
+    /// Length of a pomodoro under the settings read so far.
+    pomodoro: TimeDelta,
+
+    /// Format of the dates of day records, from the settings read so far.
+    date_format: Option<String>,
+
+    /// The source as given, kept while `normalize_input` folds the rest of
+    /// `source` to ASCII.
+    unfolded: Option<Vec<char>>,
+
+    strictness: Strictness,
+    /// Issues accepted under [`Strictness::Lenient`].
+    warnings: Vec<ParseError>,
 }
 
 impl Parser {
+    /// A parser of `source`, which may start with a byte order mark and
+    /// use CRLF line endings. Positions count the characters of `source`
+    /// as given.
     pub fn new(source: Vec<char>) -> Self {
+        let bom = usize::from(source.first() == Some(&'\u{feff}'));
         Self {
             source,
-            start: 0,
-            current: 0,
+            start: bom,
+            current: bom,
             line: 1,
             column: 1,
+            pomodoro: settings::DEFAULT_POMODORO,
+            date_format: None,
+            unfolded: None,
+            strictness: Strictness::default(),
+            warnings: Vec::new(),
         }
     }
 
+    pub fn with_strictness(self, strictness: Strictness) -> Self {
+        Self { strictness, ..self }
+    }
+
+    /// Minor issues accepted so far; only recorded when lenient.
+    pub fn warnings(&self) -> &[ParseError] {
+        &self.warnings
+    }
+
     pub fn parse_file(&mut self) -> Result<File> {
         self.skip_space();
-        let settings = if self.peek() == Some('-') {
+        self.skip_blank_lines();
+        let mut table = if self.peek() == Some('-') {
             Some(self.parse_settings()?)
         } else {
             None
         };
+        let settings = table
+            .as_ref()
+            .map(|table| self.deserialize_settings(table.clone()))
+            .transpose()?;
 
-        while matches!(self.peek(), Some('\n' | '\r')) {
-            self.advance();
-        }
-        self.clear();
+        self.skip_blank_lines();
 
         let mut records: Vec<DayRecord> = Vec::new();
-        while self.peek().is_some() {
-            records.push(self.parse_day_record()?);
-
-            while matches!(self.peek(), Some('\n' | '\r')) {
-                self.advance();
+        let mut changes = Vec::new();
+        let mut includes = Vec::new();
+        let mut pending = None;
+        while let Some(c) = self.peek() {
+            if c == '@' {
+                includes.push(self.parse_include()?);
+            } else if c == '-' {
+                let line = self.line;
+                let overrides = self.parse_settings()?;
+                let table = table.get_or_insert_with(toml::Table::new);
+                settings::merge(table, overrides);
+                pending = Some((line, self.deserialize_settings(table.clone())?));
+            } else {
+                let record = self.parse_day_record()?;
+                if let Some((line, settings)) = pending.take() {
+                    changes.push(SettingsChange {
+                        from: record.date,
+                        line,
+                        settings,
+                    });
+                }
+                records.push(record);
+                let line = self.line;
+                if self.skip_blank_lines() > 0 {
+                    self.warn(ParseError::new(ParseErrorKind::ExtraBlankLines, line, 1))?;
+                }
+                continue;
             }
-            self.clear();
+            self.skip_blank_lines();
+        }
+        if self.source.last().is_some_and(|c| *c != '\n') {
+            self.warn(self.make_error(ParseErrorKind::MissingFinalNewline))?;
+        }
+
+        Ok(File {
+            records,
+            settings,
+            changes,
+            includes,
+        })
+    }
+
+    fn parse_include(&mut self) -> Result<Include> {
+        let line = self.line;
+        self.expect_string("@include")?;
+        self.skip_space();
+        self.extract_until('\n');
+        let path = self.collect().unwrap_or_default();
+        let path = path.trim();
+        if path.is_empty() {
+            return Err(self.make_error(ParseErrorKind::MissingIncludePath));
         }
+        self.advance();
+        self.clear();
 
-        Ok(File { records, settings })
+        Ok(Include {
+            path: path.into(),
+            line,
+        })
     }
 
-    fn parse_settings(&mut self) -> Result<Settings> {
+    /// Reads the TOML or YAML between two `---` lines.
+    fn parse_settings(&mut self) -> Result<toml::Table> {
         self.expect_string("---\n")?;
         self.clear();
         while self.peek().is_some() {
+            if self.at_fence() {
+                let source = self.collect().unwrap_or_default();
+                self.expect_string("---")?;
+                if self.peek().is_some() {
+                    self.expect_char('\n')?;
+                }
+                return self.parse_front_matter(&source);
+            }
             self.extract_until('\n');
             self.expect_char('\n')?;
-            if self.peek() == Some('-') {
-                let Some(toml) = self.collect() else {
-                    return Err(self.make_error(ParseErrorKind::UnexpectedEof));
-                };
-                self.expect_string("---\n")?;
-                let settings: Settings = match toml::from_str(&toml) {
-                    Ok(settings) => settings,
-                    Err(e) => return Err(self.make_error(ParseErrorKind::TomlError(e))),
-                };
-                return Ok(settings);
-            }
         }
 
         Err(self.make_error(ParseErrorKind::UnexpectedEof))
     }
 
+    fn parse_front_matter(&self, source: &str) -> Result<toml::Table> {
+        parse_settings_table(source).map_err(|kind| self.make_error(kind))
+    }
+
+    /// Whether a line consisting of `---` starts here.
+    fn at_fence(&self) -> bool {
+        let rest = &self.source[self.current.min(self.source.len())..];
+        rest.starts_with(&['-', '-', '-'])
+            && matches!(rest.get(3..), Some([] | ['\n', ..] | ['\r', '\n', ..]))
+    }
+
+    fn deserialize_settings(&mut self, table: toml::Table) -> Result<Settings> {
+        let settings = Settings::deserialize(table)
+            .map_err(|e| self.make_error(ParseErrorKind::TomlError(e)))?;
+        self.pomodoro = settings
+            .pomodoro
+            .map_or(settings::DEFAULT_POMODORO, |pomodoro| pomodoro.0);
+        self.date_format = settings.date_format.clone();
+        self.set_folding(settings.normalize_input);
+
+        Ok(settings)
+    }
+
+    /// Folds the rest of the source to ASCII, or restores it. Characters
+    /// are replaced one for one, so positions stay the same.
+    fn set_folding(&mut self, enabled: bool) {
+        let rest = self.current.min(self.source.len())..;
+        match (enabled, &self.unfolded) {
+            (true, None) => {
+                self.unfolded = Some(self.source.clone());
+                for c in &mut self.source[rest] {
+                    *c = fold_width(*c);
+                }
+            }
+            (false, Some(unfolded)) => {
+                self.source[rest.clone()].copy_from_slice(&unfolded[rest]);
+                self.unfolded = None;
+            }
+            _ => {}
+        }
+    }
+
     fn parse_day_record(&mut self) -> Result<DayRecord> {
+        let line = self.line;
         let date = self.parse_date()?;
 
-        self.skip_space();
-        self.expect_char('\n')?;
+        self.skip_trailing_space()?;
+        if self.peek().is_some() {
+            self.expect_char('\n')?;
+        }
         self.clear();
         let mut events = Vec::new();
 
-        while let Some(c) = self.peek() {
-            if c == '\n' {
-                self.advance();
-                self.clear();
-                break;
+        while self.peek().is_some() {
+            if self.at_blank_line() {
+                // A blank line ends the record, unless events follow it.
+                if !self.event_after_blank_lines() {
+                    self.skip_line()?;
+                    break;
+                }
+                self.tolerate(self.make_error(ParseErrorKind::BlankLineInRecord))?;
+                while self.peek().is_some() && self.at_blank_line() {
+                    self.skip_line()?;
+                }
+                continue;
+            }
+            // Events and comments may be indented with spaces or tabs.
+            self.skip_space();
+            if self.peek() == Some('#') {
+                self.skip_comment();
             } else {
                 events.push(self.parse_event()?);
             }
         }
 
-        Ok(DayRecord { date, events })
+        Ok(DayRecord {
+            date,
+            events,
+            line,
+            origin: None,
+        })
     }
 
+    /// A date as given by the `date_format` setting, or as year, month and
+    /// day separated by `-`, `/` or `.`.
     fn parse_date(&mut self) -> Result<NaiveDate> {
-        let year: i32 = self.extract_num()?.parse().expect("failed to parse year");
-        self.expect_char('-')?;
+        if let Some(format) = &self.date_format {
+            let line: String = self.source[self.current.min(self.source.len())..]
+                .iter()
+                .take_while(|c| **c != '\n')
+                .collect();
+            let text = line.trim_end();
+            if let Ok(date) = NaiveDate::parse_from_str(text, format) {
+                for _ in text.chars() {
+                    self.advance();
+                }
+                self.clear();
+                return Ok(date);
+            }
+        }
+
+        let Ok(year) = self.extract_num()?.parse::<i32>() else {
+            return Err(self.make_error(ParseErrorKind::InvalidDate));
+        };
+        let separator = self.peek();
+        self.expect_chars(['-', '/', '.'])?;
         self.clear();
 
-        let month: u32 = self.extract_num()?.parse().expect("failed to parse month");
-        self.expect_char('-')?;
+        let Ok(month) = self.extract_num()?.parse::<u32>() else {
+            return Err(self.make_error(ParseErrorKind::InvalidDate));
+        };
+        self.expect_char(separator.unwrap_or('-'))?;
         self.clear();
 
-        let day: u32 = self.extract_num()?.parse().expect("failed to parse day");
+        let Ok(day) = self.extract_num()?.parse::<u32>() else {
+            return Err(self.make_error(ParseErrorKind::InvalidDate));
+        };
         self.clear();
 
         let Some(date) = NaiveDate::from_ymd_opt(year, month, day) else {
@@ -140,12 +485,23 @@ impl Parser {
     }
 
     fn parse_event(&mut self) -> Result<Event> {
+        let line = self.line;
         let tags = if Some('[') == self.peek() {
             let tags = self.parse_tags()?;
             Some(tags)
         } else {
             None
         };
+        self.skip_space();
+        let estimate = if Some('~') == self.peek() {
+            self.advance();
+            self.clear();
+            let estimate = self.parse_duration()?;
+            self.skip_space();
+            Some(estimate)
+        } else {
+            None
+        };
 
         let mut info = Vec::new();
         while let Some(c) = self.peek() {
@@ -157,14 +513,19 @@ impl Parser {
 
             self.skip_space();
             info.push(self.parse_event_info()?);
-            self.skip_space();
+            self.skip_trailing_space()?;
             if Some(',') == self.peek() {
                 self.advance();
                 self.clear();
             }
         }
 
-        Ok(Event { tags, info })
+        Ok(Event {
+            tags,
+            estimate,
+            info,
+            line,
+        })
     }
 
     fn parse_tags(&mut self) -> Result<Tags> {
@@ -172,12 +533,13 @@ impl Parser {
         self.clear();
         let mut tags = Vec::new();
 
-        while matches!(self.peek(), Some(c) if c != ']') {
+        self.skip_space();
+        // An unclosed `[` ends at the line end rather than reading empty tags.
+        while matches!(self.peek(), Some(c) if c != ']' && c != '\n') {
             tags.push(self.parse_tag()?);
+            self.skip_space();
         }
 
-        self.skip_space();
-
         self.expect_char(']')?;
         self.clear();
 
@@ -194,6 +556,8 @@ impl Parser {
             self.advance();
         }
 
+        let start = self.start;
+        let title_end = self.current;
         let Some(tag) = self.collect() else {
             return Err(self.make_error(ParseErrorKind::UnexpectedEof));
         };
@@ -217,27 +581,159 @@ impl Parser {
             None
         };
 
-        Ok(Tag { title: tag, detail })
+        let (tag, detail) = if self.unfolded.is_some() {
+            let nfkc = |text: String| text.nfkc().collect::<String>();
+            (nfkc(tag), detail.map(nfkc))
+        } else {
+            (tag, detail)
+        };
+
+        Ok(Tag {
+            title: tag,
+            detail,
+            span: start..self.current,
+            title_span: start..title_end,
+        })
     }
 
     fn parse_event_info(&mut self) -> Result<EventInfo> {
-        // time
+        // A correction such as `!-30m` takes time off the tags of its event.
+        if self.peek() == Some('!') {
+            self.advance();
+            self.expect_char('-')?;
+            self.clear();
+            return Ok(EventInfo {
+                time: None,
+                duration: -self.parse_duration()?,
+                pomodoros: None,
+            });
+        }
+
+        let time = if self.at_time() {
+            Some(self.parse_start()?)
+        } else {
+            None
+        };
+
+        // A duration alone may be written with or without the hyphen.
+        self.skip_space();
+        if time.is_some() || self.peek() == Some('-') {
+            self.expect_char('-')?;
+            self.skip_space();
+        }
+        self.clear();
+
+        let pomodoros = self.parse_pomodoros();
+        let duration = match pomodoros {
+            Some(count) => i32::try_from(count)
+                .ok()
+                .and_then(|count| self.pomodoro.checked_mul(count))
+                .ok_or_else(|| self.make_error(ParseErrorKind::InvalidDurationFormat))?,
+            None => self.parse_duration()?,
+        };
+
+        Ok(EventInfo {
+            time,
+            duration,
+            pomodoros,
+        })
+    }
+
+    /// Whether a start time such as `21:00` comes next, rather than a
+    /// duration.
+    fn at_time(&self) -> bool {
+        let rest = &self.source[self.current.min(self.source.len())..];
+        let digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+        digits > 0 && rest.get(digits) == Some(&':')
+    }
+
+    fn parse_start(&mut self) -> Result<NaiveTime> {
         let Ok(date_hours) = self.extract_num()?.parse() else {
-            return Err(self.make_error(ParseErrorKind::UnexpectedEof));
+            return Err(self.make_error(ParseErrorKind::InvalidTime));
         };
 
         self.expect_char(':')?;
         self.clear();
 
         let Ok(date_minutes) = self.extract_num()?.parse() else {
-            return Err(self.make_error(ParseErrorKind::UnexpectedEof));
+            return Err(self.make_error(ParseErrorKind::InvalidTime));
+        };
+
+        let date_seconds = if self.peek() == Some(':') {
+            self.advance();
+            self.clear();
+            let Ok(seconds) = self.extract_num()?.parse() else {
+                return Err(self.make_error(ParseErrorKind::InvalidTime));
+            };
+            seconds
+        } else {
+            0
         };
 
         self.skip_space();
-        self.expect_char('-')?;
-        self.skip_space();
+        let date_hours = match self.parse_meridiem() {
+            Some(pm) => {
+                if !(1..=12).contains(&date_hours) {
+                    return Err(self.make_error(ParseErrorKind::InvalidTime));
+                }
+                date_hours % 12 + if pm { 12 } else { 0 }
+            }
+            None => date_hours,
+        };
+
+        NaiveTime::from_hms_opt(date_hours, date_minutes, date_seconds)
+            .ok_or_else(|| self.make_error(ParseErrorKind::InvalidTime))
+    }
+
+    /// Reads `am` or `pm` in any case, possibly with dots as in `p.m.`,
+    /// returning whether it is `pm`. Consumes nothing if there is neither.
+    fn parse_meridiem(&mut self) -> Option<bool> {
+        let rest = &self.source[self.current.min(self.source.len())..];
+        let pm = match rest.first()?.to_ascii_lowercase() {
+            'a' => false,
+            'p' => true,
+            _ => return None,
+        };
+        let dot = usize::from(rest.get(1) == Some(&'.'));
+        if !rest.get(1 + dot)?.eq_ignore_ascii_case(&'m') {
+            return None;
+        }
+        let mut length = 2 + dot;
+        if dot == 1 && rest.get(length) == Some(&'.') {
+            length += 1;
+        }
+        if rest.get(length).is_some_and(|c| c.is_alphanumeric()) {
+            return None;
+        }
+        for _ in 0..length {
+            self.advance();
+        }
+        self.clear();
+
+        Some(pm)
+    }
+
+    /// Reads a count of pomodoros such as `3p`, consuming nothing if there
+    /// is none.
+    fn parse_pomodoros(&mut self) -> Option<u32> {
+        let digits = self.source[self.current.min(self.source.len())..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        let unit = self.source.get(self.current + digits).copied();
+        let after = self.source.get(self.current + digits + 1).copied();
+        if digits == 0 || unit != Some('p') || after.is_some_and(char::is_alphanumeric) {
+            return None;
+        }
+
+        let count = self.extract_num().ok()?.parse().ok()?;
+        self.advance();
+        self.clear();
+
+        Some(count)
+    }
 
-        // duration
+    fn parse_duration(&mut self) -> Result<TimeDelta> {
         let mut hms: [Option<i64>; 3] = [None, None, None];
         let mut i: usize = 0;
 
@@ -275,15 +771,15 @@ impl Parser {
             return Err(self.make_error(ParseErrorKind::InvalidDurationFormat));
         }
 
-        let Some(time) = NaiveTime::from_hms_opt(date_hours, date_minutes, 0) else {
-            return Err(self.make_error(ParseErrorKind::InvalidDurationFormat));
-        };
-
-        let duration = TimeDelta::hours(hms.first().unwrap().unwrap_or_default())
-            + TimeDelta::minutes(hms.get(1).unwrap().unwrap_or_default())
-            + TimeDelta::seconds(hms.get(2).unwrap().unwrap_or_default());
-
-        Ok(EventInfo { time, duration })
+        let [hours, minutes, seconds] = hms.map(Option::unwrap_or_default);
+        // Out of range amounts such as `9999999999999h` are errors, not panics.
+        TimeDelta::try_hours(hours)
+            .zip(TimeDelta::try_minutes(minutes))
+            .zip(TimeDelta::try_seconds(seconds))
+            .and_then(|((hours, minutes), seconds)| {
+                hours.checked_add(&minutes)?.checked_add(&seconds)
+            })
+            .ok_or_else(|| self.make_error(ParseErrorKind::InvalidDurationFormat))
     }
 
     #[must_use]
@@ -329,6 +825,114 @@ impl Parser {
         }
     }
 
+    /// Skips empty lines and `#` comment lines and returns the number of
+    /// empty lines.
+    fn skip_blank_lines(&mut self) -> usize {
+        let mut blank = 0;
+        loop {
+            match self.peek() {
+                Some('#') => self.skip_comment(),
+                Some(_) if self.at_blank_line() => {
+                    // Whitespace was reported by `skip_line` if it fails.
+                    if self.skip_line().is_err() {
+                        break;
+                    }
+                    blank += 1;
+                }
+                _ => break,
+            }
+        }
+
+        self.clear();
+        blank
+    }
+
+    /// Whether the rest of the line is whitespace.
+    fn at_blank_line(&self) -> bool {
+        self.source[self.current.min(self.source.len())..]
+            .iter()
+            .take_while(|c| **c != '\n')
+            .all(|c| c.is_whitespace())
+    }
+
+    /// Whether the line ends here.
+    fn at_line_end(&self) -> bool {
+        matches!(self.peek(), None | Some('\n'))
+    }
+
+    /// Skips a blank line, warning about whitespace on it.
+    fn skip_line(&mut self) -> Result<()> {
+        if !self.at_line_end() {
+            let warning = self.make_error(ParseErrorKind::TrailingWhitespace);
+            self.warn(warning)?;
+        }
+        self.extract_until('\n');
+        self.advance();
+        self.clear();
+
+        Ok(())
+    }
+
+    /// Skips spaces before the end of a line, warning about them.
+    fn skip_trailing_space(&mut self) -> Result<()> {
+        let start = self.current;
+        let warning = self.make_error(ParseErrorKind::TrailingWhitespace);
+        self.skip_space();
+        if self.current > start && self.at_line_end() {
+            self.warn(warning)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether an event line, rather than a date or something else, follows
+    /// the blank lines starting here.
+    fn event_after_blank_lines(&self) -> bool {
+        let mut rest = &self.source[self.current.min(self.source.len())..];
+        while let Some(end) = rest.iter().position(|c| *c == '\n') {
+            if !rest[..end].iter().all(|c| c.is_whitespace()) {
+                break;
+            }
+            rest = &rest[end + 1..];
+        }
+        let indent = rest.iter().take_while(|c| matches!(c, ' ' | '\t')).count();
+        let rest = &rest[indent..];
+        let digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+        match rest.first() {
+            Some('[' | '~') => true,
+            Some(c) if c.is_ascii_digit() => rest.get(digits) == Some(&':'),
+            _ => false,
+        }
+    }
+
+    /// Records a minor issue that the file reads the same with, unless
+    /// strict.
+    fn warn(&mut self, warning: ParseError) -> Result<()> {
+        match self.strictness {
+            Strictness::Lenient => self.warnings.push(warning),
+            Strictness::Normal => {}
+            Strictness::Strict => return Err(warning),
+        }
+
+        Ok(())
+    }
+
+    /// Accepts an issue that changes how the file reads only when lenient.
+    fn tolerate(&mut self, warning: ParseError) -> Result<()> {
+        if self.strictness != Strictness::Lenient {
+            return Err(warning);
+        }
+        self.warnings.push(warning);
+
+        Ok(())
+    }
+
+    fn skip_comment(&mut self) {
+        self.extract_until('\n');
+        self.advance();
+        self.clear();
+    }
+
     fn skip_space(&mut self) {
         while matches!(self.peek(), Some(c) if c.is_whitespace() && c != '\n') {
             self.advance();
@@ -337,12 +941,14 @@ impl Parser {
         self.clear();
     }
 
+    /// Moves past the next character, or past a CRLF line ending as if it
+    /// were one `\n`.
     fn advance(&mut self) -> Option<char> {
-        let c = self.source.get(self.current);
-        self.current += 1;
+        let c = self.peek();
+        self.current += if self.at_crlf() { 2 } else { 1 };
 
         if let Some(c) = c {
-            if *c == '\n' {
+            if c == '\n' {
                 self.line += 1;
                 self.column = 1;
             } else {
@@ -350,13 +956,22 @@ impl Parser {
             }
         }
 
-        c.cloned()
+        c
     }
 
+    /// The next character, `\n` for a CRLF line ending.
     fn peek(&self) -> Option<char> {
+        if self.at_crlf() {
+            return Some('\n');
+        }
         self.source.get(self.current).cloned()
     }
 
+    fn at_crlf(&self) -> bool {
+        self.source.get(self.current) == Some(&'\r')
+            && self.source.get(self.current + 1) == Some(&'\n')
+    }
+
     fn extract_until(&mut self, c: char) {
         while let Some(current) = self.peek() {
             if current == c {
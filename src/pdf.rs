@@ -0,0 +1,253 @@
+use std::fmt::Write as _;
+
+use chrono::TimeDelta;
+
+use crate::{
+    processing::PeriodReport,
+    render::{tag_goal, Extras, Renderer},
+};
+
+/// A4 in points.
+const WIDTH: f64 = 595.0;
+const HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 56.0;
+/// Where bars start and how long the longest one is.
+const BAR_X: f64 = 300.0;
+const BAR_WIDTH: f64 = 240.0;
+const BAR: &str = "0.16 0.5 0.73 rg";
+const GOOD: &str = "0.15 0.6 0.3 rg";
+const TRACK: &str = "0.9 0.9 0.9 rg";
+
+/// Pages of drawing operators, filled from the top.
+struct Document {
+    pages: Vec<String>,
+    y: f64,
+}
+
+impl Document {
+    fn new() -> Self {
+        Document {
+            pages: vec![String::new()],
+            y: HEIGHT - MARGIN,
+        }
+    }
+
+    /// Moves down by `height`, onto a new page if it does not fit, and
+    /// returns the baseline.
+    fn advance(&mut self, height: f64) -> f64 {
+        if self.y - height < MARGIN {
+            self.pages.push(String::new());
+            self.y = HEIGHT - MARGIN;
+        }
+        self.y -= height;
+
+        self.y
+    }
+
+    fn page(&mut self) -> &mut String {
+        self.pages.last_mut().unwrap()
+    }
+
+    fn text(&mut self, x: f64, y: f64, size: f64, text: &str) {
+        writeln!(
+            self.page(),
+            "BT /F1 {size} Tf {x:.1} {y:.1} Td ({}) Tj ET",
+            escape(text)
+        )
+        .unwrap();
+    }
+
+    fn rectangle(&mut self, color: &str, x: f64, y: f64, width: f64, height: f64) {
+        writeln!(
+            self.page(),
+            "{color} {x:.1} {y:.1} {width:.1} {height:.1} re f 0 g"
+        )
+        .unwrap();
+    }
+
+    /// A row of `label` and `value` with a bar filled to `fraction`.
+    fn bar_row(&mut self, label: &str, value: &str, fraction: f64, color: &str) {
+        let y = self.advance(18.0);
+        self.text(MARGIN, y, 11.0, label);
+        self.text(MARGIN + 150.0, y, 11.0, value);
+        self.rectangle(TRACK, BAR_X, y - 2.0, BAR_WIDTH, 10.0);
+        let width = BAR_WIDTH * fraction.clamp(0.0, 1.0);
+        if width > 0.0 {
+            self.rectangle(color, BAR_X, y - 2.0, width, 10.0);
+        }
+    }
+}
+
+/// PDF of `report` for printing: the time per tag and per day as tables with
+/// bars, the total against the goal and the corrections. Text is set in
+/// Helvetica, so characters outside Latin-1 come out as `?`.
+pub fn report(renderer: &Renderer, report: &PeriodReport, extras: &Extras) -> Vec<u8> {
+    let renderer = renderer.clone().without_color();
+    let mut document = Document::new();
+
+    let y = document.advance(20.0);
+    document.text(MARGIN, y, 18.0, &renderer.period_heading(report));
+    document.advance(12.0);
+
+    let fraction = |duration: TimeDelta, max: TimeDelta| {
+        if max <= TimeDelta::zero() {
+            0.0
+        } else {
+            duration.num_seconds() as f64 / max.num_seconds() as f64
+        }
+    };
+    let total = match extras.goal {
+        Some(goal) => format!(
+            "{} / {}",
+            renderer.duration(report.total),
+            renderer.duration(goal)
+        ),
+        None => renderer.duration(report.total),
+    };
+    let goal = extras.goal.unwrap_or(report.total);
+    let color = if report.total >= goal { GOOD } else { BAR };
+    document.bar_row("Total", &total, fraction(report.total, goal), color);
+    document.advance(12.0);
+
+    if !report.tags.is_empty() {
+        let y = document.advance(18.0);
+        document.text(MARGIN, y, 13.0, "Tags");
+        let max = report
+            .tags
+            .iter()
+            .map(|(_, duration)| *duration)
+            .max()
+            .unwrap_or_default();
+        for (tag, duration) in &report.tags {
+            match tag_goal(extras, tag) {
+                Some(goal) => {
+                    let value = format!(
+                        "{} / {}",
+                        renderer.duration(*duration),
+                        renderer.duration(goal)
+                    );
+                    let color = if *duration >= goal { GOOD } else { BAR };
+                    document.bar_row(tag, &value, fraction(*duration, goal), color);
+                }
+                None => document.bar_row(
+                    tag,
+                    &renderer.duration(*duration),
+                    fraction(*duration, max),
+                    BAR,
+                ),
+            }
+        }
+        document.advance(12.0);
+    }
+
+    if !report.days.is_empty() {
+        let y = document.advance(18.0);
+        document.text(MARGIN, y, 13.0, "Days");
+        let max = report
+            .days
+            .iter()
+            .map(|(_, duration)| *duration)
+            .max()
+            .unwrap_or_default();
+        for (date, duration) in &report.days {
+            document.bar_row(
+                &renderer.date(*date),
+                &renderer.duration(*duration),
+                fraction(*duration, max),
+                BAR,
+            );
+        }
+        document.advance(12.0);
+    }
+
+    if !report.corrections.is_empty() {
+        let y = document.advance(18.0);
+        document.text(MARGIN, y, 13.0, "Corrections");
+        for correction in &report.corrections {
+            let y = document.advance(16.0);
+            document.text(MARGIN, y, 11.0, &renderer.date(correction.date));
+            document.text(MARGIN + 150.0, y, 11.0, &correction.tags.join(" "));
+            document.text(BAR_X, y, 11.0, &renderer.duration(correction.duration));
+        }
+    }
+
+    write(&document.pages)
+}
+
+/// PDF file with a page per content stream and Helvetica as `/F1`.
+fn write(pages: &[String]) -> Vec<u8> {
+    // Catalog, page tree and font, then a page and its content per page.
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        Vec::new(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+    ];
+    let mut kids = Vec::new();
+    for content in pages {
+        let page = objects.len() + 1;
+        kids.push(format!("{page} 0 R"));
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {WIDTH} {HEIGHT}] \
+                 /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                page + 1
+            )
+            .into_bytes(),
+        );
+        let content = latin1(content);
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"endstream");
+        objects.push(stream);
+    }
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        pages.len()
+    )
+    .into_bytes();
+
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend(format!("{} 0 obj\n", index + 1).bytes());
+        out.extend(object);
+        out.extend(b"\nendobj\n");
+    }
+    let xref = out.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        writeln!(table, "{offset:010} 00000 n ").unwrap();
+    }
+    write!(
+        table,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    )
+    .unwrap();
+    out.extend(table.bytes());
+
+    out
+}
+
+/// Escapes a string for a PDF literal.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('(', "\\(")
+        .replace(')', "\\)")
+}
+
+/// `text` in WinAnsiEncoding, `?` for what it lacks.
+fn latin1(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            '–' => 0x96,
+            '—' => 0x97,
+            '…' => 0x85,
+            '\0'..='\u{7f}' | '\u{a0}'..='\u{ff}' => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
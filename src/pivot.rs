@@ -0,0 +1,66 @@
+use std::fmt::Write as _;
+
+use chrono::{NaiveDate, TimeDelta};
+
+use crate::processing::PeriodReport;
+
+/// Length of the periods of `export timeseries`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Granularity {
+    Day,
+    Week,
+}
+
+/// CSV with one row per tag and one column per week, headed by the first day
+/// of the week, holding hours. Tags are in order of first appearance.
+pub fn csv(weeks: &[PeriodReport]) -> String {
+    let mut tags: Vec<&str> = Vec::new();
+    for (tag, _) in weeks.iter().flat_map(|week| &week.tags) {
+        if !tags.contains(&tag.as_str()) {
+            tags.push(tag);
+        }
+    }
+
+    let mut out = String::from("tag");
+    for week in weeks {
+        write!(out, ",{}", week.start.date()).unwrap();
+    }
+    for tag in tags {
+        write!(out, "\n{}", field(tag)).unwrap();
+        for week in weeks {
+            let duration = week
+                .tags
+                .iter()
+                .find(|(title, _)| title == tag)
+                .map_or(TimeDelta::zero(), |(_, duration)| *duration);
+            write!(out, ",{:.2}", duration.num_minutes() as f64 / 60.0).unwrap();
+        }
+    }
+
+    out
+}
+
+/// CSV with the hours of each period, headed by its first day.
+pub fn timeseries(rows: &[(NaiveDate, TimeDelta)]) -> String {
+    let mut out = String::from("date,hours");
+    for (date, duration) in rows {
+        write!(
+            out,
+            "\n{},{:.2}",
+            date,
+            duration.num_minutes() as f64 / 60.0
+        )
+        .unwrap();
+    }
+
+    out
+}
+
+/// Quotes a field containing a separator, quote or line break.
+fn field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,50 @@
+use std::{
+    io::{self, Write as _},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::{ast, error::Error};
+
+/// Prefix of the executables run by `export NAME`.
+pub const EXPORTER_PREFIX: &str = "learning-record-export-";
+
+/// Runs the exporter `learning-record-export-NAME` found on `PATH` with
+/// `args`, writing the syntax tree of `file` as JSON to its standard input.
+///
+/// The exporter inherits standard output and error, and gets the path of
+/// the record file in `LEARNING_RECORD_FILE`.
+pub fn export(name: &str, args: &[String], path: &Path, file: &ast::File) -> Result<(), Error> {
+    let program = format!("{EXPORTER_PREFIX}{name}");
+    let mut child = match Command::new(&program)
+        .args(args)
+        .env("LEARNING_RECORD_FILE", path)
+        .stdin(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(Error::UnknownExporter(name.to_string()))
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let json = serde_json::to_vec(file).expect("AST is serializable");
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    match stdin.write_all(&json) {
+        // The exporter may not need the whole tree.
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {}
+        result => result?,
+    }
+    drop(stdin);
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(Error::ExporterFailed {
+            program,
+            code: status.code(),
+        });
+    }
+
+    Ok(())
+}
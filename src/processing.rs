@@ -1,17 +1,290 @@
-use chrono::{DateTime, Datelike, Local, NaiveDateTime, NaiveTime};
+use std::collections::BTreeMap;
 
-use crate::ast;
+use chrono::{Datelike, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike, Weekday};
+
+use unicode_normalization::UnicodeNormalization as _;
+
+use crate::{
+    ast,
+    query::Query,
+    settings::{Plan, Recurring, Rounding, RoundingMode, RoundingScope, Settings},
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Error {
+pub enum Error {}
+
+/// Restricts which sessions are taken into account.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    /// Keep only events carrying at least one of these tags. Empty means all.
+    pub tags: Vec<String>,
+    pub query: Option<Query>,
+    /// Keep only sessions starting within this time of day.
+    pub between: Option<TimeWindow>,
+    /// Keep only day records on these weekdays. Empty means all.
+    pub days: Vec<Weekday>,
+    /// Leave out events carrying any of these tags.
+    pub ignore_tags: Vec<String>,
 }
 
-pub fn calc_weekly_records(file: &ast::File, today: DateTime<Local>) -> Result<NaiveTime, Error> {
-    let (start_weekday, start_time) = match &file.settings {
-        Some(settings) => (settings.start.weekday, settings.start.time),
+impl Filter {
+    /// Leaves out the events with a tag of `settings.ignore_tags` and, under
+    /// `normalize_tags`, spells the wanted `tags` like those of the file.
+    pub fn ignoring(self, settings: Option<&Settings>) -> Self {
+        let Some(settings) = settings else {
+            return self;
+        };
+        let normalize = |tags: &[String]| {
+            if settings.normalize_tags {
+                tags.iter().map(|tag| normalize_tag(tag)).collect()
+            } else {
+                tags.to_vec()
+            }
+        };
+
+        Self {
+            tags: normalize(&self.tags),
+            ignore_tags: normalize(&settings.ignore_tags),
+            ..self
+        }
+    }
+
+    pub fn matches(&self, event: &ast::Event) -> bool {
+        let mut tags = event.tags.iter().flat_map(|tags| &tags.tags);
+        if tags
+            .clone()
+            .any(|tag| self.ignore_tags.contains(&tag.title))
+        {
+            return false;
+        }
+        if self.tags.is_empty() {
+            return true;
+        }
+
+        tags.any(|tag| self.tags.contains(&tag.title))
+    }
+
+    /// Whether the session `info` of `event` is kept.
+    pub fn matches_session(
+        &self,
+        record: &ast::DayRecord,
+        event: &ast::Event,
+        info: &ast::EventInfo,
+    ) -> bool {
+        self.matches(event)
+            && self
+                .query
+                .as_ref()
+                .is_none_or(|query| query.matches(record, event, info))
+            && self
+                .between
+                .is_none_or(|window| info.time.is_some_and(|time| window.contains(time)))
+            && (self.days.is_empty() || self.days.contains(&record.date.weekday()))
+    }
+}
+
+/// A time of day window such as `05:00..12:00`, start inclusive and end
+/// exclusive. A window whose end is not after its start spans midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    /// Named windows accepted in place of `start..end`.
+    pub const PRESETS: &[(&str, &str, &str)] = &[
+        ("morning", "05:00", "12:00"),
+        ("afternoon", "12:00", "17:00"),
+        ("evening", "17:00", "22:00"),
+        ("night", "22:00", "05:00"),
+    ];
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+pub const WEEKDAYS: [Weekday; 5] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+
+pub const WEEKENDS: [Weekday; 2] = [Weekday::Sat, Weekday::Sun];
+
+impl std::str::FromStr for TimeWindow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let time = |s: &str| NaiveTime::parse_from_str(s.trim(), "%H:%M").ok();
+
+        let (start, end) = match Self::PRESETS
+            .iter()
+            .find(|(name, _, _)| name.eq_ignore_ascii_case(s))
+        {
+            Some(&(_, start, end)) => (start, end),
+            None => s.split_once("..").unwrap_or((s, "")),
+        };
+        match (time(start), time(end)) {
+            (Some(start), Some(end)) => Ok(Self { start, end }),
+            _ => Err(format!(
+                "`{s}` is not a time window (05:00..12:00) or one of {}",
+                Self::PRESETS
+                    .iter()
+                    .map(|(name, _, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+/// `title` in NFKC form and lowercase.
+pub fn normalize_tag(title: &str) -> String {
+    title.nfkc().collect::<String>().to_lowercase()
+}
+
+/// Normalizes the tag titles of the records whose settings have
+/// `normalize_tags` set.
+pub fn normalize_tags(file: &mut ast::File) {
+    let enabled: Vec<bool> = file
+        .records
+        .iter()
+        .map(|record| {
+            file.settings_at(record.date)
+                .is_some_and(|settings| settings.normalize_tags)
+        })
+        .collect();
+
+    for (record, _) in file
+        .records
+        .iter_mut()
+        .zip(enabled)
+        .filter(|(_, enabled)| *enabled)
+    {
+        for tags in record.events.iter_mut().flat_map(|event| &mut event.tags) {
+            // `[Rust rust]` becomes one tag, not two.
+            let mut seen = Vec::new();
+            tags.tags.retain_mut(|tag| {
+                tag.title = normalize_tag(&tag.title);
+                if seen.contains(&tag.title) {
+                    false
+                } else {
+                    seen.push(tag.title.clone());
+                    true
+                }
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeriodReport {
+    pub start: NaiveDateTime,
+    /// Exclusive end of the period.
+    pub end: NaiveDateTime,
+    pub total: TimeDelta,
+    /// Time per tag in order of first appearance. An event with several tags
+    /// counts towards each of them.
+    pub tags: Vec<(String, TimeDelta)>,
+    /// Total of each day record in the period, in file order.
+    pub days: Vec<(NaiveDate, TimeDelta)>,
+    /// Pomodoros of the sessions written like `3p`.
+    pub pomodoros: u32,
+    /// Corrections applied to the totals, in file order.
+    pub corrections: Vec<Correction>,
+}
+
+/// Time taken off with an entry such as `[rust] !-30m`, e.g. for time
+/// logged twice on an earlier day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Correction {
+    pub date: NaiveDate,
+    pub line: usize,
+    pub tags: Vec<String>,
+    /// Negative.
+    pub duration: TimeDelta,
+}
+
+impl PeriodReport {
+    /// Calendar days the period starts on.
+    pub fn dates(&self) -> impl Iterator<Item = NaiveDate> {
+        let end = self.end.date();
+        self.start
+            .date()
+            .iter_days()
+            .take_while(move |date| *date < end)
+    }
+}
+
+/// Sums the sessions of the reporting period containing `today`: a week
+/// from the `[start]` weekday and time, several with `weeks`, or a cycle
+/// of `days`.
+pub fn calc_weekly_records(
+    file: &ast::File,
+    today: NaiveDateTime,
+    filter: &Filter,
+) -> Result<PeriodReport, Error> {
+    let (start_date, end_date) = week_bounds(file, today);
+
+    calc_period(file, start_date, end_date, filter)
+}
+
+/// A moment in the reporting period `offset` periods after the one
+/// containing `today`, e.g. `-1` for the previous one.
+pub fn shift_period(file: &ast::File, today: NaiveDateTime, offset: i64) -> NaiveDateTime {
+    let mut day = today;
+    for _ in 0..offset.unsigned_abs() {
+        let (start, end) = week_bounds(file, day);
+        day = if offset < 0 {
+            start - TimeDelta::seconds(1)
+        } else {
+            end
+        };
+    }
+
+    day
+}
+
+/// Start and exclusive end of the reporting period containing `today`.
+fn week_bounds(file: &ast::File, today: NaiveDateTime) -> (NaiveDateTime, NaiveDateTime) {
+    let start = file
+        .settings_at(today.date())
+        .map(|settings| &settings.start);
+    if let Some((days, start)) = start.and_then(|start| Some((start.days?.max(1), start))) {
+        let from = start
+            .from
+            .unwrap_or(NaiveDate::from_ymd_opt(1970, 1, 1).unwrap())
+            .and_time(start.time);
+        let length = TimeDelta::days(i64::from(days));
+        let cycles = (today - from)
+            .num_seconds()
+            .div_euclid(length.num_seconds());
+        let start_date = from + length * cycles as i32;
+        tracing::debug!(days, %start_date, "computed the start of the cycle");
+        return (start_date, start_date + length);
+    }
+
+    let (start_weekday, start_time, weeks, from) = match file.settings_at(today.date()) {
+        Some(settings) => (
+            settings.start.weekday,
+            settings.start.time,
+            settings.start.weeks.unwrap_or(1).max(1),
+            settings.start.from,
+        ),
         None => (
-            (today.naive_local() - chrono::Duration::days(7)).weekday(),
+            (today - chrono::Duration::days(7)).weekday(),
             NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+            1,
+            None,
         ),
     };
     let start_date = {
@@ -31,26 +304,815 @@ pub fn calc_weekly_records(file: &ast::File, today: DateTime<Local>) -> Result<N
             date
         }
     }
-    .with_time(start_time)
-    .unwrap()
-    .naive_local();
+    .date()
+    .and_time(start_time);
+
+    // Back to the first week of the period, counting whole weeks from the
+    // one `from` is in.
+    let from = from.unwrap_or(NaiveDate::from_ymd_opt(1970, 1, 1).unwrap());
+    let from = from - TimeDelta::days(i64::from(from.weekday().days_since(start_weekday)));
+    let elapsed = (start_date.date() - from).num_days().div_euclid(7);
+    let start_date = start_date - TimeDelta::weeks(elapsed.rem_euclid(i64::from(weeks)));
+
+    let end_date = start_date + TimeDelta::weeks(i64::from(weeks));
+    tracing::debug!(?start_weekday, weeks, %start_date, "computed the start of the period");
+
+    (start_date, end_date)
+}
+
+/// Calendar month containing `today`, from the 1st at midnight.
+pub fn calc_monthly_records(
+    file: &ast::File,
+    today: NaiveDateTime,
+    filter: &Filter,
+) -> Result<PeriodReport, Error> {
+    let first = today.date().with_day(1).unwrap();
+    let next = first.checked_add_months(Months::new(1)).unwrap();
+
+    calc_period(
+        file,
+        first.and_time(NaiveTime::MIN),
+        next.and_time(NaiveTime::MIN),
+        filter,
+    )
+}
+
+/// Year containing `today`, from the first of the `year_start` month at
+/// midnight, or only up to `today` when `to_date`.
+pub fn calc_yearly_records(
+    file: &ast::File,
+    today: NaiveDateTime,
+    filter: &Filter,
+    to_date: bool,
+) -> Result<PeriodReport, Error> {
+    let month = file
+        .settings_at(today.date())
+        .and_then(|settings| settings.year_start)
+        .filter(|month| (1..=12).contains(month))
+        .unwrap_or(1);
+    let year = if today.month() >= month {
+        today.year()
+    } else {
+        today.year() - 1
+    };
+    let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+    let next = first.checked_add_months(Months::new(12)).unwrap();
+    let end = if to_date {
+        today
+    } else {
+        next.and_time(NaiveTime::MIN)
+    };
 
-    let mut sum: NaiveTime = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+    calc_period(file, first.and_time(NaiveTime::MIN), end, filter)
+}
+
+/// Sums the sessions starting in `[start_date, end_date)`.
+pub fn calc_period(
+    file: &ast::File,
+    start_date: NaiveDateTime,
+    end_date: NaiveDateTime,
+    filter: &Filter,
+) -> Result<PeriodReport, Error> {
+    let mut report = PeriodReport {
+        start: start_date,
+        end: end_date,
+        total: TimeDelta::zero(),
+        tags: Vec::new(),
+        days: Vec::new(),
+        pomodoros: 0,
+        corrections: Vec::new(),
+    };
     for day_record in &file.records {
-        if day_record.date < start_date.date() {
+        if day_record.date < start_date.date() || day_record.date > end_date.date() {
             continue;
         }
+
+        let rounding = file
+            .settings_at(day_record.date)
+            .and_then(|settings| settings.rounding);
+        let mut day_total = TimeDelta::zero();
+        let mut day_tags = Vec::new();
         for event in &day_record.events {
             for event_info in &event.info {
-                let event_datetime = NaiveDateTime::new(day_record.date, event_info.time);
-                if event_datetime < start_date && event_datetime < today.naive_local() {
+                if !filter.matches_session(day_record, event, event_info) {
+                    continue;
+                }
+
+                // Sessions without a start belong to their day as a whole.
+                let event_datetime = NaiveDateTime::new(
+                    day_record.date,
+                    event_info.time.unwrap_or(start_date.time()),
+                );
+                if event_datetime < start_date || event_datetime >= end_date {
                     continue;
                 }
 
-                sum += event_info.duration;
+                let duration = match rounding {
+                    // Corrections are rounded like the time they take off.
+                    Some(rounding)
+                        if rounding.scope == RoundingScope::Session
+                            && event_info.is_correction() =>
+                    {
+                        -round(-event_info.duration, rounding)
+                    }
+                    Some(rounding) if rounding.scope == RoundingScope::Session => {
+                        round(event_info.duration, rounding)
+                    }
+                    _ => event_info.duration,
+                };
+                if event_info.is_correction() {
+                    report.corrections.push(Correction {
+                        date: day_record.date,
+                        line: event.line,
+                        tags: event
+                            .tags
+                            .iter()
+                            .flat_map(|tags| &tags.tags)
+                            .map(|tag| tag.title.clone())
+                            .collect(),
+                        duration,
+                    });
+                }
+
+                day_total += duration;
+                report.pomodoros += event_info.pomodoros.unwrap_or_default();
+                for tag in event.tags.iter().flat_map(|tags| &tags.tags) {
+                    add_to_tag(&mut day_tags, &tag.title, duration);
+                }
+            }
+        }
+
+        if let Some(rounding) = rounding.filter(|rounding| rounding.scope == RoundingScope::Day) {
+            day_total = round(day_total, rounding);
+            for (_, duration) in &mut day_tags {
+                *duration = round(*duration, rounding);
+            }
+        }
+
+        report.total += day_total;
+        report.days.push((day_record.date, day_total));
+        for (title, duration) in day_tags {
+            add_to_tag(&mut report.tags, &title, duration);
+        }
+    }
+
+    Ok(report)
+}
+
+fn add_to_tag(tags: &mut Vec<(String, TimeDelta)>, title: &str, duration: TimeDelta) {
+    match tags.iter_mut().find(|(existing, _)| existing == title) {
+        Some((_, sum)) => *sum += duration,
+        None => tags.push((title.to_string(), duration)),
+    }
+}
+
+/// Reduces `goal` by the share of holidays among the days of `report`,
+/// to whole minutes.
+pub fn prorate_goal(goal: TimeDelta, report: &PeriodReport, settings: &Settings) -> TimeDelta {
+    let days = report.dates().count() as i64;
+    let working = report
+        .dates()
+        .filter(|date| !settings.is_holiday(*date))
+        .count() as i64;
+    if days == 0 {
+        return goal;
+    }
+
+    TimeDelta::minutes(goal.num_minutes() * working / days)
+}
+
+/// Prorated `tag_goals` of `settings`, by tag title as it appears in
+/// reports.
+pub fn tag_goals(report: &PeriodReport, settings: &Settings) -> Vec<(String, TimeDelta)> {
+    settings
+        .tag_goals
+        .iter()
+        .map(|(tag, goal)| {
+            let tag = if settings.normalize_tags {
+                normalize_tag(tag)
+            } else {
+                tag.clone()
+            };
+            (tag, prorate_goal(goal.0, report, settings))
+        })
+        .collect()
+}
+
+/// Planned and actual time of one day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlannedDay {
+    pub date: NaiveDate,
+    pub planned: TimeDelta,
+    pub actual: TimeDelta,
+}
+
+impl PlannedDay {
+    /// Positive when more time was spent than planned.
+    pub fn variance(&self) -> TimeDelta {
+        self.actual - self.planned
+    }
+}
+
+/// Compares each day of `report` up to `today` with `plan`. Nothing is
+/// planned on holidays.
+pub fn schedule(
+    report: &PeriodReport,
+    settings: &Settings,
+    plan: &Plan,
+    today: NaiveDate,
+) -> Vec<PlannedDay> {
+    report
+        .dates()
+        .take_while(|date| *date <= today)
+        .map(|date| PlannedDay {
+            date,
+            planned: if settings.is_holiday(date) {
+                TimeDelta::zero()
+            } else {
+                plan.get(date.weekday())
+            },
+            actual: report
+                .days
+                .iter()
+                .filter(|(day, _)| *day == date)
+                .map(|(_, total)| *total)
+                .sum(),
+        })
+        .collect()
+}
+
+/// How far a recurring session of one day was done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotStatus {
+    Fulfilled,
+    Partial,
+    Missed,
+    /// Later today or on a later day of the week.
+    Upcoming,
+}
+
+/// A recurring session planned on one day, with the time recorded on its
+/// tag that day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedSlot {
+    pub date: NaiveDate,
+    pub tag: String,
+    pub planned: TimeDelta,
+    pub actual: TimeDelta,
+}
+
+impl PlannedSlot {
+    pub fn status(&self, today: NaiveDate) -> SlotStatus {
+        if self.actual >= self.planned {
+            SlotStatus::Fulfilled
+        } else if self.date > today || (self.date == today && self.actual.is_zero()) {
+            SlotStatus::Upcoming
+        } else if self.actual.is_zero() {
+            SlotStatus::Missed
+        } else {
+            SlotStatus::Partial
+        }
+    }
+}
+
+/// The `[[recurring]]` sessions of each day of `report`, skipping holidays.
+pub fn planned_slots(
+    file: &ast::File,
+    report: &PeriodReport,
+    settings: &Settings,
+    filter: &Filter,
+) -> Vec<PlannedSlot> {
+    let mut slots = Vec::new();
+    for date in report.dates() {
+        if settings.is_holiday(date) {
+            continue;
+        }
+        for Recurring {
+            tag,
+            every,
+            duration,
+        } in &settings.recurring
+        {
+            if !every.contains(date.weekday()) {
+                continue;
+            }
+            let tag = if settings.normalize_tags {
+                normalize_tag(tag)
+            } else {
+                tag.clone()
+            };
+            let actual = file
+                .records
+                .iter()
+                .filter(|record| record.date == date)
+                .flat_map(|record| record.events.iter().map(move |event| (record, event)))
+                .filter(|(_, event)| {
+                    event
+                        .tags
+                        .iter()
+                        .flat_map(|tags| &tags.tags)
+                        .any(|event_tag| event_tag.title == tag)
+                })
+                .flat_map(|(record, event)| {
+                    event
+                        .info
+                        .iter()
+                        .filter(move |info| filter.matches_session(record, event, info))
+                })
+                .map(|info| info.duration)
+                .sum();
+            slots.push(PlannedSlot {
+                date,
+                tag,
+                planned: duration.0,
+                actual,
+            });
+        }
+    }
+
+    slots
+}
+
+/// Goal time left at the end of one day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BurnDay {
+    pub date: NaiveDate,
+    /// Left if the goal were worked off evenly over the days that are not
+    /// holidays.
+    pub ideal: TimeDelta,
+    /// Actually left; `None` after `today`.
+    pub remaining: Option<TimeDelta>,
+}
+
+/// Goal time left over the days of a week.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Burndown {
+    /// Prorated goal, left at the start of the week.
+    pub goal: TimeDelta,
+    pub days: Vec<BurnDay>,
+}
+
+/// Burn-down of the prorated `goal` over the days of `report`.
+pub fn burndown(
+    report: &PeriodReport,
+    settings: &Settings,
+    goal: TimeDelta,
+    today: NaiveDate,
+) -> Burndown {
+    let goal = prorate_goal(goal, report, settings);
+    let working = report
+        .dates()
+        .filter(|date| !settings.is_holiday(*date))
+        .count()
+        .max(1) as i64;
+    let mut worked_days = 0;
+    let mut done = TimeDelta::zero();
+
+    let days = report
+        .dates()
+        .map(|date| {
+            if !settings.is_holiday(date) {
+                worked_days += 1;
+            }
+            done += report
+                .days
+                .iter()
+                .filter(|(day, _)| *day == date)
+                .map(|(_, total)| *total)
+                .sum::<TimeDelta>();
+            BurnDay {
+                date,
+                ideal: goal - TimeDelta::minutes(goal.num_minutes() * worked_days / working),
+                remaining: (date <= today).then(|| (goal - done).max(TimeDelta::zero())),
+            }
+        })
+        .collect();
+
+    Burndown { goal, days }
+}
+
+/// Usage of one tag over the whole file.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, schemars::JsonSchema)]
+pub struct TagStats {
+    pub name: String,
+    /// Number of events carrying the tag.
+    pub events: usize,
+    #[serde(serialize_with = "serialize_minutes", rename = "minutes")]
+    #[schemars(with = "i64")]
+    pub total: TimeDelta,
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+    /// Distinct details in order of first appearance.
+    pub details: Vec<String>,
+}
+
+/// Collects usage statistics of every tag, in order of first appearance.
+pub fn tag_stats(file: &ast::File) -> Vec<TagStats> {
+    let mut stats: Vec<TagStats> = Vec::new();
+    for record in &file.records {
+        for event in &record.events {
+            let duration: TimeDelta = event.info.iter().map(|info| info.duration).sum();
+            for tag in event.tags.iter().flat_map(|tags| &tags.tags) {
+                let index = match stats.iter().position(|stats| stats.name == tag.title) {
+                    Some(index) => index,
+                    None => {
+                        stats.push(TagStats {
+                            name: tag.title.clone(),
+                            events: 0,
+                            total: TimeDelta::zero(),
+                            first: record.date,
+                            last: record.date,
+                            details: Vec::new(),
+                        });
+                        stats.len() - 1
+                    }
+                };
+                let entry = &mut stats[index];
+                entry.events += 1;
+                entry.total += duration;
+                entry.first = entry.first.min(record.date);
+                entry.last = entry.last.max(record.date);
+                if let Some(detail) = &tag.detail {
+                    if !entry.details.contains(detail) {
+                        entry.details.push(detail.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    stats
+}
+
+/// Sessions starting within one hour of the day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HourStats {
+    /// 0 to 23.
+    pub hour: u32,
+    pub sessions: usize,
+    pub total: TimeDelta,
+}
+
+impl HourStats {
+    /// Average length of the sessions, to whole minutes.
+    pub fn average(&self) -> TimeDelta {
+        TimeDelta::minutes(self.total.num_minutes() / self.sessions.max(1) as i64)
+    }
+}
+
+/// A stretch of work, possibly joined from several sessions under
+/// `merge_gap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Session {
+    pub start: NaiveDateTime,
+    /// Time worked, without the gaps of joined sessions.
+    pub duration: TimeDelta,
+}
+
+/// Sessions of the whole file kept by `filter`, ordered by start within each
+/// day record. Sessions of a day starting less than the `merge_gap` in effect
+/// after the previous one ends are joined into one. Sessions written without
+/// a start are left out.
+pub fn sessions(file: &ast::File, filter: &Filter) -> Vec<Session> {
+    let mut sessions: Vec<Session> = Vec::new();
+    for record in &file.records {
+        let merge_gap = file
+            .settings_at(record.date)
+            .and_then(|settings| settings.merge_gap)
+            .map(|gap| gap.0);
+        let mut day: Vec<Session> = record
+            .events
+            .iter()
+            .flat_map(|event| {
+                event
+                    .info
+                    .iter()
+                    .filter(move |info| filter.matches_session(record, event, info))
+            })
+            .filter_map(|info| {
+                Some(Session {
+                    start: record.date.and_time(info.time?),
+                    duration: info.duration,
+                })
+            })
+            .collect();
+        day.sort_by_key(|session| session.start);
+
+        // End of the previous session of this day.
+        let mut end: Option<NaiveDateTime> = None;
+        for session in day {
+            let session_end = session.start + session.duration;
+            match (end, merge_gap, sessions.last_mut()) {
+                (Some(previous), Some(gap), Some(last)) if session.start - previous < gap => {
+                    last.duration += session.duration;
+                    end = Some(previous.max(session_end));
+                }
+                _ => {
+                    sessions.push(session);
+                    end = Some(session_end);
+                }
             }
         }
     }
 
-    Ok(sum)
+    sessions
+}
+
+/// `sessions` by the hour they start in, for the hours with any.
+pub fn hour_stats(sessions: &[Session]) -> Vec<HourStats> {
+    let mut stats: Vec<HourStats> = (0..24)
+        .map(|hour| HourStats {
+            hour,
+            sessions: 0,
+            total: TimeDelta::zero(),
+        })
+        .collect();
+    for session in sessions {
+        let entry = &mut stats[session.start.hour() as usize];
+        entry.sessions += 1;
+        entry.total += session.duration;
+    }
+    stats.retain(|stats| stats.sessions > 0);
+
+    stats
+}
+
+/// Pearson correlation of the start hour and the length of `sessions`,
+/// `None` when either never varies.
+pub fn hour_correlation(sessions: &[Session]) -> Option<f64> {
+    let points: Vec<(f64, f64)> = sessions
+        .iter()
+        .map(|session| {
+            let hour = session.start.time().num_seconds_from_midnight() as f64 / 3600.0;
+            (hour, session.duration.num_seconds() as f64 / 60.0)
+        })
+        .collect();
+
+    let n = points.len() as f64;
+    let mean_hour = points.iter().map(|(hour, _)| hour).sum::<f64>() / n;
+    let mean_minutes = points.iter().map(|(_, minutes)| minutes).sum::<f64>() / n;
+    let (mut covariance, mut hour_variance, mut minutes_variance) = (0.0, 0.0, 0.0);
+    for (hour, minutes) in points {
+        let (hour, minutes) = (hour - mean_hour, minutes - mean_minutes);
+        covariance += hour * minutes;
+        hour_variance += hour * hour;
+        minutes_variance += minutes * minutes;
+    }
+    if hour_variance == 0.0 || minutes_variance == 0.0 {
+        return None;
+    }
+
+    Some(covariance / (hour_variance * minutes_variance).sqrt())
+}
+
+/// How well the estimates of a tag's events matched their actual time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EstimateStats {
+    pub tag: String,
+    /// First day of the month, when broken down by month.
+    pub month: Option<NaiveDate>,
+    /// Number of estimated events.
+    pub events: usize,
+    pub estimated: TimeDelta,
+    pub actual: TimeDelta,
+    /// Sum of `|actual - estimate| / estimate` over the events.
+    error: f64,
+}
+
+impl EstimateStats {
+    /// Actual time per estimated time; above 1 when work overran.
+    pub fn ratio(&self) -> Option<f64> {
+        (self.estimated > TimeDelta::zero())
+            .then(|| self.actual.num_seconds() as f64 / self.estimated.num_seconds() as f64)
+    }
+
+    /// Average relative difference between the estimate and the actual
+    /// time of an event.
+    pub fn mean_error(&self) -> f64 {
+        self.error / self.events.max(1) as f64
+    }
+}
+
+/// Estimate accuracy of the events with an estimate, per tag in order of
+/// first appearance and, if `monthly`, per month.
+pub fn estimate_stats(file: &ast::File, filter: &Filter, monthly: bool) -> Vec<EstimateStats> {
+    let mut stats: Vec<EstimateStats> = Vec::new();
+    for record in &file.records {
+        let month = monthly.then(|| record.date.with_day(1).unwrap());
+        for event in &record.events {
+            let Some(estimate) = event.estimate else {
+                continue;
+            };
+            let actual: TimeDelta = event
+                .info
+                .iter()
+                .filter(|info| filter.matches_session(record, event, info))
+                .map(|info| info.duration)
+                .sum();
+            if !filter.matches(event) {
+                continue;
+            }
+            let error = if estimate > TimeDelta::zero() {
+                (actual - estimate).num_seconds().abs() as f64 / estimate.num_seconds() as f64
+            } else {
+                0.0
+            };
+
+            for tag in event.tags.iter().flat_map(|tags| &tags.tags) {
+                let index = match stats
+                    .iter()
+                    .position(|stats| stats.tag == tag.title && stats.month == month)
+                {
+                    Some(index) => index,
+                    None => {
+                        stats.push(EstimateStats {
+                            tag: tag.title.clone(),
+                            month,
+                            events: 0,
+                            estimated: TimeDelta::zero(),
+                            actual: TimeDelta::zero(),
+                            error: 0.0,
+                        });
+                        stats.len() - 1
+                    }
+                };
+                let entry = &mut stats[index];
+                entry.events += 1;
+                entry.estimated += estimate;
+                entry.actual += actual;
+                entry.error += error;
+            }
+        }
+    }
+    if monthly {
+        // Keep the months of a tag together.
+        let order: Vec<String> = stats.iter().map(|stats| stats.tag.clone()).collect();
+        stats.sort_by_key(|stats| {
+            let first = order.iter().position(|tag| *tag == stats.tag);
+            (first, stats.month)
+        });
+    }
+
+    stats
+}
+
+fn serialize_minutes<S: serde::Serializer>(
+    delta: &TimeDelta,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i64(delta.num_minutes())
+}
+
+/// Money earned at hourly `rates` during a week and the month containing it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Earnings {
+    /// Earnings of each rated tag of the week.
+    pub tags: Vec<(String, f64)>,
+    pub week: f64,
+    pub month: f64,
+}
+
+/// Converts the time spent on rated tags to money. An event with several
+/// rated tags is paid once per tag.
+pub fn earnings(
+    week: &PeriodReport,
+    month: &PeriodReport,
+    rates: &BTreeMap<String, f64>,
+) -> Earnings {
+    let tags: Vec<(String, f64)> = priced(week, rates).collect();
+    Earnings {
+        week: tags.iter().map(|(_, amount)| amount).sum(),
+        month: priced(month, rates).map(|(_, amount)| amount).sum(),
+        tags,
+    }
+}
+
+fn priced<'a>(
+    report: &'a PeriodReport,
+    rates: &'a BTreeMap<String, f64>,
+) -> impl Iterator<Item = (String, f64)> + 'a {
+    report.tags.iter().filter_map(|(title, duration)| {
+        let rate = rates.get(title)?;
+        let hours = duration.num_seconds() as f64 / 3600.0;
+        Some((title.clone(), hours * rate))
+    })
+}
+
+/// Rounds `duration` to a multiple of the rounding granularity.
+pub fn round(duration: TimeDelta, rounding: Rounding) -> TimeDelta {
+    let step = rounding.granularity.0.num_seconds();
+    if step <= 0 {
+        return duration;
+    }
+
+    let seconds = duration.num_seconds();
+    let down = seconds.div_euclid(step) * step;
+    let rounded = match rounding.mode {
+        RoundingMode::Down => down,
+        RoundingMode::Up if down == seconds => down,
+        RoundingMode::Up => down + step,
+        RoundingMode::Nearest if (seconds - down) * 2 >= step => down + step,
+        RoundingMode::Nearest => down,
+    };
+
+    TimeDelta::seconds(rounded)
+}
+
+/// Runs of consecutive logged days. A day is logged when it has a day
+/// record, even one without events, which records a day off on purpose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Streaks {
+    /// Logged days up to today, or up to yesterday while today is not.
+    pub current: usize,
+    pub longest: usize,
+    /// Last day of the longest run.
+    pub longest_end: Option<NaiveDate>,
+    /// Days with a day record, from the first one to today.
+    pub logged: usize,
+    /// Logged days with time recorded.
+    pub active: usize,
+    /// Days from the first day record to today, both included, leaving out
+    /// holidays without a day record.
+    pub days: usize,
+}
+
+impl Streaks {
+    /// Share of days that were logged.
+    pub fn consistency(&self) -> f64 {
+        self.logged as f64 / self.days.max(1) as f64
+    }
+}
+
+/// Streaks of the days up to `today`; time counts towards `active` only in
+/// sessions matching `filter`. Holidays without a day record neither break
+/// a run nor count among the `days`.
+pub fn streaks(file: &ast::File, today: NaiveDate, filter: &Filter) -> Streaks {
+    // Whether each logged day had time recorded.
+    let mut logged: BTreeMap<NaiveDate, bool> = BTreeMap::new();
+    for record in file.records.iter().filter(|record| record.date <= today) {
+        let active = record.events.iter().any(|event| {
+            event.info.iter().any(|info| {
+                info.duration > TimeDelta::zero() && filter.matches_session(record, event, info)
+            })
+        });
+        *logged.entry(record.date).or_default() |= active;
+    }
+    let Some(first) = logged.keys().next().copied() else {
+        return Streaks::default();
+    };
+
+    let mut streaks = Streaks {
+        logged: logged.len(),
+        active: logged.values().filter(|active| **active).count(),
+        ..Streaks::default()
+    };
+    let mut run = 0;
+    for date in first.iter_days().take_while(|date| *date <= today) {
+        if logged.contains_key(&date) {
+            run += 1;
+            if run > streaks.longest {
+                streaks.longest = run;
+                streaks.longest_end = Some(date);
+            }
+        } else if is_holiday(file, date) {
+            continue;
+        } else if date < today {
+            // Today can still be logged.
+            run = 0;
+        }
+        streaks.days += 1;
+    }
+    streaks.current = run;
+
+    streaks
+}
+
+fn is_holiday(file: &ast::File, date: NaiveDate) -> bool {
+    file.settings_at(date)
+        .is_some_and(|settings| settings.is_holiday(date))
+}
+
+/// Mean total of `reports`, each weighed by the share of its days that are
+/// not holidays, so that a week off does not drag the average down. `None`
+/// when they are all holidays.
+pub fn average_total(file: &ast::File, reports: &[PeriodReport]) -> Option<TimeDelta> {
+    let mut minutes = 0;
+    let mut weight = 0.0;
+    for report in reports {
+        let days = report.dates().count();
+        let working = report
+            .dates()
+            .filter(|date| !is_holiday(file, *date))
+            .count();
+        if days == 0 || working == 0 {
+            continue;
+        }
+        minutes += report.total.num_minutes();
+        weight += working as f64 / days as f64;
+    }
+    if weight == 0.0 {
+        return None;
+    }
+
+    Some(TimeDelta::minutes((minutes as f64 / weight).round() as i64))
 }
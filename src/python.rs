@@ -0,0 +1,193 @@
+use std::fs;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyDict, PyList},
+};
+
+use crate::{
+    ast,
+    parser::Parser,
+    processing::{self, Filter},
+};
+
+/// A parsed record file.
+#[pyclass(module = "learning_record", frozen)]
+pub struct File(ast::File);
+
+#[pyclass(module = "learning_record", get_all, frozen, skip_from_py_object)]
+#[derive(Clone)]
+pub struct DayRecord {
+    date: NaiveDate,
+    events: Vec<Event>,
+    line: usize,
+}
+
+#[pyclass(module = "learning_record", get_all, frozen, skip_from_py_object)]
+#[derive(Clone)]
+pub struct Event {
+    tags: Vec<Tag>,
+    sessions: Vec<Session>,
+    line: usize,
+}
+
+#[pyclass(module = "learning_record", get_all, frozen, skip_from_py_object)]
+#[derive(Clone)]
+pub struct Tag {
+    title: String,
+    detail: Option<String>,
+}
+
+#[pyclass(module = "learning_record", get_all, frozen, skip_from_py_object)]
+#[derive(Clone)]
+pub struct Session {
+    /// `None` when only the duration was written, as in `[anki] 20m`.
+    start: Option<NaiveTime>,
+    duration: TimeDelta,
+}
+
+/// Totals of one week.
+#[pyclass(module = "learning_record", get_all, frozen)]
+pub struct Report {
+    start: NaiveDateTime,
+    /// Exclusive end of the week.
+    end: NaiveDateTime,
+    total: TimeDelta,
+    /// Prorated weekly goal, `None` without one.
+    goal: Option<TimeDelta>,
+    /// `(tag, time)` in order of first appearance.
+    tags: Vec<(String, TimeDelta)>,
+    /// `(date, time)` of each day record.
+    days: Vec<(NaiveDate, TimeDelta)>,
+}
+
+#[pymethods]
+impl File {
+    #[getter]
+    fn records(&self) -> Vec<DayRecord> {
+        self.0
+            .records
+            .iter()
+            .map(|record| DayRecord {
+                date: record.date,
+                line: record.line,
+                events: record
+                    .events
+                    .iter()
+                    .map(|event| Event {
+                        line: event.line,
+                        tags: event
+                            .tags
+                            .iter()
+                            .flat_map(|tags| &tags.tags)
+                            .map(|tag| Tag {
+                                title: tag.title.clone(),
+                                detail: tag.detail.clone(),
+                            })
+                            .collect(),
+                        sessions: event
+                            .info
+                            .iter()
+                            .map(|info| Session {
+                                start: info.time,
+                                duration: info.duration,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// One dict per session with its date, start, duration, minutes, tags
+    /// and line, ready for `pandas.DataFrame`.
+    fn sessions<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let rows = PyList::empty(py);
+        for record in &self.0.records {
+            for event in &record.events {
+                let tags: Vec<&str> = event
+                    .tags
+                    .iter()
+                    .flat_map(|tags| &tags.tags)
+                    .map(|tag| tag.title.as_str())
+                    .collect();
+                for info in &event.info {
+                    let row = PyDict::new(py);
+                    row.set_item("date", record.date)?;
+                    row.set_item("start", info.time)?;
+                    row.set_item("duration", info.duration)?;
+                    row.set_item("minutes", info.duration.num_minutes())?;
+                    row.set_item("tags", tags.clone())?;
+                    row.set_item("line", event.line)?;
+                    rows.append(row)?;
+                }
+            }
+        }
+
+        Ok(rows)
+    }
+
+    /// The syntax tree as JSON, like `ast --format json`.
+    fn to_json(&self) -> String {
+        serde_json::to_string(&self.0).expect("AST is serializable")
+    }
+
+    /// Totals of the week containing `today`.
+    fn weekly_report(&self, today: NaiveDateTime) -> PyResult<Report> {
+        let settings = self.0.settings_at(today.date());
+        let filter = Filter::default().ignoring(settings);
+        let report = processing::calc_weekly_records(&self.0, today, &filter)
+            .map_err(|err| PyValueError::new_err(format!("{err:?}")))?;
+        let goal = settings.and_then(|settings| {
+            let goal = settings.goal?.0;
+            Some(processing::prorate_goal(goal, &report, settings))
+        });
+
+        Ok(Report {
+            start: report.start,
+            end: report.end,
+            total: report.total,
+            goal,
+            tags: report.tags,
+            days: report.days,
+        })
+    }
+}
+
+fn parse_source(source: &str) -> PyResult<File> {
+    let mut file = Parser::new(source.chars().collect())
+        .parse_file()
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    processing::normalize_tags(&mut file);
+
+    Ok(File(file))
+}
+
+/// Parses the content of a record file.
+#[pyfunction]
+fn parse(source: &str) -> PyResult<File> {
+    parse_source(source)
+}
+
+/// Reads and parses the record file at `path`. `@include` directives are not
+/// followed.
+#[pyfunction]
+fn parse_file(path: &str) -> PyResult<File> {
+    parse_source(&fs::read_to_string(path)?)
+}
+
+#[pymodule]
+fn learning_record(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(parse, module)?)?;
+    module.add_function(wrap_pyfunction!(parse_file, module)?)?;
+    module.add_class::<File>()?;
+    module.add_class::<DayRecord>()?;
+    module.add_class::<Event>()?;
+    module.add_class::<Tag>()?;
+    module.add_class::<Session>()?;
+    module.add_class::<Report>()?;
+
+    Ok(())
+}
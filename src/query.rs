@@ -0,0 +1,307 @@
+use std::str::FromStr;
+
+use chrono::{Datelike as _, NaiveDate, NaiveTime, TimeDelta, Weekday};
+
+use crate::{ast, parser};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// The offending term as written.
+    pub term: String,
+    pub kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// No terms at all, or nothing on one side of `or`.
+    Empty,
+    UnknownField(String),
+    MissingValue,
+    /// A comparison on a field that only supports `:`.
+    UnsupportedOperator,
+    InvalidDate,
+    InvalidDuration,
+    InvalidTime,
+    InvalidWeekday,
+    UnterminatedQuote,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.term.is_empty() {
+            write!(f, "{}", self.kind)
+        } else {
+            write!(f, "`{}`: {}", self.term, self.kind)
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::Empty => write!(f, "empty query"),
+            ErrorKind::UnknownField(field) => write!(
+                f,
+                "unknown field `{}` (expected tag, detail, after, before, on, day, dur or time)",
+                field
+            ),
+            ErrorKind::MissingValue => write!(f, "missing value"),
+            ErrorKind::UnsupportedOperator => write!(f, "only `:` is supported here"),
+            ErrorKind::InvalidDate => write!(f, "invalid date, expected YYYY-MM-DD"),
+            ErrorKind::InvalidDuration => write!(f, "invalid duration, expected e.g. 1h30m"),
+            ErrorKind::InvalidTime => write!(f, "invalid time, expected HH:MM"),
+            ErrorKind::InvalidWeekday => write!(f, "invalid weekday, expected e.g. mon"),
+            ErrorKind::UnterminatedQuote => write!(f, "unterminated quote"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A filter over sessions such as
+/// `tag:rust after:2024-01-01 dur>1h or -tag:anki time<09:00`.
+///
+/// Terms separated by spaces must all match; `or` separates alternatives.
+/// A leading `-` negates a term. Words without a field match tags and
+/// details containing them, ignoring case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    clauses: Vec<Vec<Term>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Term {
+    negated: bool,
+    predicate: Predicate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Predicate {
+    /// Tag title, ignoring case.
+    Tag(String),
+    /// Part of a tag detail, lowercase.
+    Detail(String),
+    /// Part of a tag title or detail, lowercase.
+    Text(String),
+    /// On or after the date.
+    After(NaiveDate),
+    /// Strictly before the date.
+    Before(NaiveDate),
+    On(NaiveDate),
+    Day(Weekday),
+    Duration(Comparison, TimeDelta),
+    Time(Comparison, NaiveTime),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn holds<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Equal => left == right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Greater => left > right,
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let empty = |term: &str| Error {
+            term: term.to_string(),
+            kind: ErrorKind::Empty,
+        };
+
+        let mut clauses = vec![Vec::new()];
+        for token in tokenize(source)? {
+            if token.eq_ignore_ascii_case("or") {
+                if clauses.last().is_some_and(Vec::is_empty) {
+                    return Err(empty(&token));
+                }
+                clauses.push(Vec::new());
+            } else {
+                let term = parse_term(&token)?;
+                clauses
+                    .last_mut()
+                    .expect("there is always a clause")
+                    .push(term);
+            }
+        }
+        if clauses.iter().any(Vec::is_empty) {
+            return Err(empty(if clauses.len() > 1 { "or" } else { "" }));
+        }
+
+        Ok(Self { clauses })
+    }
+}
+
+impl Query {
+    /// Words looked for in tags and details, for highlighting.
+    pub fn words(&self) -> Vec<&str> {
+        self.clauses
+            .iter()
+            .flatten()
+            .filter(|term| !term.negated)
+            .filter_map(|term| match &term.predicate {
+                Predicate::Tag(text) | Predicate::Detail(text) | Predicate::Text(text) => {
+                    Some(text.as_str())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether the session `info` of `event` on the day `record` matches.
+    pub fn matches(
+        &self,
+        record: &ast::DayRecord,
+        event: &ast::Event,
+        info: &ast::EventInfo,
+    ) -> bool {
+        self.clauses.iter().any(|terms| {
+            terms
+                .iter()
+                .all(|term| term.predicate.matches(record, event, info) != term.negated)
+        })
+    }
+}
+
+impl Predicate {
+    fn matches(&self, record: &ast::DayRecord, event: &ast::Event, info: &ast::EventInfo) -> bool {
+        let mut tags = event.tags.iter().flat_map(|tags| &tags.tags);
+        let detail_contains = |tag: &ast::Tag, text: &str| {
+            tag.detail
+                .as_ref()
+                .is_some_and(|detail| detail.to_lowercase().contains(text))
+        };
+
+        match self {
+            Predicate::Tag(name) => tags.any(|tag| tag.title.eq_ignore_ascii_case(name)),
+            Predicate::Detail(text) => tags.any(|tag| detail_contains(tag, text)),
+            Predicate::Text(text) => tags
+                .any(|tag| tag.title.to_lowercase().contains(text) || detail_contains(tag, text)),
+            Predicate::After(date) => record.date >= *date,
+            Predicate::Before(date) => record.date < *date,
+            Predicate::On(date) => record.date == *date,
+            Predicate::Day(weekday) => record.date.weekday() == *weekday,
+            Predicate::Duration(comparison, duration) => comparison.holds(info.duration, *duration),
+            Predicate::Time(comparison, time) => info
+                .time
+                .is_some_and(|start| comparison.holds(start, *time)),
+        }
+    }
+}
+
+/// Splits at spaces outside double quotes, removing the quotes.
+fn tokenize(source: &str) -> Result<Vec<String>, Error> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in source.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if quoted {
+        return Err(Error {
+            term: current,
+            kind: ErrorKind::UnterminatedQuote,
+        });
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    Ok(tokens)
+}
+
+fn parse_term(token: &str) -> Result<Term, Error> {
+    let error = |kind| Error {
+        term: token.to_string(),
+        kind,
+    };
+
+    let (negated, body) = match token.strip_prefix('-') {
+        Some("") => return Err(error(ErrorKind::MissingValue)),
+        Some(body) => (true, body),
+        None => (false, token),
+    };
+
+    let Some(split) = body.find([':', '<', '>', '=']) else {
+        return Ok(Term {
+            negated,
+            predicate: Predicate::Text(body.to_lowercase()),
+        });
+    };
+    let field = &body[..split];
+    let rest = &body[split..];
+    let (comparison, value) = [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+        ("=", Comparison::Equal),
+        (":", Comparison::Equal),
+    ]
+    .into_iter()
+    .find_map(|(operator, comparison)| Some((comparison, rest.strip_prefix(operator)?)))
+    .expect("split is at an operator");
+    let is_colon = rest.starts_with(':');
+
+    if value.is_empty() {
+        return Err(error(ErrorKind::MissingValue));
+    }
+    let only_colon = |predicate: Predicate| {
+        if is_colon {
+            Ok(predicate)
+        } else {
+            Err(error(ErrorKind::UnsupportedOperator))
+        }
+    };
+    let date =
+        || NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| error(ErrorKind::InvalidDate));
+
+    let predicate = match field.to_lowercase().as_str() {
+        "tag" => only_colon(Predicate::Tag(value.to_string()))?,
+        "detail" => only_colon(Predicate::Detail(value.to_lowercase()))?,
+        "after" => only_colon(Predicate::After(date()?))?,
+        "before" => only_colon(Predicate::Before(date()?))?,
+        "on" => only_colon(Predicate::On(date()?))?,
+        "day" => {
+            let weekday = value
+                .parse::<Weekday>()
+                .map_err(|_| error(ErrorKind::InvalidWeekday))?;
+            only_colon(Predicate::Day(weekday))?
+        }
+        "dur" => {
+            let duration =
+                parser::parse_duration(value).map_err(|_| error(ErrorKind::InvalidDuration))?;
+            Predicate::Duration(comparison, duration)
+        }
+        "time" => {
+            let time = NaiveTime::parse_from_str(value, "%H:%M")
+                .map_err(|_| error(ErrorKind::InvalidTime))?;
+            Predicate::Time(comparison, time)
+        }
+        field => return Err(error(ErrorKind::UnknownField(field.to_string()))),
+    };
+
+    Ok(Term { negated, predicate })
+}
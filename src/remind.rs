@@ -0,0 +1,101 @@
+use chrono::{NaiveDateTime, TimeDelta};
+
+use crate::{
+    ast, format,
+    processing::{self, Filter},
+};
+
+/// Something worth a nudge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reminder {
+    NothingToday,
+    /// The week is behind the pace needed to reach the goal by its end.
+    GoalAtRisk {
+        total: TimeDelta,
+        /// Share of the goal due by now.
+        expected: TimeDelta,
+        goal: TimeDelta,
+    },
+}
+
+impl std::fmt::Display for Reminder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reminder::NothingToday => write!(f, "Nothing recorded today yet"),
+            Reminder::GoalAtRisk {
+                total,
+                expected,
+                goal,
+            } => write!(
+                f,
+                "Weekly goal at risk: {} of {}, {} expected by now",
+                format::duration(*total),
+                format::duration(*goal),
+                format::duration(*expected)
+            ),
+        }
+    }
+}
+
+/// Reminders due at `now`: no sessions today, or less time this week than
+/// its elapsed share of the prorated goal.
+pub fn check(
+    file: &ast::File,
+    now: NaiveDateTime,
+    filter: &Filter,
+) -> Result<Vec<Reminder>, processing::Error> {
+    let mut reminders = Vec::new();
+
+    // A day record without events logs a day off.
+    let recorded_today = file
+        .records
+        .iter()
+        .filter(|record| record.date == now.date())
+        .any(|record| {
+            record.events.is_empty()
+                || record.events.iter().any(|event| {
+                    event
+                        .info
+                        .iter()
+                        .any(|info| filter.matches_session(record, event, info))
+                })
+        });
+    if !recorded_today {
+        reminders.push(Reminder::NothingToday);
+    }
+
+    let settings = file.settings_at(now.date());
+    if let Some((settings, goal)) = settings.and_then(|settings| Some((settings, settings.goal?.0)))
+    {
+        let report = processing::calc_weekly_records(file, now, filter)?;
+        let goal = processing::prorate_goal(goal, &report, settings);
+        let elapsed = (now - report.start).num_seconds() as f64;
+        let length = (report.end - report.start).num_seconds() as f64;
+        let expected = TimeDelta::minutes((goal.num_minutes() as f64 * elapsed / length) as i64);
+        if report.total < expected {
+            reminders.push(Reminder::GoalAtRisk {
+                total: report.total,
+                expected,
+                goal,
+            });
+        }
+    }
+
+    Ok(reminders)
+}
+
+/// Shows `reminders` as one desktop notification.
+pub fn notify(reminders: &[Reminder]) -> Result<(), notify_rust::error::Error> {
+    let body = reminders
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    notify_rust::Notification::new()
+        .appname("learning-record")
+        .summary("Learning record")
+        .body(&body)
+        .show()?;
+
+    Ok(())
+}
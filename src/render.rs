@@ -0,0 +1,1501 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Write as _},
+    io::IsTerminal as _,
+    path::Path,
+};
+
+use anstyle::{AnsiColor, Style};
+use chrono::{
+    format::{Fixed, Item, StrftimeItems},
+    Datelike, NaiveDate, NaiveDateTime, TimeDelta, Weekday,
+};
+use clap::ColorChoice;
+
+use crate::{
+    ast,
+    diff::{Difference, Side},
+    doctor::{Check, Severity},
+    format,
+    gaps::Gap,
+    i18n::Locale,
+    overview::Overview,
+    processing::{
+        Burndown, Earnings, EstimateStats, HourStats, PeriodReport, PlannedDay, PlannedSlot,
+        Session, SlotStatus, Streaks, TagStats,
+    },
+    search::Hit,
+    settings::{DisplaySettings, ReportFormat, Settings, TagStyle},
+    stats::{Lengths, BUCKETS, DEEP_WORK},
+};
+
+const TAG: Style = AnsiColor::Cyan.on_default();
+const HEADING: Style = Style::new().bold();
+const GOOD: Style = AnsiColor::Green.on_default().bold();
+const BAD: Style = AnsiColor::Red.on_default().bold();
+const WARNING: Style = AnsiColor::Yellow.on_default().bold();
+const ERROR: Style = AnsiColor::Red.on_default().bold();
+/// Calendar days with little to a lot of time.
+const SHADES: [Style; 4] = [
+    Style::new().fg_color(Some(anstyle::Color::Ansi256(anstyle::Ansi256Color(22)))),
+    Style::new().fg_color(Some(anstyle::Color::Ansi256(anstyle::Ansi256Color(28)))),
+    Style::new().fg_color(Some(anstyle::Color::Ansi256(anstyle::Ansi256Color(34)))),
+    Style::new()
+        .fg_color(Some(anstyle::Color::Ansi256(anstyle::Ansi256Color(40))))
+        .bold(),
+];
+
+/// What a report shows besides the period itself.
+#[derive(Debug, Clone, Default)]
+pub struct Extras {
+    pub goal: Option<TimeDelta>,
+    /// Prorated goal per tag; empty without `[tag_goals]`.
+    pub tag_goals: Vec<(String, TimeDelta)>,
+    /// Present when `[rates]` are configured.
+    pub earnings: Option<Earnings>,
+    /// Planned and actual time of each day so far; empty without a `[plan]`.
+    pub schedule: Vec<PlannedDay>,
+    /// Values set by the `report.script`, by name.
+    pub metrics: BTreeMap<String, serde_json::Value>,
+    /// Totals of the weeks up to this one, oldest first; empty unless asked
+    /// for.
+    pub trend: Vec<TimeDelta>,
+    /// Mean total of the last `AVERAGE_WEEKS` completed weeks since the
+    /// first day record and how many weeks that is; `None` before a week has
+    /// been completed.
+    pub average: Option<(TimeDelta, usize)>,
+}
+
+/// Number of weeks averaged next to the total of weekly reports.
+pub const AVERAGE_WEEKS: usize = 4;
+
+/// Whether to emit ANSI colors on a stream; `auto` honors `NO_COLOR`.
+pub fn color_enabled(choice: ColorChoice, terminal: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => std::env::var_os("NO_COLOR").is_none() && terminal,
+    }
+}
+
+/// Turns computed results into text for the terminal.
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    color: bool,
+    plain: bool,
+    locale: Locale,
+    display: DisplaySettings,
+    /// Symbol put in front of amounts of money.
+    currency: String,
+    /// Colors and icons per tag.
+    tags: BTreeMap<String, TagStyle>,
+    /// Diagnostics go to stderr, which may be redirected independently.
+    error_color: bool,
+}
+
+impl Renderer {
+    /// `plain` output has no colors, emoji or box drawing characters.
+    pub fn new(choice: ColorChoice, plain: bool) -> Self {
+        Self {
+            color: !plain && color_enabled(choice, std::io::stdout().is_terminal()),
+            plain,
+            locale: Locale::default(),
+            display: DisplaySettings::default(),
+            currency: String::new(),
+            tags: BTreeMap::new(),
+            error_color: !plain && color_enabled(choice, std::io::stderr().is_terminal()),
+        }
+    }
+
+    /// Applies the locale and display settings of the record file.
+    pub fn with_settings(self, settings: Option<&Settings>) -> Self {
+        let locale = settings
+            .and_then(|settings| settings.locale)
+            .unwrap_or_default();
+        let mut display = settings
+            .and_then(|settings| settings.display.clone())
+            .unwrap_or_default();
+
+        let invalid = display.date_format.as_deref().is_some_and(|format| {
+            StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+        });
+        if invalid {
+            let format = display.date_format.take();
+            tracing::warn!(?format, "ignoring the invalid date format");
+        }
+
+        let currency = settings
+            .and_then(|settings| settings.currency.clone())
+            .unwrap_or_default();
+
+        let tags = settings
+            .map(|settings| settings.tags.clone())
+            .unwrap_or_default();
+
+        let renderer = Self {
+            locale,
+            display,
+            currency,
+            tags,
+            ..self
+        };
+        if renderer.display.plain {
+            renderer.into_plain()
+        } else {
+            renderer
+        }
+    }
+
+    fn into_plain(self) -> Self {
+        Self {
+            color: false,
+            error_color: false,
+            plain: true,
+            ..self
+        }
+    }
+
+    /// Formats a date for output with the configured format and weekday names.
+    pub fn date(&self, date: NaiveDate) -> String {
+        let format = self
+            .display
+            .date_format
+            .as_deref()
+            .unwrap_or(self.locale.date_format());
+        let weekday = date.weekday();
+        let short = match &self.display.weekdays {
+            Some(names) => names[weekday.num_days_from_monday() as usize].as_str(),
+            None => self.locale.weekday(weekday),
+        };
+
+        let items = StrftimeItems::new(format).map(|item| match item {
+            Item::Fixed(Fixed::ShortWeekdayName) => Item::Literal(short),
+            Item::Fixed(Fixed::LongWeekdayName) => Item::Literal(self.locale.weekday_long(weekday)),
+            item => item,
+        });
+
+        date.format_with_items(items).to_string()
+    }
+
+    /// The same renderer for output that does not go to a terminal.
+    pub fn without_color(self) -> Self {
+        Self {
+            color: false,
+            ..self
+        }
+    }
+
+    pub fn duration(&self, delta: TimeDelta) -> String {
+        let format = self.display.duration_format.unwrap_or_default();
+        format::display(delta, format, self.locale.duration_units())
+    }
+
+    pub fn money(&self, amount: f64) -> String {
+        format!("{}{:.2}", self.currency, amount)
+    }
+
+    /// Signed difference to a plan, e.g. `+30m` or `-1h`.
+    pub fn variance(&self, delta: TimeDelta) -> String {
+        let sign = if delta < TimeDelta::zero() { "-" } else { "+" };
+        let text = format!("{}{}", sign, self.duration(delta.abs()));
+        match delta.cmp(&TimeDelta::zero()) {
+            std::cmp::Ordering::Less => self.paint(BAD, text),
+            std::cmp::Ordering::Equal => text,
+            std::cmp::Ordering::Greater => self.paint(GOOD, text),
+        }
+    }
+
+    /// Heading of the weekly report starting at `start`.
+    pub fn heading(&self, start: NaiveDateTime) -> String {
+        let time = start.format("%H:%M").to_string();
+        self.locale.week_from(&self.date(start.date()), &time)
+    }
+
+    /// Label of `Extras::average` for periods like `report`.
+    fn average_label(&self, report: &PeriodReport, weeks: usize) -> String {
+        self.locale
+            .average(weeks, report.end - report.start == TimeDelta::weeks(1))
+    }
+
+    /// `heading` for a week, the first and last day for other periods.
+    pub fn period_heading(&self, report: &PeriodReport) -> String {
+        if report.end - report.start == TimeDelta::weeks(1) {
+            return self.heading(report.start);
+        }
+        let time = report.start.format("%H:%M").to_string();
+        let last = report.dates().last().unwrap_or(report.start.date());
+        self.locale
+            .period(&self.date(report.start.date()), &time, &self.date(last))
+    }
+
+    fn paint(&self, style: Style, text: impl Display) -> String {
+        paint(self.color, style, text)
+    }
+
+    /// `tag` with its icon in front, unless output is plain.
+    pub fn tag_label(&self, tag: &str) -> String {
+        match self.tags.get(tag).and_then(|style| style.icon.as_ref()) {
+            Some(icon) if !self.plain => format!("{icon} {tag}"),
+            _ => tag.to_string(),
+        }
+    }
+
+    /// Terminal columns `tag_label` takes up.
+    fn tag_width(&self, tag: &str) -> usize {
+        unicode_width::UnicodeWidthStr::width(self.tag_label(tag).as_str())
+    }
+
+    /// `tag_label` padded to `width` columns, in the color of the tag.
+    fn tag(&self, tag: &str, width: usize) -> String {
+        let label = self.tag_label(tag);
+        let padding = width.saturating_sub(self.tag_width(tag));
+        let style = match self.tags.get(tag).and_then(|style| style.color) {
+            Some(color) => {
+                let [r, g, b] = color.0;
+                Style::new().fg_color(Some(anstyle::RgbColor(r, g, b).into()))
+            }
+            None => TAG,
+        };
+
+        format!("{}{}", self.paint(style, label), " ".repeat(padding))
+    }
+
+    /// An event found by `search` with `context` lines of `source` around it.
+    pub fn search_hit(
+        &self,
+        hit: &Hit,
+        path: &Path,
+        source: &str,
+        words: &[&str],
+        context: usize,
+    ) -> String {
+        let mut out = format!(
+            "{}  {}:{}",
+            self.paint(HEADING, self.date(hit.record.date)),
+            path.display(),
+            hit.event.line
+        );
+        let lines: Vec<&str> = source.lines().collect();
+        let index = hit.event.line - 1;
+        let first = index.saturating_sub(context);
+        let last = (index + context).min(lines.len().saturating_sub(1));
+        for (number, line) in lines.iter().enumerate().take(last + 1).skip(first) {
+            let line = if number == index {
+                self.highlight(line, words)
+            } else {
+                line.to_string()
+            };
+            write!(out, "\n{:>5} | {}", number + 1, line).unwrap();
+        }
+
+        out
+    }
+
+    /// One line per event of `log`: its date, the first start of its sessions,
+    /// its tags, time and tag details.
+    pub fn log(&self, hits: &[Hit]) -> String {
+        let starts: Vec<String> = hits
+            .iter()
+            .map(|hit| {
+                let start = hit.event.info.iter().find_map(|info| info.time);
+                start.map(format::time).unwrap_or_default()
+            })
+            .collect();
+        let start_width = starts.iter().map(String::len).max().unwrap_or_default();
+        let tags: Vec<Vec<&ast::Tag>> = hits
+            .iter()
+            .map(|hit| hit.event.tags.iter().flat_map(|tags| &tags.tags).collect())
+            .collect();
+        let widths: Vec<usize> = tags
+            .iter()
+            .map(|tags| {
+                let labels: usize = tags.iter().map(|tag| self.tag_width(&tag.title)).sum();
+                labels + tags.len().saturating_sub(1)
+            })
+            .collect();
+        let width = widths.iter().copied().max().unwrap_or_default();
+        let mut out = String::new();
+        for (((hit, start), tags), used) in hits.iter().zip(&starts).zip(&tags).zip(&widths) {
+            let labels: Vec<String> = tags.iter().map(|tag| self.tag(&tag.title, 0)).collect();
+            let total: TimeDelta = hit.event.info.iter().map(|info| info.duration).sum();
+            write!(
+                out,
+                "{} {:start_width$}  {}{}  {:>8}",
+                self.paint(HEADING, self.date(hit.record.date)),
+                start,
+                labels.join(" "),
+                " ".repeat(width - used),
+                self.duration(total),
+            )
+            .unwrap();
+            let details: Vec<&str> = tags
+                .iter()
+                .filter_map(|tag| tag.detail.as_deref())
+                .collect();
+            if !details.is_empty() {
+                write!(out, "  {}", details.join(", ")).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+        out.pop();
+
+        out
+    }
+
+    /// Paints the occurrences of `words` in `text`, ignoring case.
+    fn highlight(&self, text: &str, words: &[&str]) -> String {
+        let lower = text.to_lowercase();
+        if !self.color || lower.len() != text.len() {
+            return text.to_string();
+        }
+
+        let mut marked = vec![false; text.len()];
+        for word in words.iter().filter(|word| !word.is_empty()) {
+            for (start, _) in lower.match_indices(&word.to_lowercase()) {
+                marked[start..start + word.len()].fill(true);
+            }
+        }
+
+        let mut out = String::new();
+        let mut start = 0;
+        while start < text.len() {
+            let painted = marked[start];
+            let end = (start..text.len())
+                .find(|&index| marked[index] != painted && text.is_char_boundary(index))
+                .unwrap_or(text.len());
+            if painted {
+                out += &self.paint(TAG, &text[start..end]);
+            } else {
+                out += &text[start..end];
+            }
+            start = end;
+        }
+
+        out
+    }
+
+    /// Table of tag statistics for `tags`.
+    pub fn tag_stats(&self, stats: &[TagStats]) -> String {
+        let width = stats
+            .iter()
+            .map(|stats| self.tag_width(&stats.name))
+            .max()
+            .unwrap_or_default();
+        let mut out = String::new();
+        for stats in stats {
+            write!(
+                out,
+                "{}  {:>8}  {:>4}×  {} – {}",
+                self.tag(&stats.name, width),
+                self.duration(stats.total),
+                stats.events,
+                self.date(stats.first),
+                self.date(stats.last),
+            )
+            .unwrap();
+            if !stats.details.is_empty() {
+                write!(out, "  ({})", stats.details.join(", ")).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+        out.pop();
+
+        out
+    }
+
+    /// Bar of how much of `goal` `value` reaches, e.g. `██████░░░░`.
+    pub fn progress(&self, value: TimeDelta, goal: TimeDelta) -> String {
+        const WIDTH: i64 = 10;
+        let filled = if goal <= TimeDelta::zero() {
+            WIDTH
+        } else {
+            (value.num_seconds() * WIDTH / goal.num_seconds()).clamp(0, WIDTH)
+        } as usize;
+        let (full, empty) = if self.plain {
+            ('#', '-')
+        } else {
+            ('█', '░')
+        };
+        let style = if value >= goal { GOOD } else { TAG };
+
+        format!(
+            "{}{}",
+            self.paint(style, full.to_string().repeat(filled)),
+            empty.to_string().repeat(WIDTH as usize - filled)
+        )
+    }
+
+    /// Goal time left and ideally left at the end of each day, with a bar
+    /// of what is left of the goal.
+    pub fn burndown(&self, start: NaiveDateTime, burndown: &Burndown) -> String {
+        const WIDTH: i64 = 10;
+        let (goal, days) = (burndown.goal, &burndown.days);
+        let mut out = self.paint(HEADING, self.heading(start));
+        let dates: Vec<String> = days.iter().map(|day| self.date(day.date)).collect();
+        let date_width = dates
+            .iter()
+            .map(|date| date.chars().count())
+            .max()
+            .unwrap_or_default();
+        let remaining: Vec<String> = days
+            .iter()
+            .map(|day| {
+                day.remaining
+                    .map(|left| self.duration(left))
+                    .unwrap_or_default()
+            })
+            .collect();
+        let ideal: Vec<String> = days.iter().map(|day| self.duration(day.ideal)).collect();
+        let left_width = remaining
+            .iter()
+            .map(|left| left.chars().count())
+            .chain([self.locale.left().chars().count()])
+            .max()
+            .unwrap_or_default();
+        let ideal_width = ideal
+            .iter()
+            .map(|ideal| ideal.chars().count())
+            .chain([self.locale.ideal().chars().count()])
+            .max()
+            .unwrap_or_default();
+
+        write!(
+            out,
+            "\n  {:date_width$}  {:>left_width$}  {:>ideal_width$}",
+            "",
+            self.locale.left(),
+            self.locale.ideal()
+        )
+        .unwrap();
+        for (index, day) in days.iter().enumerate() {
+            write!(
+                out,
+                "\n  {:date_width$}  {:>left_width$}  {:>ideal_width$}",
+                dates[index], remaining[index], ideal[index]
+            )
+            .unwrap();
+            let Some(left) = day.remaining else {
+                continue;
+            };
+            let filled = if goal <= TimeDelta::zero() {
+                0
+            } else {
+                (left.num_seconds() * WIDTH / goal.num_seconds()).clamp(0, WIDTH)
+            } as usize;
+            let (full, empty) = if self.plain {
+                ('#', '-')
+            } else {
+                ('█', '░')
+            };
+            let style = if left <= day.ideal { GOOD } else { BAD };
+            write!(
+                out,
+                "  {}{}  {}",
+                self.paint(style, full.to_string().repeat(filled)),
+                empty.to_string().repeat(WIDTH as usize - filled),
+                self.variance(day.ideal - left)
+            )
+            .unwrap();
+        }
+
+        out
+    }
+
+    /// One bar per total, scaled to the largest one.
+    pub fn sparkline(&self, totals: &[TimeDelta]) -> String {
+        let bars: &[char] = if self.plain {
+            &['.', ':', '-', '=', '+', '*', '#', '%']
+        } else {
+            &['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█']
+        };
+        let max = totals.iter().max().copied().unwrap_or_default();
+        totals
+            .iter()
+            .map(|total| {
+                if max <= TimeDelta::zero() {
+                    return bars[0];
+                }
+                let level =
+                    total.num_seconds().max(0) * (bars.len() as i64 - 1) / max.num_seconds();
+                bars[level as usize]
+            })
+            .collect()
+    }
+
+    /// Month grid starting at `week_start`, with the minutes recorded on
+    /// each day shaded by amount.
+    pub fn calendar(
+        &self,
+        month: NaiveDate,
+        week_start: Weekday,
+        days: &[(NaiveDate, TimeDelta)],
+    ) -> String {
+        const WIDTH: usize = 5;
+        let mut out = String::new();
+        writeln!(out, "{}", self.paint(HEADING, month.format("%Y-%m"))).unwrap();
+
+        let weekdays = std::iter::successors(Some(week_start), |day| Some(day.succ())).take(7);
+        for weekday in weekdays {
+            let name = match &self.display.weekdays {
+                Some(names) => names[weekday.num_days_from_monday() as usize].as_str(),
+                None => self.locale.weekday(weekday),
+            };
+            write!(out, "{:>WIDTH$}", name).unwrap();
+        }
+
+        let offset = month.weekday().days_since(week_start) as usize;
+        let dates: Vec<NaiveDate> = month
+            .iter_days()
+            .take_while(|date| date.month() == month.month())
+            .collect();
+        let empty = if self.plain { "." } else { "·" };
+        for week in std::iter::repeat_n(None, offset)
+            .chain(dates.into_iter().map(Some))
+            .collect::<Vec<_>>()
+            .chunks(7)
+        {
+            let mut numbers = String::new();
+            let mut minutes = String::new();
+            for date in week {
+                let Some(date) = date else {
+                    numbers += &" ".repeat(WIDTH);
+                    minutes += &" ".repeat(WIDTH);
+                    continue;
+                };
+                write!(numbers, "{:>WIDTH$}", date.day()).unwrap();
+                let total: TimeDelta = days
+                    .iter()
+                    .filter(|(day, _)| day == date)
+                    .map(|(_, duration)| *duration)
+                    .sum();
+                let total = total.num_minutes();
+                let text = if total == 0 {
+                    empty.to_string()
+                } else {
+                    total.to_string()
+                };
+                let padded = format!("{:>WIDTH$}", text);
+                minutes += &match total {
+                    0 => padded,
+                    1..30 => self.paint(SHADES[0], padded),
+                    30..60 => self.paint(SHADES[1], padded),
+                    60..120 => self.paint(SHADES[2], padded),
+                    _ => self.paint(SHADES[3], padded),
+                };
+            }
+            write!(out, "\n{}\n{}", numbers.trim_end(), minutes.trim_end()).unwrap();
+        }
+
+        out
+    }
+
+    /// Sessions and their average length per start hour, with the hours of
+    /// the longest and shortest sessions and the longest session of all.
+    pub fn hour_stats(
+        &self,
+        stats: &[HourStats],
+        correlation: Option<f64>,
+        longest_session: Option<&Session>,
+    ) -> String {
+        const WIDTH: i64 = 20;
+        let longest = stats.iter().max_by_key(|stats| stats.average());
+        let shortest = stats.iter().min_by_key(|stats| stats.average());
+        let max = longest.map(HourStats::average).unwrap_or_default();
+        let sessions_width = stats
+            .iter()
+            .map(|stats| stats.sessions.to_string().len())
+            .max()
+            .unwrap_or_default();
+        let averages: Vec<String> = stats
+            .iter()
+            .map(|stats| self.duration(stats.average()))
+            .collect();
+        let average_width = averages
+            .iter()
+            .map(|average| average.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        let mut lines = Vec::new();
+        for (stats, average) in stats.iter().zip(averages) {
+            let filled = if max <= TimeDelta::zero() {
+                0
+            } else {
+                stats.average().num_seconds() * WIDTH / max.num_seconds()
+            } as usize;
+            let bar = if self.plain { "#" } else { "█" }.repeat(filled.max(1));
+            lines.push(format!(
+                "  {:02}:00  {:>sessions_width$}×  {:>average_width$}  {}",
+                stats.hour,
+                stats.sessions,
+                average,
+                self.paint(TAG, bar)
+            ));
+        }
+        if let (Some(longest), Some(shortest), 2..) = (longest, shortest, stats.len()) {
+            lines.push(format!(
+                "Longest sessions start at {}:00 ({} on average), shortest at {}:00 ({})",
+                self.paint(GOOD, format!("{:02}", longest.hour)),
+                self.duration(longest.average()),
+                self.paint(BAD, format!("{:02}", shortest.hour)),
+                self.duration(shortest.average())
+            ));
+        }
+        if let Some(session) = longest_session {
+            lines.push(format!(
+                "Longest session {} on {} at {}",
+                self.paint(HEADING, self.duration(session.duration)),
+                self.date(session.start.date()),
+                session.start.format("%H:%M")
+            ));
+        }
+        if let Some(correlation) = correlation {
+            lines.push(format!(
+                "Correlation of start hour and length: {:+.2}",
+                correlation
+            ));
+        }
+
+        lines.join("\n")
+    }
+
+    /// Gaps grouped by day, followed by their number, average and longest.
+    /// All-time total, number, median and 90th percentile of the session
+    /// lengths of each entry of `stats`, with what they are mostly like, and
+    /// the days covered.
+    pub fn session_lengths(&self, stats: &[Lengths]) -> String {
+        let name_width = stats
+            .iter()
+            .map(|stats| match &stats.tag {
+                Some(tag) => self.tag_width(tag),
+                None => "All".len(),
+            })
+            .max()
+            .unwrap_or_default();
+        let columns: Vec<[String; 4]> = stats
+            .iter()
+            .map(|stats| {
+                [
+                    self.duration(stats.all_time()),
+                    format!("{}×", stats.sessions()),
+                    format!("p50 {}", self.duration(stats.percentile(50))),
+                    format!("p90 {}", self.duration(stats.percentile(90))),
+                ]
+            })
+            .collect();
+        let width = |column: usize| {
+            columns
+                .iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        };
+        let (total_width, count_width) = (width(0), width(1));
+        let (p50_width, p90_width) = (width(2), width(3));
+
+        let mut lines = Vec::new();
+        for (stats, [total, count, p50, p90]) in stats.iter().zip(columns) {
+            let name = match &stats.tag {
+                Some(tag) => self.tag(tag, name_width),
+                None => self.paint(HEADING, format!("{:name_width$}", "All")),
+            };
+            lines.push(format!(
+                "{name}  {total:>total_width$}  {count:>count_width$}  {p50:p50_width$}  {p90:p90_width$}  {}",
+                distribution(stats)
+            ));
+        }
+        if let Some((all, first, last)) = stats
+            .first()
+            .and_then(|all| Some((all, all.first?, all.last?)))
+        {
+            lines.push(format!(
+                "All time: {} from {} to {} ({} days)",
+                self.paint(HEADING, self.duration(all.all_time())),
+                self.date(first),
+                self.date(last),
+                (last - first).num_days() + 1
+            ));
+        }
+
+        lines.join("\n")
+    }
+
+    /// Sessions of each entry of `stats` by length, with the buckets of
+    /// deep work highlighted and their share.
+    pub fn histogram(&self, stats: &[Lengths]) -> String {
+        const WIDTH: usize = 20;
+        let bound = |minutes: i64| self.duration(TimeDelta::minutes(minutes));
+        let mut labels = vec![format!("<{}", bound(BUCKETS[0]))];
+        labels.extend(
+            BUCKETS
+                .windows(2)
+                .map(|pair| format!("{}–{}", bound(pair[0]), bound(pair[1]))),
+        );
+        labels.push(format!("{}+", bound(BUCKETS[BUCKETS.len() - 1])));
+        let label_width = labels
+            .iter()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        let mut lines = Vec::new();
+        for stats in stats {
+            lines.push(match &stats.tag {
+                Some(tag) => self.tag(tag, 0),
+                None => self.paint(HEADING, "All"),
+            });
+            let counts = stats.histogram();
+            let max = counts.iter().max().copied().unwrap_or_default().max(1);
+            let count_width = max.to_string().len();
+            for (index, (label, count)) in labels.iter().zip(counts).enumerate() {
+                let deep = index > 0 && BUCKETS[index - 1] >= DEEP_WORK;
+                let bar = if self.plain { "#" } else { "█" }.repeat(count * WIDTH / max);
+                let line = format!("  {label:label_width$}  {count:>count_width$}");
+                lines.push(match count {
+                    0 => line,
+                    _ if deep => format!("{line}  {}", self.paint(GOOD, bar)),
+                    _ => format!("{line}  {bar}"),
+                });
+            }
+            lines.push(format!(
+                "  deep work ({}+): {}% of sessions",
+                bound(DEEP_WORK),
+                stats.deep_work() * 100 / stats.sessions().max(1)
+            ));
+        }
+
+        lines.join("\n")
+    }
+
+    pub fn gaps(&self, gaps: &[Gap]) -> String {
+        let mut lines = Vec::new();
+        let mut date = None;
+        for gap in gaps {
+            if date != Some(gap.date) {
+                date = Some(gap.date);
+                lines.push(self.paint(HEADING, self.date(gap.date)));
+            }
+            lines.push(format!(
+                "  {}–{}  {}",
+                gap.start.format("%H:%M"),
+                gap.end.format("%H:%M"),
+                self.duration(gap.length())
+            ));
+        }
+
+        let total: TimeDelta = gaps.iter().map(Gap::length).sum();
+        let longest = gaps.iter().max_by_key(|gap| gap.length());
+        let mut summary = format!(
+            "{} gap{}",
+            gaps.len(),
+            if gaps.len() == 1 { "" } else { "s" }
+        );
+        if let Some(longest) = longest {
+            write!(
+                summary,
+                ", {} on average, longest {} on {} at {}",
+                self.duration(TimeDelta::minutes(total.num_minutes() / gaps.len() as i64)),
+                self.paint(HEADING, self.duration(longest.length())),
+                self.date(longest.date),
+                longest.start.format("%H:%M")
+            )
+            .unwrap();
+        }
+        lines.push(summary);
+
+        lines.join("\n")
+    }
+
+    /// The recurring sessions of the week starting at `start` by day, with
+    /// whether each was fulfilled by `today`.
+    pub fn planned_slots(
+        &self,
+        start: NaiveDateTime,
+        slots: &[PlannedSlot],
+        today: NaiveDate,
+    ) -> String {
+        let tag_width = slots
+            .iter()
+            .map(|slot| self.tag_width(&slot.tag))
+            .max()
+            .unwrap_or_default();
+        let mut lines = vec![self.paint(HEADING, self.heading(start))];
+        let mut date = None;
+        let mut counts = [0; 4];
+        for slot in slots {
+            if date != Some(slot.date) {
+                date = Some(slot.date);
+                lines.push(format!("  {}", self.date(slot.date)));
+            }
+            let status = slot.status(today);
+            let (style, label) = match status {
+                SlotStatus::Fulfilled => (GOOD, "done"),
+                SlotStatus::Partial => (WARNING, "partial"),
+                SlotStatus::Missed => (BAD, "missed"),
+                SlotStatus::Upcoming => (Style::new(), "upcoming"),
+            };
+            counts[status as usize] += 1;
+            lines.push(format!(
+                "    {}  {:>8} / {:<8}  {}",
+                self.tag(&slot.tag, tag_width),
+                self.duration(slot.actual),
+                self.duration(slot.planned),
+                self.paint(style, label)
+            ));
+        }
+        let [fulfilled, partial, missed, upcoming] = counts;
+        lines.push(format!(
+            "{} done, {} partial, {} missed, {} upcoming",
+            fulfilled, partial, missed, upcoming
+        ));
+
+        lines.join("\n")
+    }
+
+    /// Table of estimate accuracy: estimated events, estimated and actual
+    /// time, their ratio and the average error.
+    pub fn estimate_stats(&self, stats: &[EstimateStats]) -> String {
+        let width = stats
+            .iter()
+            .map(|stats| self.tag_width(&stats.tag))
+            .max()
+            .unwrap_or_default();
+        let mut lines = Vec::new();
+        for stats in stats {
+            let mut line = format!("  {}", self.tag(&stats.tag, width));
+            if let Some(month) = stats.month {
+                write!(line, "  {}", month.format("%Y-%m")).unwrap();
+            }
+            let ratio = match stats.ratio() {
+                Some(ratio) => {
+                    let style = if (ratio - 1.0).abs() <= 0.1 {
+                        GOOD
+                    } else {
+                        BAD
+                    };
+                    self.paint(style, format!("{:.2}×", ratio))
+                }
+                None => "-".to_string(),
+            };
+            write!(
+                line,
+                "  {:>4} {:6}  {:>8} estimated  {:>8} actual  {}  ±{:.0}%",
+                stats.events,
+                if stats.events == 1 { "event" } else { "events" },
+                self.duration(stats.estimated),
+                self.duration(stats.actual),
+                ratio,
+                stats.mean_error() * 100.0
+            )
+            .unwrap();
+            lines.push(line);
+        }
+
+        lines.join("\n")
+    }
+
+    /// Current and longest streak of logged days and the share of days
+    /// logged.
+    pub fn streaks(&self, streaks: &Streaks) -> String {
+        let days = |count: usize| format!("{} day{}", count, if count == 1 { "" } else { "s" });
+        let mut longest = days(streaks.longest);
+        if let Some(end) = streaks.longest_end {
+            write!(longest, " (until {})", self.date(end)).unwrap();
+        }
+        let style = if streaks.current > 0 { GOOD } else { BAD };
+
+        [
+            format!(
+                "Current streak  {}",
+                self.paint(style, days(streaks.current))
+            ),
+            format!("Longest streak  {}", longest),
+            format!(
+                "Logged          {} of {} ({:.0}%), {} with time recorded",
+                streaks.logged,
+                days(streaks.days),
+                streaks.consistency() * 100.0,
+                streaks.active
+            ),
+        ]
+        .join("\n")
+    }
+
+    /// Dates covered by the record file, how many days have events, its
+    /// tags and size.
+    pub fn overview(&self, overview: &Overview) -> String {
+        let range = match (overview.first, overview.last) {
+            (Some(first), Some(last)) => format!(
+                "{} – {} ({} days)",
+                self.date(first),
+                self.date(last),
+                overview.span()
+            ),
+            _ => "no day records".to_string(),
+        };
+        let coverage = overview.coverage();
+        let style = if coverage >= 50.0 { GOOD } else { WARNING };
+
+        [
+            format!("Records   {range}"),
+            format!(
+                "Days      {} with events in {} day records",
+                overview.days, overview.records
+            ),
+            format!("Coverage  {}", self.paint(style, format!("{coverage:.1}%"))),
+            format!("Tags      {}", overview.tags),
+            format!("Size      {}", file_size(overview.bytes)),
+        ]
+        .join("\n")
+    }
+
+    /// Days and events in only one of two files: `-` for the first, `+`
+    /// for the second, with events under the date they belong to.
+    pub fn file_diff(&self, differences: &[Difference]) -> String {
+        let mut lines = Vec::new();
+        let mut date = None;
+        for difference in differences {
+            let (sign, style) = match difference.side {
+                Side::First => ("-", BAD),
+                Side::Second => ("+", GOOD),
+            };
+            match &difference.event {
+                None => lines.push(self.paint(style, format!("{} {}", sign, difference.date))),
+                Some(event) => {
+                    if date != Some(difference.date) {
+                        lines.push(format!("  {}", difference.date));
+                    }
+                    lines.push(self.paint(style, format!("{}   {}", sign, event)));
+                }
+            }
+            date = Some(difference.date);
+        }
+
+        lines.join("\n")
+    }
+
+    /// Time per tag in two periods and its change from the first to the
+    /// second, then the totals.
+    pub fn period_diff(
+        &self,
+        first: &PeriodReport,
+        second: &PeriodReport,
+        changes: &[(String, TimeDelta, TimeDelta)],
+    ) -> String {
+        let width = changes
+            .iter()
+            .map(|(tag, _, _)| self.tag_width(tag))
+            .chain([self.locale.total().chars().count()])
+            .max()
+            .unwrap_or_default();
+        let heading = format!(
+            "{} → {}",
+            self.period_heading(first),
+            self.date(second.start.date())
+        );
+        let mut lines = vec![self.paint(HEADING, heading)];
+        for (tag, before, after) in changes {
+            lines.push(format!(
+                "  {}  {:>8} → {:<8}  {}",
+                self.tag(tag, width),
+                self.duration(*before),
+                self.duration(*after),
+                self.variance(*after - *before)
+            ));
+        }
+        lines.push(format!(
+            "  {:width$}  {:>8} → {:<8}  {}",
+            self.locale.total(),
+            self.duration(first.total),
+            self.duration(second.total),
+            self.variance(second.total - first.total)
+        ));
+
+        lines.join("\n")
+    }
+
+    /// One line per diagnostic of `doctor`, followed by its hint.
+    pub fn check(&self, check: &Check) -> String {
+        let (symbol, word, style) = match check.severity {
+            Severity::Ok => ("✔", "ok", GOOD),
+            Severity::Warning => ("⚠", "warning", WARNING),
+            Severity::Error => ("✘", "error", BAD),
+        };
+        let status = if self.plain { word } else { symbol };
+        let mut out = format!("{} {}", self.paint(style, status), check.message);
+        if let Some(hint) = &check.hint {
+            let arrow = if self.plain { "hint:" } else { "→" };
+            write!(out, "\n    {} {}", arrow, hint).unwrap();
+        }
+
+        out
+    }
+
+    pub fn error(&self, err: impl Display) -> String {
+        let prefix = if self.plain { "error:" } else { "🛑" };
+        paint(self.error_color, ERROR, format!("{} {}", prefix, err))
+    }
+
+    pub fn report(&self, format: ReportFormat, report: &PeriodReport, extras: &Extras) -> String {
+        match format {
+            ReportFormat::Text => self.weekly_report(report, extras),
+            ReportFormat::Markdown => self.markdown_report(report, extras),
+            ReportFormat::Json => json_report(report, extras, &self.tags),
+            ReportFormat::Template => unreachable!("templates are rendered by the template module"),
+        }
+    }
+
+    pub fn weekly_report(&self, report: &PeriodReport, extras: &Extras) -> String {
+        let (goal, earnings) = (extras.goal, extras.earnings.as_ref());
+        let mut out = String::new();
+
+        let heading = self.period_heading(report);
+        writeln!(out, "{}", self.paint(HEADING, heading)).unwrap();
+        if !extras.trend.is_empty() {
+            writeln!(out, "  {}", self.sparkline(&extras.trend)).unwrap();
+        }
+
+        let width = report
+            .tags
+            .iter()
+            .map(|(title, _)| self.tag_width(title))
+            .max()
+            .unwrap_or_default();
+        let times: Vec<String> = report
+            .tags
+            .iter()
+            .map(|(title, duration)| match tag_goal(extras, title) {
+                Some(goal) => format!("{} / {}", self.duration(*duration), self.duration(goal)),
+                None => self.duration(*duration),
+            })
+            .collect();
+        let time_width = times
+            .iter()
+            .map(|time| time.chars().count())
+            .max()
+            .unwrap_or_default();
+        for ((title, duration), time) in report.tags.iter().zip(times) {
+            write!(out, "  {}  ", self.tag(title, width)).unwrap();
+            match tag_goal(extras, title) {
+                Some(goal) => write!(
+                    out,
+                    "{time:time_width$}  {}",
+                    self.progress(*duration, goal)
+                )
+                .unwrap(),
+                None => write!(out, "{time}").unwrap(),
+            }
+            if let Some(amount) = earnings.and_then(|earnings| tag_earnings(earnings, title)) {
+                write!(out, "  {}", self.money(amount)).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+
+        if !report.corrections.is_empty() {
+            writeln!(out, "{}", self.paint(HEADING, self.locale.corrections())).unwrap();
+            for correction in &report.corrections {
+                writeln!(
+                    out,
+                    "  {}  {}  {}",
+                    self.date(correction.date),
+                    self.paint(TAG, correction.tags.join(" ")),
+                    self.paint(WARNING, self.duration(correction.duration))
+                )
+                .unwrap();
+            }
+        }
+
+        if !extras.schedule.is_empty() {
+            writeln!(out, "{}", self.paint(HEADING, self.locale.plan())).unwrap();
+            for day in &extras.schedule {
+                writeln!(
+                    out,
+                    "  {}  {} / {}  {}",
+                    self.date(day.date),
+                    self.duration(day.actual),
+                    self.duration(day.planned),
+                    self.variance(day.variance())
+                )
+                .unwrap();
+            }
+        }
+
+        let total = self.duration(report.total);
+        let total = match goal {
+            Some(goal) => {
+                let style = if report.total >= goal { GOOD } else { BAD };
+                format!(
+                    "{} / {}  {}",
+                    self.paint(style, total),
+                    self.duration(goal),
+                    self.progress(report.total, goal)
+                )
+            }
+            None => self.paint(HEADING, total),
+        };
+        write!(out, "{} {}", self.locale.total(), total).unwrap();
+        if report.pomodoros > 0 {
+            write!(out, " ({})", self.locale.pomodoros(report.pomodoros)).unwrap();
+        }
+        if let Some((average, weeks)) = extras.average {
+            write!(
+                out,
+                "  {} {}",
+                self.average_label(report, weeks),
+                self.duration(average)
+            )
+            .unwrap();
+        }
+        if let Some(earnings) = earnings {
+            write!(
+                out,
+                "\n{} {}, {} {}",
+                self.locale.earnings(),
+                self.paint(HEADING, self.money(earnings.week)),
+                self.money(earnings.month),
+                self.locale.this_month()
+            )
+            .unwrap();
+        }
+        for (name, value) in &extras.metrics {
+            write!(out, "\n{} {}", name, metric(value)).unwrap();
+        }
+
+        out
+    }
+
+    fn markdown_report(&self, report: &PeriodReport, extras: &Extras) -> String {
+        let (goal, earnings) = (extras.goal, extras.earnings.as_ref());
+        let locale = self.locale;
+        let mut out = String::new();
+
+        let time = |title: &str, duration: TimeDelta| match tag_goal(extras, title) {
+            Some(goal) => format!("{} / {}", self.duration(duration), self.duration(goal)),
+            None => self.duration(duration),
+        };
+
+        writeln!(out, "## {}", self.period_heading(report)).unwrap();
+        writeln!(out).unwrap();
+        if !extras.trend.is_empty() {
+            writeln!(out, "`{}`", self.sparkline(&extras.trend)).unwrap();
+            writeln!(out).unwrap();
+        }
+        if !report.tags.is_empty() {
+            match earnings {
+                Some(earnings) => {
+                    writeln!(
+                        out,
+                        "| {} | {} | {} |",
+                        locale.tag(),
+                        locale.time(),
+                        locale.earnings()
+                    )
+                    .unwrap();
+                    writeln!(out, "| --- | ---: | ---: |").unwrap();
+                    for (title, duration) in &report.tags {
+                        let amount = tag_earnings(earnings, title)
+                            .map(|amount| self.money(amount))
+                            .unwrap_or_default();
+                        writeln!(
+                            out,
+                            "| {} | {} | {} |",
+                            title,
+                            time(title, *duration),
+                            amount
+                        )
+                        .unwrap();
+                    }
+                }
+                None => {
+                    writeln!(out, "| {} | {} |", locale.tag(), locale.time()).unwrap();
+                    writeln!(out, "| --- | ---: |").unwrap();
+                    for (title, duration) in &report.tags {
+                        writeln!(out, "| {} | {} |", title, time(title, *duration)).unwrap();
+                    }
+                }
+            }
+            writeln!(out).unwrap();
+        }
+
+        if !report.corrections.is_empty() {
+            writeln!(
+                out,
+                "| {} | {} | {} |",
+                locale.day(),
+                locale.corrections(),
+                locale.time()
+            )
+            .unwrap();
+            writeln!(out, "| --- | --- | ---: |").unwrap();
+            for correction in &report.corrections {
+                writeln!(
+                    out,
+                    "| {} | {} | {} |",
+                    self.date(correction.date),
+                    correction.tags.join(" "),
+                    self.duration(correction.duration)
+                )
+                .unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+
+        if !extras.schedule.is_empty() {
+            writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                locale.day(),
+                locale.time(),
+                locale.plan(),
+                locale.variance()
+            )
+            .unwrap();
+            writeln!(out, "| --- | ---: | ---: | ---: |").unwrap();
+            for day in &extras.schedule {
+                writeln!(
+                    out,
+                    "| {} | {} | {} | {} |",
+                    self.date(day.date),
+                    self.duration(day.actual),
+                    self.duration(day.planned),
+                    self.variance(day.variance())
+                )
+                .unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+
+        write!(
+            out,
+            "**{}:** {}",
+            locale.total(),
+            self.duration(report.total)
+        )
+        .unwrap();
+        if let Some(goal) = goal {
+            write!(out, " / {}", self.duration(goal)).unwrap();
+        }
+        if report.pomodoros > 0 {
+            write!(out, " ({})", locale.pomodoros(report.pomodoros)).unwrap();
+        }
+        if let Some((average, weeks)) = extras.average {
+            write!(
+                out,
+                "  \n**{}:** {}",
+                self.average_label(report, weeks),
+                self.duration(average)
+            )
+            .unwrap();
+        }
+        if let Some(earnings) = earnings {
+            write!(
+                out,
+                "  \n**{}:** {} ({} {})",
+                locale.earnings(),
+                self.money(earnings.week),
+                self.money(earnings.month),
+                locale.this_month()
+            )
+            .unwrap();
+        }
+        for (name, value) in &extras.metrics {
+            write!(out, "  \n**{}:** {}", name, metric(value)).unwrap();
+        }
+
+        out
+    }
+}
+
+#[derive(serde::Serialize, schemars::JsonSchema)]
+#[schemars(rename = "Report")]
+struct JsonReport<'a> {
+    start: chrono::NaiveDateTime,
+    total_minutes: i64,
+    goal_minutes: Option<i64>,
+    /// Pomodoros of the sessions written like `3p`.
+    #[serde(skip_serializing_if = "is_zero")]
+    pomodoros: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    earnings: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    month_earnings: Option<f64>,
+    tags: Vec<JsonTag<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    days: Vec<JsonDay>,
+    /// Entries such as `[rust] !-30m` in the period.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    corrections: Vec<JsonCorrection<'a>>,
+    /// Set by the `report.script`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    metrics: &'a BTreeMap<String, serde_json::Value>,
+    /// Totals of the last weeks, oldest first, with `--trend`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    trend_minutes: Vec<i64>,
+    /// Mean total of the last completed weeks, up to four.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    average_minutes: Option<i64>,
+}
+
+#[derive(serde::Serialize, schemars::JsonSchema)]
+#[schemars(rename = "ReportDay")]
+struct JsonDay {
+    date: NaiveDate,
+    minutes: i64,
+    planned_minutes: i64,
+    variance_minutes: i64,
+}
+
+#[derive(serde::Serialize, schemars::JsonSchema)]
+#[schemars(rename = "ReportCorrection")]
+struct JsonCorrection<'a> {
+    date: NaiveDate,
+    line: usize,
+    tags: &'a [String],
+    /// Negative.
+    minutes: i64,
+}
+
+#[derive(serde::Serialize, schemars::JsonSchema)]
+#[schemars(rename = "ReportTag")]
+struct JsonTag<'a> {
+    tag: &'a str,
+    minutes: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    goal_minutes: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    earnings: Option<f64>,
+    /// `#rrggbb` from `[tags]`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icon: Option<&'a str>,
+}
+
+fn is_zero(count: &u32) -> bool {
+    *count == 0
+}
+
+/// Goal of `tag`, `None` if it has none.
+pub fn tag_goal(extras: &Extras, tag: &str) -> Option<TimeDelta> {
+    extras
+        .tag_goals
+        .iter()
+        .find(|(title, _)| title == tag)
+        .map(|(_, goal)| *goal)
+}
+
+/// Earnings of `tag`, `None` if it has no rate.
+pub fn tag_earnings(earnings: &Earnings, tag: &str) -> Option<f64> {
+    earnings
+        .tags
+        .iter()
+        .find(|(title, _)| title == tag)
+        .map(|(_, amount)| *amount)
+}
+
+/// A script metric as shown in text reports: strings without quotes, the
+/// rest as JSON.
+fn metric(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// JSON Schema of `report --format json`.
+pub fn report_schema() -> schemars::Schema {
+    schemars::schema_for!(JsonReport)
+}
+
+fn json_report(
+    report: &PeriodReport,
+    extras: &Extras,
+    styles: &BTreeMap<String, TagStyle>,
+) -> String {
+    let (goal, earnings) = (extras.goal, extras.earnings.as_ref());
+    let json = JsonReport {
+        start: report.start,
+        total_minutes: report.total.num_minutes(),
+        goal_minutes: goal.map(|goal| goal.num_minutes()),
+        pomodoros: report.pomodoros,
+        earnings: earnings.map(|earnings| earnings.week),
+        month_earnings: earnings.map(|earnings| earnings.month),
+        tags: report
+            .tags
+            .iter()
+            .map(|(title, duration)| JsonTag {
+                tag: title,
+                minutes: duration.num_minutes(),
+                goal_minutes: tag_goal(extras, title).map(|goal| goal.num_minutes()),
+                earnings: earnings.and_then(|earnings| tag_earnings(earnings, title)),
+                color: styles
+                    .get(title)
+                    .and_then(|style| style.color)
+                    .map(|color| color.hex()),
+                icon: styles.get(title).and_then(|style| style.icon.as_deref()),
+            })
+            .collect(),
+        days: extras
+            .schedule
+            .iter()
+            .map(|day| JsonDay {
+                date: day.date,
+                minutes: day.actual.num_minutes(),
+                planned_minutes: day.planned.num_minutes(),
+                variance_minutes: day.variance().num_minutes(),
+            })
+            .collect(),
+        corrections: report
+            .corrections
+            .iter()
+            .map(|correction| JsonCorrection {
+                date: correction.date,
+                line: correction.line,
+                tags: &correction.tags,
+                minutes: correction.duration.num_minutes(),
+            })
+            .collect(),
+        metrics: &extras.metrics,
+        trend_minutes: extras.trend.iter().map(TimeDelta::num_minutes).collect(),
+        average_minutes: extras.average.map(|(average, _)| average.num_minutes()),
+    };
+
+    serde_json::to_string_pretty(&json).expect("report is always serializable")
+}
+
+fn paint(color: bool, style: Style, text: impl Display) -> String {
+    if color {
+        format!("{style}{text}{style:#}")
+    } else {
+        text.to_string()
+    }
+}
+
+/// What the sessions of `stats` are mostly like, from their median and 90th
+/// percentile.
+fn distribution(stats: &Lengths) -> &'static str {
+    if stats.sessions() == 0 {
+        return "no sessions";
+    }
+    let (median, p90) = (stats.percentile(50), stats.percentile(90));
+    let long_tail = p90 >= median * 3 && p90 >= TimeDelta::minutes(30);
+    match (median.num_minutes(), long_tail) {
+        (..15, false) => "many very short sessions",
+        (..15, true) => "many very short sessions, a few long ones",
+        (15..30, false) => "many short sessions",
+        (15..30, true) => "many short sessions, a few long ones",
+        (30..60, false) => "sessions of half an hour to an hour",
+        (30..60, true) => "sessions of half an hour to an hour, a few much longer",
+        (_, false) => "a few long sessions",
+        (_, true) => "a few long sessions, some very long",
+    }
+}
+
+/// `bytes` in B, KiB or MiB.
+fn file_size(bytes: u64) -> String {
+    match bytes {
+        ..1024 => format!("{bytes} B"),
+        1024..1_048_576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
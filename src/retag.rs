@@ -0,0 +1,135 @@
+use std::{collections::BTreeSet, ops::Range};
+
+use crate::{ast, processing};
+
+/// Outcome of rewriting the tags of a record file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rewrite {
+    pub content: String,
+    /// Changed lines in file order.
+    pub lines: Vec<Line>,
+    /// Number of tags replaced or removed.
+    pub tags: usize,
+    /// Number of events changed.
+    pub events: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    /// Line number, starting at 1.
+    pub number: usize,
+    pub before: String,
+    pub after: String,
+}
+
+enum Edit {
+    /// Replaces the title as written in the source.
+    Title(Range<usize>),
+    /// Removes the whole tag with the spaces before it.
+    Remove(Range<usize>),
+}
+
+/// Replaces every tag titled `old` in `source`, which `file` was parsed from,
+/// with `new`, keeping details and everything else as written.
+pub fn rename(source: &str, file: &ast::File, old: &str, new: &str) -> Rewrite {
+    merge(source, file, &[old], new)
+}
+
+/// Replaces every tag titled one of `tags` with `into`.
+///
+/// An event left with the same tag twice keeps the first one, unless the
+/// later one carries another detail. Records pulled in with `@include` are
+/// left alone. Under `normalize_tags` the titles are compared in normalized
+/// form.
+pub fn merge(source: &str, file: &ast::File, tags: &[&str], into: &str) -> Rewrite {
+    let mut chars: Vec<char> = source.chars().collect();
+    let mut edits = Vec::new();
+    let mut numbers = BTreeSet::new();
+    let mut events = 0;
+
+    for record in file.records.iter().filter(|record| record.origin.is_none()) {
+        let normalize = file
+            .settings_at(record.date)
+            .is_some_and(|settings| settings.normalize_tags);
+        let same = |a: &str, b: &str| {
+            a == b || normalize && processing::normalize_tag(a) == processing::normalize_tag(b)
+        };
+
+        for event in &record.events {
+            let mut event_edits = Vec::new();
+            let mut any_renamed = false;
+            let mut kept: Vec<Option<&str>> = Vec::new();
+            for tag in event.tags.iter().flat_map(|tags| &tags.tags) {
+                let renamed = tags.iter().any(|old| same(&tag.title, old));
+                if !renamed && !same(&tag.title, into) {
+                    continue;
+                }
+
+                any_renamed |= renamed;
+                let detail = tag.detail.as_deref();
+                let end = tag.span.end.min(chars.len());
+                if kept.iter().any(|kept| detail.is_none() || *kept == detail) {
+                    event_edits.push(Edit::Remove(tag.span.start..end));
+                } else {
+                    kept.push(detail);
+                    if renamed {
+                        event_edits.push(Edit::Title(tag.title_span.clone()));
+                    }
+                }
+            }
+            // Tags already duplicated elsewhere are not ours to clean up.
+            if any_renamed {
+                edits.extend(event_edits);
+                events += 1;
+                numbers.insert(event.line);
+            }
+        }
+    }
+
+    // Later edits first, so that earlier positions stay valid.
+    let count = edits.len();
+    edits.sort_by_key(|edit| {
+        std::cmp::Reverse(match edit {
+            Edit::Title(span) | Edit::Remove(span) => span.start,
+        })
+    });
+    for edit in edits {
+        match edit {
+            Edit::Title(span) => {
+                chars.splice(span, into.chars());
+            }
+            Edit::Remove(span) => {
+                let mut start = span.start;
+                while start > 0 && chars[start - 1] == ' ' {
+                    start -= 1;
+                }
+                chars.drain(start..span.end);
+            }
+        }
+    }
+    let content: String = chars.into_iter().collect();
+
+    let lines = numbers
+        .into_iter()
+        .map(|number| Line {
+            number,
+            before: source
+                .lines()
+                .nth(number - 1)
+                .unwrap_or_default()
+                .to_string(),
+            after: content
+                .lines()
+                .nth(number - 1)
+                .unwrap_or_default()
+                .to_string(),
+        })
+        .collect();
+
+    Rewrite {
+        content,
+        lines,
+        tags: count,
+        events,
+    }
+}
@@ -0,0 +1,136 @@
+use std::{collections::BTreeMap, path::Path};
+
+use chrono::{NaiveDate, NaiveTime, TimeDelta};
+use rhai::{Dynamic, Engine, Scope};
+
+use crate::{
+    ast,
+    processing::{Filter, PeriodReport},
+};
+
+/// A session as seen by scripts in `events`.
+#[derive(serde::Serialize)]
+struct Event<'a> {
+    date: NaiveDate,
+    /// `()` for sessions written without a start.
+    time: Option<NaiveTime>,
+    minutes: i64,
+    tags: Vec<&'a str>,
+    /// Detail of each tag, `()` where there is none.
+    details: Vec<Option<&'a str>>,
+    line: usize,
+}
+
+/// The report as seen by scripts in `report`. Changes to `total_minutes`,
+/// `tags` and `days` are rendered.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Report {
+    start: String,
+    end: String,
+    total_minutes: i64,
+    tags: Vec<Tag>,
+    days: Vec<Day>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Tag {
+    tag: String,
+    minutes: i64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Day {
+    date: NaiveDate,
+    minutes: i64,
+}
+
+/// Runs the Rhai script at `path` over `report` and the sessions of `file`
+/// it was computed from, returning the `metrics` the script set.
+pub fn run(
+    path: &Path,
+    file: &ast::File,
+    filter: &Filter,
+    report: &mut PeriodReport,
+) -> Result<BTreeMap<String, serde_json::Value>, String> {
+    let mut events = Vec::new();
+    for record in &file.records {
+        for event in &record.events {
+            let tags = event.tags.iter().flat_map(|tags| &tags.tags);
+            for info in &event.info {
+                let start = record
+                    .date
+                    .and_time(info.time.unwrap_or(report.start.time()));
+                if start < report.start
+                    || start >= report.end
+                    || !filter.matches_session(record, event, info)
+                {
+                    continue;
+                }
+                events.push(Event {
+                    date: record.date,
+                    time: info.time,
+                    minutes: info.duration.num_minutes(),
+                    tags: tags.clone().map(|tag| tag.title.as_str()).collect(),
+                    details: tags.clone().map(|tag| tag.detail.as_deref()).collect(),
+                    line: event.line,
+                });
+            }
+        }
+    }
+    let input = Report {
+        start: report.start.to_string(),
+        end: report.end.to_string(),
+        total_minutes: report.total.num_minutes(),
+        tags: report
+            .tags
+            .iter()
+            .map(|(tag, duration)| Tag {
+                tag: tag.clone(),
+                minutes: duration.num_minutes(),
+            })
+            .collect(),
+        days: report
+            .days
+            .iter()
+            .map(|(date, duration)| Day {
+                date: *date,
+                minutes: duration.num_minutes(),
+            })
+            .collect(),
+    };
+
+    let mut scope = Scope::new();
+    scope.push("events", to_dynamic(&events)?);
+    scope.push("report", to_dynamic(&input)?);
+    scope.push("metrics", rhai::Map::new());
+    Engine::new()
+        .run_file_with_scope(&mut scope, path.to_path_buf())
+        .map_err(|err| err.to_string())?;
+
+    let output: Report = from_dynamic(scope.get("report"), "report")?;
+    report.total = TimeDelta::minutes(output.total_minutes);
+    report.tags = output
+        .tags
+        .into_iter()
+        .map(|tag| (tag.tag, TimeDelta::minutes(tag.minutes)))
+        .collect();
+    report.days = output
+        .days
+        .into_iter()
+        .map(|day| (day.date, TimeDelta::minutes(day.minutes)))
+        .collect();
+
+    from_dynamic(scope.get("metrics"), "metrics")
+}
+
+fn to_dynamic(value: &impl serde::Serialize) -> Result<Dynamic, String> {
+    rhai::serde::to_dynamic(value).map_err(|err| err.to_string())
+}
+
+fn from_dynamic<T: serde::de::DeserializeOwned>(
+    value: Option<&Dynamic>,
+    name: &str,
+) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("`{name}` was removed"))?;
+    rhai::serde::from_dynamic(value).map_err(|err| format!("invalid `{name}`: {err}"))
+}
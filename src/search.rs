@@ -0,0 +1,58 @@
+use chrono::NaiveDate;
+
+use crate::{ast, processing::Filter, query::Query};
+
+/// Restricts a search to some dates, both inclusive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DateRange {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl DateRange {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from.is_none_or(|from| from <= date) && self.to.is_none_or(|to| date <= to)
+    }
+}
+
+/// An event matching a search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hit<'a> {
+    pub record: &'a ast::DayRecord,
+    pub event: &'a ast::Event,
+}
+
+/// Finds the events with a session matching `query`.
+pub fn search<'a>(file: &'a ast::File, query: &Query, range: DateRange) -> Vec<Hit<'a>> {
+    let mut hits: Vec<Hit> = file
+        .records
+        .iter()
+        .filter(|record| range.contains(record.date))
+        .flat_map(|record| record.events.iter().map(move |event| Hit { record, event }))
+        .filter(|hit| {
+            hit.event
+                .info
+                .iter()
+                .any(|info| query.matches(hit.record, hit.event, info))
+        })
+        .collect();
+    hits.sort_by_key(|hit| hit.record.date);
+
+    hits
+}
+
+/// The events kept by `filter`, newest first, at most `limit` of them.
+pub fn recent<'a>(file: &'a ast::File, filter: &Filter, limit: Option<usize>) -> Vec<Hit<'a>> {
+    let mut hits: Vec<Hit> = file
+        .records
+        .iter()
+        .flat_map(|record| record.events.iter().map(move |event| Hit { record, event }))
+        .filter(|hit| filter.matches(hit.event))
+        .collect();
+    // Stable, so events of one day stay in file order before the reversal.
+    hits.sort_by_key(|hit| hit.record.date);
+    hits.reverse();
+    hits.truncate(limit.unwrap_or(usize::MAX));
+
+    hits
+}
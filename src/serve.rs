@@ -0,0 +1,215 @@
+use std::{
+    fs,
+    path::Path,
+    time::{Duration as StdDuration, SystemTime},
+};
+
+use chrono::NaiveDateTime;
+use tiny_http::{Header, Method, ReadWrite, Request, Response, Server, StatusCode};
+use tungstenite::{protocol::Role, Message, WebSocket};
+
+use crate::{
+    error::Error,
+    import::{Entry, Import, NewEvent},
+};
+
+/// How often the record file is checked for changes to push to `/live`.
+const POLL: StdDuration = StdDuration::from_millis(500);
+
+/// Number of weeks up to this one listed by `/days`.
+pub const DAYS_WEEKS: usize = 26;
+
+/// Single page shown at `/`, built on the other routes.
+const DASHBOARD: &str = include_str!("dashboard.html");
+
+type Client = WebSocket<Box<dyn ReadWrite + Send>>;
+
+/// What the routes do. Each call reads the record file again, so that
+/// edits made meanwhile are seen.
+pub struct Routes<'a> {
+    /// The record file, watched for changes to push to `/live`.
+    pub file: &'a Path,
+    /// This week's report as JSON, like `report --format json`.
+    pub report: Box<dyn Fn() -> Result<String, Error> + 'a>,
+    /// `{"date", "minutes"}` of each day of the last [`DAYS_WEEKS`] weeks.
+    pub days: Box<dyn Fn() -> Result<String, Error> + 'a>,
+    /// Current wall-clock time of the record file.
+    pub now: Box<dyn Fn() -> Result<NaiveDateTime, Error> + 'a>,
+    /// Adds a session to the record file.
+    pub add: Box<dyn Fn(Entry) -> Result<Import, Error> + 'a>,
+}
+
+/// Serves `routes` on `address` until the process is stopped.
+///
+/// `POST` requests must send `Authorization: Bearer TOKEN`; without a
+/// `token` they are refused. WebSocket clients of `/live` get the report
+/// when they connect and again whenever the record file changes.
+pub fn run(address: &str, token: Option<&str>, routes: &Routes) -> Result<(), String> {
+    let server = Server::http(address).map_err(|err| err.to_string())?;
+    eprintln!("listening on http://{}", server.server_addr());
+    let mut clients: Vec<Client> = Vec::new();
+    let mut seen = stamp(routes.file);
+    loop {
+        if let Some(mut request) = server.recv_timeout(POLL).map_err(|err| err.to_string())? {
+            if request.url() == "/live" && request.method() == &Method::Get {
+                if let Some(client) = connect(request, routes) {
+                    clients.push(client);
+                }
+                continue;
+            }
+
+            let (status, body) = handle(&mut request, token, routes);
+            tracing::info!(method = %request.method(), url = request.url(), status);
+            let content_type = match request.url() {
+                "/" if status == 200 => "text/html; charset=utf-8",
+                _ => "application/json",
+            };
+            let response = Response::from_string(body)
+                .with_status_code(status)
+                .with_header(header("Content-Type", content_type));
+            if let Err(err) = request.respond(response) {
+                tracing::warn!(%err, "cannot respond");
+            }
+        }
+
+        let stamp = stamp(routes.file);
+        if stamp != seen {
+            seen = stamp;
+            if !clients.is_empty() {
+                match (routes.report)() {
+                    Ok(json) => clients.retain_mut(|client| push(client, &json)),
+                    Err(err) => tracing::warn!(%err, "cannot report the changed file"),
+                }
+            }
+        }
+    }
+}
+
+/// Modification time and length of `path`, `None` if unreadable.
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Completes the WebSocket handshake of `request` and sends the report.
+fn connect(request: Request, routes: &Routes) -> Option<Client> {
+    let key = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Sec-WebSocket-Key"))
+        .map(|header| tungstenite::handshake::derive_accept_key(header.value.as_bytes()));
+    let Some(accept) = key else {
+        let (status, body) = error(400, "expected a WebSocket upgrade");
+        let response = Response::from_string(body).with_status_code(status);
+        if let Err(err) = request.respond(response) {
+            tracing::warn!(%err, "cannot respond");
+        }
+        return None;
+    };
+
+    tracing::info!(url = request.url(), "WebSocket client connected");
+    let response =
+        Response::empty(StatusCode(101)).with_header(header("Sec-WebSocket-Accept", &accept));
+    let stream = request.upgrade("websocket", response);
+    let mut client = WebSocket::from_raw_socket(stream, Role::Server, None);
+    let json = match (routes.report)() {
+        Ok(json) => json,
+        Err(err) => error(500, err).1,
+    };
+
+    push(&mut client, &json).then_some(client)
+}
+
+/// Sends `json` to `client`; `false` once it is gone.
+fn push(client: &mut Client, json: &str) -> bool {
+    match client.send(Message::text(json)) {
+        Ok(()) => true,
+        Err(err) => {
+            tracing::info!(%err, "WebSocket client disconnected");
+            false
+        }
+    }
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field, value).expect("the header is valid")
+}
+
+fn handle(request: &mut Request, token: Option<&str>, routes: &Routes) -> (u16, String) {
+    let path = request.url().split('?').next().unwrap_or_default();
+    match (request.method(), path) {
+        (Method::Get, "/") => (200, DASHBOARD.to_string()),
+        (Method::Get, "/report") => match (routes.report)() {
+            Ok(json) => (200, json),
+            Err(err) => error(500, err),
+        },
+        (Method::Get, "/days") => match (routes.days)() {
+            Ok(json) => (200, json),
+            Err(err) => error(500, err),
+        },
+        (Method::Post, "/events") => {
+            let Some(token) = token else {
+                return error(403, "writing is disabled without a token");
+            };
+            if !authorized(request, token) {
+                return error(401, "missing or wrong bearer token");
+            }
+            add(request, routes)
+        }
+        (_, "/" | "/report" | "/days" | "/events") => error(405, "method not allowed"),
+        _ => error(404, "not found"),
+    }
+}
+
+fn add(request: &mut Request, routes: &Routes) -> (u16, String) {
+    let mut body = String::new();
+    if let Err(err) = request.as_reader().read_to_string(&mut body) {
+        return error(400, err);
+    }
+    let event: NewEvent = match serde_json::from_str(&body) {
+        Ok(event) => event,
+        Err(err) => return error(400, err),
+    };
+    let now = match (routes.now)() {
+        Ok(now) => now,
+        Err(err) => return error(500, err),
+    };
+    let entry = match event.entry(now) {
+        Ok(entry) => entry,
+        Err(message) => return error(400, message),
+    };
+
+    match (routes.add)(entry) {
+        Ok(import) => match import.added.first() {
+            Some(entry) => {
+                let body = serde_json::json!({
+                    "date": entry.date,
+                    "line": entry.line(),
+                });
+                (201, body.to_string())
+            }
+            None => error(409, "a session already starts at that time"),
+        },
+        Err(err) => error(500, err),
+    }
+}
+
+fn authorized(request: &Request, token: &str) -> bool {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+        .is_some_and(|given| constant_time_eq(given.trim().as_bytes(), token.as_bytes()))
+}
+
+/// Compares without returning early, so that the time taken does not tell
+/// how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn error(status: u16, message: impl std::fmt::Display) -> (u16, String) {
+    let body = serde_json::json!({ "error": message.to_string() });
+    (status, body.to_string())
+}
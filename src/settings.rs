@@ -1,12 +1,591 @@
-use chrono::{NaiveTime, Weekday};
+use chrono::{NaiveDate, NaiveTime, TimeDelta, Weekday};
+use chrono_tz::Tz;
+use serde::Deserialize as _;
 
-#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    format::{self, DurationFormat},
+    i18n::Locale,
+    parser,
+};
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize, schemars::JsonSchema)]
 pub struct Settings {
+    /// Schema version; files without one are version 1.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_version",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub version: Option<u32>,
     pub start: Start,
+    /// IANA name such as `"Asia/Tokyo"`; the system time zone when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub timezone: Option<Tz>,
+    /// Language of reports, `"en"` (default) or `"ja"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<Locale>,
+    /// Weekly target, e.g. `goal = "10h"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<Duration>,
+    /// Weekly target per tag, e.g. `[tag_goals] rust = "5h"`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tag_goals: BTreeMap<String, Duration>,
+    /// Month yearly reports start in, e.g. `4` for an academic year from
+    /// April; January when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year_start: Option<u32>,
+    /// Length of one pomodoro in durations like `3p`, 25 minutes when
+    /// absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pomodoro: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rounding: Option<Rounding>,
+    /// Sessions of a day starting less than this after the previous one
+    /// ends count as one in session statistics, e.g. `merge_gap = "5m"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_gap: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<DisplaySettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<ReportSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync: Option<Remote>,
+    /// Rules checked by `validate`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validate: Option<ValidateSettings>,
+    /// Hourly rate per tag, e.g. `[rates] mentoring = 60`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rates: BTreeMap<String, f64>,
+    /// Symbol put in front of amounts of money, e.g. `"$"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<Plan>,
+    /// Sessions planned on a tag every week, checked by `plan`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recurring: Vec<Recurring>,
+    /// Days off that do not count against the goal, the plan, streaks or
+    /// the average of recent weeks.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub holidays: Vec<Holiday>,
+    /// Named variations selected with `--profile`, e.g. `[profiles.work]`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Compare tags in NFKC form and lowercase, so that `Rust`, `rust` and
+    /// full-width `Ｒｕｓｔ` count as one tag.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub normalize_tags: bool,
+    /// How the dates of day records are written, in `strftime` form such
+    /// as `%d/%m/%Y`. Dates like `2024-09-15`, `2024/09/15` and
+    /// `2024.09.15` are read either way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    /// Read full-width digits, letters and punctuation in day records, such
+    /// as `２１：００`, as ASCII, and tags in NFKC form, as typed with
+    /// Japanese input methods.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub normalize_input: bool,
+    /// Events with any of these tags are kept in the file but left out of
+    /// every total, e.g. `ignore_tags = ["break", "commute"]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore_tags: Vec<String>,
+    /// Color and icon of tags in the terminal, the dashboard and charts,
+    /// e.g. `[tags.rust] color = "orange"`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, TagStyle>,
+}
+
+/// Pomodoro length used without a `pomodoro` setting.
+pub const DEFAULT_POMODORO: TimeDelta = TimeDelta::minutes(25);
+
+impl Settings {
+    /// These settings with the keys of `overrides` merged over them.
+    pub fn merged(&self, overrides: &toml::Table) -> Result<Settings, toml::de::Error> {
+        let mut table = toml::Table::try_from(self).expect("settings are always serializable");
+        merge(&mut table, overrides.clone());
+        Settings::deserialize(table)
+    }
+
+    /// Replaces the week start and the goal with those of `profile` where
+    /// it sets them.
+    pub fn apply_profile(&mut self, profile: &Profile) {
+        if let Some(start) = &profile.start {
+            self.start = start.clone();
+        }
+        if let Some(goal) = profile.goal {
+            self.goal = Some(goal);
+        }
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.holidays.iter().any(|holiday| holiday.contains(date))
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
+)]
 pub struct Start {
+    /// Not needed with `days`.
+    #[serde(default = "default_weekday")]
     pub weekday: Weekday,
     pub time: NaiveTime,
+    /// Length of the reporting period in weeks, e.g. `2` for a fortnightly
+    /// review; one week when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weeks: Option<u32>,
+    /// Length of the reporting period in days, e.g. `10`, instead of weeks
+    /// from a weekday.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days: Option<u32>,
+    /// A day in the first period, from which periods of several weeks or
+    /// of `days` are counted; 1970-01-01 or its week when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<NaiveDate>,
+}
+
+fn default_weekday() -> Weekday {
+    Weekday::Mon
+}
+
+/// Latest settings schema version, see `migrate`.
+pub const VERSION: u32 = 1;
+
+fn deserialize_version<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u32>, D::Error> {
+    let version = u32::deserialize(deserializer)?;
+    if version > VERSION {
+        return Err(serde::de::Error::custom(format!(
+            "settings version {} is newer than this learning-record supports ({}); please upgrade it",
+            version, VERSION
+        )));
+    }
+
+    Ok(Some(version))
+}
+
+/// Merges `overrides` into `base`, recursing into tables present in both.
+pub fn merge(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(value)) => merge(base, value),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Overrides for one context, such as work or personal learning.
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
+)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<Start>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<Duration>,
+    /// Only count events with one of these tags, unless `--tag` is given.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Billing-style rounding applied before durations are summed.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
+)]
+pub struct Rounding {
+    /// Step to round to, e.g. `"15m"`.
+    pub granularity: Duration,
+    #[serde(default)]
+    pub mode: RoundingMode,
+    #[serde(default)]
+    pub scope: RoundingScope,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    serde::Deserialize,
+    serde::Serialize,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundingMode {
+    #[default]
+    Up,
+    Down,
+    Nearest,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    serde::Deserialize,
+    serde::Serialize,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundingScope {
+    /// Round every session.
+    #[default]
+    Session,
+    /// Round the total of each day.
+    Day,
+}
+
+/// How dates are shown in output. Parsing of the record file is unaffected.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
+)]
+pub struct DisplaySettings {
+    /// strftime format such as `"%Y/%m/%d (%a)"`; `%a` and `%A` are the
+    /// weekday names of the locale.
+    pub date_format: Option<String>,
+    /// Abbreviated weekday names replacing those of the locale, Monday first.
+    pub weekdays: Option<[String; 7]>,
+    pub duration_format: Option<DurationFormat>,
+    /// Same as `--plain`.
+    #[serde(default)]
+    pub plain: bool,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    serde::Deserialize,
+    serde::Serialize,
+    schemars::JsonSchema,
+)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Text,
+    Markdown,
+    Json,
+    /// Handlebars template given with `--template` or `report.template`
+    Template,
+}
+
+/// Defaults for `report`, overridden by its command line flags.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
+)]
+pub struct ReportSettings {
+    pub format: Option<ReportFormat>,
+    /// Relative paths are resolved against the directory of the record file.
+    pub output: Option<PathBuf>,
+    /// Handlebars template used with `format = "template"`, resolved like
+    /// `output`.
+    pub template: Option<PathBuf>,
+    /// Rhai script run over the report before it is rendered, resolved like
+    /// `output`.
+    pub script: Option<PathBuf>,
+    /// Number of weeks shown in a sparkline at the top of reports.
+    pub trend: Option<usize>,
+}
+
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
+)]
+pub struct ValidateSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub require_tags: Option<RequiredTags>,
+    /// Most time a day may have recorded, e.g. `max_per_day = "12h"`; 24
+    /// hours when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_per_day: Option<Duration>,
+}
+
+/// `require_tags = true` asks every event for a tag, `require_tags =
+/// ["work", "study"]` for one of these.
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
+)]
+#[serde(untagged)]
+pub enum RequiredTags {
+    Any(bool),
+    OneOf(Vec<String>),
+}
+
+/// Time intended to be spent on each weekday, e.g. `[plan] mon = "2h"`.
+/// Days left out are planned as zero.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Deserialize,
+    serde::Serialize,
+    schemars::JsonSchema,
+)]
+pub struct Plan {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mon: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tue: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wed: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thu: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fri: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sat: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sun: Option<Duration>,
+}
+
+impl Plan {
+    pub fn get(&self, weekday: Weekday) -> TimeDelta {
+        let planned = match weekday {
+            Weekday::Mon => self.mon,
+            Weekday::Tue => self.tue,
+            Weekday::Wed => self.wed,
+            Weekday::Thu => self.thu,
+            Weekday::Fri => self.fri,
+            Weekday::Sat => self.sat,
+            Weekday::Sun => self.sun,
+        };
+        planned.map_or(TimeDelta::zero(), |planned| planned.0)
+    }
+}
+
+/// A session planned on the same days of every week, e.g.
+/// `{ tag = "rust", every = "weekday", duration = "1h" }`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
+)]
+pub struct Recurring {
+    pub tag: String,
+    pub every: Every,
+    pub duration: Duration,
+}
+
+/// Days a recurring session is planned on: `"day"`, `"weekday"`,
+/// `"weekend"`, or weekdays such as `["mon", "thu"]`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
+)]
+#[serde(untagged)]
+pub enum Every {
+    Period(Period),
+    Days(Vec<Weekday>),
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Day,
+    Weekday,
+    Weekend,
+}
+
+impl Every {
+    pub fn contains(&self, weekday: Weekday) -> bool {
+        let weekend = matches!(weekday, Weekday::Sat | Weekday::Sun);
+        match self {
+            Every::Period(Period::Day) => true,
+            Every::Period(Period::Weekday) => !weekend,
+            Every::Period(Period::Weekend) => weekend,
+            Every::Days(days) => days.contains(&weekday),
+        }
+    }
+}
+
+/// A holiday or a vacation, e.g. `{ from = "2024-12-28", to = "2025-01-03" }`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
+)]
+pub struct Holiday {
+    pub from: NaiveDate,
+    /// Inclusive last day; a single day when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl Holiday {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from <= date && date <= self.to.unwrap_or(self.from)
+    }
+}
+
+/// Remote endpoint used by `sync`.
+///
+/// Credentials are never stored in the record file; they are read from the
+/// environment when syncing.
+#[derive(
+    Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
+)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Remote {
+    /// Password is read from `LEARNING_RECORD_WEBDAV_PASSWORD`.
+    Webdav {
+        url: String,
+        username: Option<String>,
+    },
+    /// Credentials are read from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`
+    /// and optionally `AWS_SESSION_TOKEN`.
+    S3 {
+        endpoint: String,
+        region: String,
+        bucket: String,
+        key: String,
+    },
+}
+
+/// How a tag is shown, e.g. `[tags.rust] color = "orange" icon = "🦀"`.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
+)]
+pub struct TagStyle {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+    /// Put in front of the tag where output is not plain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+/// Named CSS colors accepted besides `#rrggbb`.
+const COLORS: [(&str, [u8; 3]); 20] = [
+    ("black", [0x00, 0x00, 0x00]),
+    ("blue", [0x00, 0x00, 0xff]),
+    ("brown", [0xa5, 0x2a, 0x2a]),
+    ("cyan", [0x00, 0xff, 0xff]),
+    ("gold", [0xff, 0xd7, 0x00]),
+    ("gray", [0x80, 0x80, 0x80]),
+    ("green", [0x00, 0x80, 0x00]),
+    ("grey", [0x80, 0x80, 0x80]),
+    ("indigo", [0x4b, 0x00, 0x82]),
+    ("lime", [0x00, 0xff, 0x00]),
+    ("magenta", [0xff, 0x00, 0xff]),
+    ("navy", [0x00, 0x00, 0x80]),
+    ("olive", [0x80, 0x80, 0x00]),
+    ("orange", [0xff, 0xa5, 0x00]),
+    ("pink", [0xff, 0xc0, 0xcb]),
+    ("purple", [0x80, 0x00, 0x80]),
+    ("red", [0xff, 0x00, 0x00]),
+    ("teal", [0x00, 0x80, 0x80]),
+    ("white", [0xff, 0xff, 0xff]),
+    ("yellow", [0xff, 0xff, 0x00]),
+];
+
+/// A color written like in CSS, e.g. `"orange"` or `"#ff8800"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color(pub [u8; 3]);
+
+impl Color {
+    /// `#rrggbb`.
+    pub fn hex(&self) -> String {
+        let [r, g, b] = self.0;
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+impl std::str::FromStr for Color {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let source = source.trim().to_lowercase();
+        if let Some(hex) = source.strip_prefix('#') {
+            let channel = |index: usize| {
+                hex.get(index..index + 2)
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+            };
+            if let (6, Some(r), Some(g), Some(b)) = (hex.len(), channel(0), channel(2), channel(4))
+            {
+                return Ok(Color([r, g, b]));
+            }
+        }
+        COLORS
+            .iter()
+            .find(|(name, _)| *name == source)
+            .map(|(_, rgb)| Color(*rgb))
+            .ok_or_else(|| {
+                format!("unknown color `{source}`, use a name like `orange` or `#rrggbb`")
+            })
+    }
+}
+
+impl serde::Serialize for Color {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.hex())
+    }
+}
+
+impl schemars::JsonSchema for Color {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Color".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "description": "A CSS color name such as \"orange\" or \"#rrggbb\"."
+        })
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Color {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A duration written like in the record file, e.g. `"1h30m"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Duration(pub TimeDelta);
+
+impl serde::Serialize for Duration {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format::duration(self.0))
+    }
+}
+
+impl schemars::JsonSchema for Duration {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Duration".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "description": "Hours, minutes and seconds such as \"1h30m\".",
+            "pattern": "^([0-9]+h)?([0-9]+m)?([0-9]+s)?$"
+        })
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Duration {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        parser::parse_duration(&source)
+            .map(Duration)
+            .map_err(|err| serde::de::Error::custom(err.kind))
+    }
 }
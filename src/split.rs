@@ -0,0 +1,87 @@
+use std::{collections::BTreeMap, path::Path};
+
+use chrono::Datelike as _;
+
+use crate::ast;
+
+/// How much of the record each file of `split` holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Period {
+    Month,
+    Year,
+}
+
+/// Files written by `split`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Split {
+    /// New content of the record file: what came before the first day
+    /// record, such as the settings, and one `@include` per file.
+    pub index: String,
+    /// Path of each file as included, with its day records.
+    pub parts: Vec<(String, String)>,
+}
+
+/// Splits the day records of `file`, parsed from `source`, into a file per
+/// `period` in `dir`, relative to the record file. Records are written in
+/// date order without their comments; `@include` lines already there are
+/// kept.
+///
+/// Settings blocks between day records cannot be split, since included
+/// files have no settings.
+pub fn split(
+    source: &str,
+    file: &ast::File,
+    period: Period,
+    dir: &Path,
+    extension: &str,
+) -> Result<Split, String> {
+    if let Some(change) = file.changes.first() {
+        return Err(format!(
+            "line {}: settings blocks between day records would be lost",
+            change.line
+        ));
+    }
+
+    let records: Vec<&ast::DayRecord> = file
+        .records
+        .iter()
+        .filter(|record| record.origin.is_none())
+        .collect();
+    let mut groups: BTreeMap<String, Vec<&ast::DayRecord>> = BTreeMap::new();
+    for record in &records {
+        let name = match period {
+            Period::Month => record.date.format("%Y-%m").to_string(),
+            Period::Year => record.date.year().to_string(),
+        };
+        groups.entry(name).or_default().push(record);
+    }
+
+    let first = records
+        .iter()
+        .map(|record| record.line)
+        .chain(file.includes.iter().map(|include| include.line))
+        .min()
+        .unwrap_or(usize::MAX);
+    let mut index: String = source.split_inclusive('\n').take(first - 1).collect();
+    if !index.is_empty() && !index.ends_with('\n') {
+        index.push('\n');
+    }
+    if !index.is_empty() && !index.ends_with("\n\n") {
+        index.push('\n');
+    }
+    for include in &file.includes {
+        index += &format!("@include {}\n", include.path.display());
+    }
+
+    let mut parts = Vec::new();
+    for (name, mut records) in groups {
+        records.sort_by_key(|record| record.date);
+        let path = dir.join(format!("{name}.{extension}"));
+        let path = path.to_string_lossy().replace('\\', "/");
+        let days: Vec<String> = records.iter().map(|record| record.to_string()).collect();
+        index += &format!("@include {path}\n");
+        parts.push((path, days.join("\n\n") + "\n"));
+    }
+
+    Ok(Split { index, parts })
+}
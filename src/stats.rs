@@ -0,0 +1,133 @@
+use chrono::{NaiveDate, TimeDelta};
+
+use crate::{ast, processing::Filter};
+
+/// Upper bounds of the histogram buckets in minutes, exclusive; the last
+/// bucket has none.
+pub const BUCKETS: [i64; 4] = [15, 30, 60, 120];
+
+/// Sessions of at least this many minutes count as deep work.
+pub const DEEP_WORK: i64 = 60;
+
+/// Lengths of the sessions of one tag, or of every session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lengths {
+    /// `None` for every session.
+    pub tag: Option<String>,
+    /// Shortest first.
+    pub lengths: Vec<TimeDelta>,
+    /// Sum of the corrections, zero or negative.
+    pub corrections: TimeDelta,
+    /// Days of the first and last session or correction.
+    pub first: Option<NaiveDate>,
+    pub last: Option<NaiveDate>,
+}
+
+impl Lengths {
+    fn new(tag: Option<String>) -> Self {
+        Lengths {
+            tag,
+            lengths: Vec::new(),
+            corrections: TimeDelta::zero(),
+            first: None,
+            last: None,
+        }
+    }
+
+    fn add(&mut self, date: NaiveDate, info: &ast::EventInfo) {
+        if info.is_correction() {
+            self.corrections += info.duration;
+        } else {
+            self.lengths.push(info.duration);
+        }
+        self.first = Some(self.first.map_or(date, |first| first.min(date)));
+        self.last = Some(self.last.map_or(date, |last| last.max(date)));
+    }
+
+    pub fn sessions(&self) -> usize {
+        self.lengths.len()
+    }
+
+    /// Time of the sessions, without corrections.
+    pub fn total(&self) -> TimeDelta {
+        self.lengths.iter().sum()
+    }
+
+    /// Time since the first record, corrections applied.
+    pub fn all_time(&self) -> TimeDelta {
+        self.total() + self.corrections
+    }
+
+    /// Length that `percent` of the sessions are no longer than, by the
+    /// nearest rank.
+    pub fn percentile(&self, percent: usize) -> TimeDelta {
+        let rank = (percent * self.lengths.len()).div_ceil(100).max(1);
+        self.lengths
+            .get(rank - 1)
+            .copied()
+            .unwrap_or(TimeDelta::zero())
+    }
+
+    /// Number of sessions in each bucket of `BUCKETS`: 0–15m, 15–30m,
+    /// 30m–1h, 1–2h and 2h or more.
+    pub fn histogram(&self) -> [usize; BUCKETS.len() + 1] {
+        let mut counts = [0; BUCKETS.len() + 1];
+        for length in &self.lengths {
+            let minutes = length.num_minutes();
+            let bucket = BUCKETS
+                .iter()
+                .position(|bound| minutes < *bound)
+                .unwrap_or(BUCKETS.len());
+            counts[bucket] += 1;
+        }
+
+        counts
+    }
+
+    /// Number of sessions of `DEEP_WORK` length or longer.
+    pub fn deep_work(&self) -> usize {
+        self.lengths
+            .iter()
+            .filter(|length| length.num_minutes() >= DEEP_WORK)
+            .count()
+    }
+}
+
+/// Session lengths over the whole file, of every session and then per tag by
+/// total time. Corrections only count towards `all_time`.
+pub fn lengths(file: &ast::File, filter: &Filter) -> Vec<Lengths> {
+    let mut all = Lengths::new(None);
+    let mut tags: Vec<Lengths> = Vec::new();
+    for record in &file.records {
+        for event in &record.events {
+            for info in &event.info {
+                if !filter.matches_session(record, event, info) {
+                    continue;
+                }
+                all.add(record.date, info);
+                for tag in event.tags.iter().flat_map(|tags| &tags.tags) {
+                    let index = match tags
+                        .iter()
+                        .position(|stats| stats.tag.as_deref() == Some(&tag.title))
+                    {
+                        Some(index) => index,
+                        None => {
+                            tags.push(Lengths::new(Some(tag.title.clone())));
+                            tags.len() - 1
+                        }
+                    };
+                    tags[index].add(record.date, info);
+                }
+            }
+        }
+    }
+    tags.sort_by_key(|stats| std::cmp::Reverse(stats.all_time()));
+
+    let mut stats = vec![all];
+    stats.extend(tags);
+    for stats in &mut stats {
+        stats.lengths.sort();
+    }
+
+    stats
+}
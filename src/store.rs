@@ -0,0 +1,166 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+
+use crate::sync;
+
+/// Number of mutations kept for `undo`.
+const JOURNAL_LEN: usize = 10;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    InvalidJournal(toml::de::Error),
+    /// Nothing left to undo.
+    EmptyJournal,
+    /// The file was edited after the journaled mutation.
+    Diverged {
+        command: String,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::InvalidJournal(err) => write!(f, "broken journal file: {}", err),
+            Error::EmptyJournal => write!(f, "nothing to undo"),
+            Error::Diverged { command } => write!(
+                f,
+                "the file was changed after `{}`; rerun with --force to undo anyway",
+                command
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+struct Journal {
+    #[serde(default)]
+    entries: Vec<Entry>,
+}
+
+/// A single mutation of the record file.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct Entry {
+    pub command: String,
+    pub at: DateTime<Local>,
+    /// Content before the mutation, `None` if the file did not exist.
+    before: Option<String>,
+    /// `sync::hash` of the content written, to notice later edits without
+    /// keeping a second copy of the file.
+    #[serde(default)]
+    after_hash: String,
+}
+
+/// Path of a hidden file stored next to the record file, e.g. `.records.lr.sync.toml`.
+pub fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.{suffix}"))
+}
+
+/// Replaces the content of the record file, journaling the previous content
+/// so that `undo` can restore it.
+///
+/// The commands build `content` with `\n` line endings; it is written with
+/// those of the file, CRLF or LF, and its byte order mark, if any.
+pub fn write(path: &Path, command: &str, content: &str) -> Result<(), Error> {
+    let before = match fs::read_to_string(path) {
+        Ok(before) => Some(before),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+    let content = match &before {
+        Some(before) => keep_newlines(before, content),
+        None => content.to_string(),
+    };
+    let content = content.as_str();
+
+    if before.as_deref() == Some(content) {
+        return Ok(());
+    }
+
+    let mut journal = load(path)?;
+    journal.entries.push(Entry {
+        command: command.to_string(),
+        at: Local::now(),
+        before,
+        after_hash: sync::hash(content),
+    });
+    let overflow = journal.entries.len().saturating_sub(JOURNAL_LEN);
+    journal.entries.drain(..overflow);
+
+    tracing::info!(command, path = %path.display(), "writing the record file");
+    fs::write(path, content)?;
+    save(path, &journal)
+}
+
+/// `content` with the line endings and byte order mark of `before`.
+fn keep_newlines(before: &str, content: &str) -> String {
+    let crlf = before
+        .find('\n')
+        .is_some_and(|end| before[..end].ends_with('\r'));
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let content = if crlf {
+        content.replace("\r\n", "\n").replace('\n', "\r\n")
+    } else {
+        content.to_string()
+    };
+
+    if before.starts_with('\u{feff}') {
+        format!("\u{feff}{content}")
+    } else {
+        content
+    }
+}
+
+/// Reverts the most recent journaled mutation.
+pub fn undo(path: &Path, force: bool) -> Result<Entry, Error> {
+    let mut journal = load(path)?;
+    let Some(entry) = journal.entries.pop() else {
+        return Err(Error::EmptyJournal);
+    };
+
+    let current = fs::read_to_string(path).ok();
+    let unchanged = current.is_some_and(|current| sync::hash(&current) == entry.after_hash);
+    if !force && !unchanged {
+        return Err(Error::Diverged {
+            command: entry.command,
+        });
+    }
+
+    match &entry.before {
+        Some(before) => fs::write(path, before)?,
+        None => fs::remove_file(path)?,
+    }
+    save(path, &journal)?;
+
+    Ok(entry)
+}
+
+fn load(path: &Path) -> Result<Journal, Error> {
+    match fs::read_to_string(sidecar(path, "journal.toml")) {
+        Ok(source) => toml::from_str(&source).map_err(Error::InvalidJournal),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Journal::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn save(path: &Path, journal: &Journal) -> Result<(), Error> {
+    let source = toml::to_string(journal).expect("journal is always serializable");
+    fs::write(sidecar(path, "journal.toml"), source)?;
+
+    Ok(())
+}
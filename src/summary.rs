@@ -0,0 +1,71 @@
+use chrono::TimeDelta;
+
+use crate::{ast, format, processing::PeriodReport};
+
+const MARKER: &str = "# Summary of the week from";
+const INDENT: &str = "#   ";
+
+/// Inserts a comment block summarizing `report` under the last day record of
+/// its week, replacing an earlier summary of the same week.
+///
+/// Returns `None` when there is no day record in that week.
+pub fn commit(
+    source: &str,
+    file: &ast::File,
+    report: &PeriodReport,
+    goal: Option<TimeDelta>,
+) -> Option<String> {
+    let last_day = file
+        .records
+        .iter()
+        .filter(|record| record.origin.is_none())
+        .filter(|record| record.date >= report.start.date() && record.date < report.end.date())
+        .max_by_key(|record| record.date)?;
+
+    let mut lines: Vec<&str> = source.lines().collect();
+    let start = last_day.line - 1;
+    let end = lines[start..]
+        .iter()
+        .position(|line| line.trim().is_empty())
+        .map_or(lines.len(), |offset| start + offset);
+
+    let marker = format!("{} {}", MARKER, report.start.format("%Y-%m-%d %H:%M"));
+    let block = render(&marker, report, goal);
+    let insert_at = match lines[start..end].iter().position(|line| *line == marker) {
+        Some(offset) => {
+            let existing = start + offset;
+            let len = 1 + lines[existing + 1..end]
+                .iter()
+                .take_while(|line| line.starts_with(INDENT))
+                .count();
+            lines.drain(existing..existing + len);
+            existing
+        }
+        None => end,
+    };
+    lines.splice(insert_at..insert_at, block.iter().map(String::as_str));
+
+    let mut result = lines.join("\n");
+    if source.ends_with('\n') {
+        result.push('\n');
+    }
+
+    Some(result)
+}
+
+fn render(marker: &str, report: &PeriodReport, goal: Option<TimeDelta>) -> Vec<String> {
+    let mut total = format::duration(report.total);
+    if let Some(goal) = goal {
+        total += &format!(" / {}", format::duration(goal));
+    }
+
+    let mut block = vec![marker.to_string(), format!("{INDENT}total: {total}")];
+    block.extend(
+        report
+            .tags
+            .iter()
+            .map(|(title, duration)| format!("{INDENT}{title}: {}", format::duration(*duration))),
+    );
+
+    block
+}
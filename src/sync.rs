@@ -0,0 +1,380 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use base64::Engine as _;
+use chrono::{DateTime, Local, Utc};
+use hmac::{Hmac, KeyInit as _, Mac as _};
+use sha2::{Digest as _, Sha256};
+
+use crate::{settings::Remote, store};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Store(store::Error),
+    Http(Box<ureq::Error>),
+    MissingCredentials(&'static str),
+    InvalidState(toml::de::Error),
+    /// `--direction pull` was requested but nothing exists on the remote yet.
+    RemoteMissing,
+    /// Both sides differ and there is no common base to decide from.
+    Conflict,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Store(err) => write!(f, "{}", err),
+            Error::Http(err) => write!(f, "{}", err),
+            Error::MissingCredentials(var) => write!(f, "environment variable {} is not set", var),
+            Error::InvalidState(err) => write!(f, "broken sync state file: {}", err),
+            Error::RemoteMissing => write!(f, "the remote file does not exist yet"),
+            Error::Conflict => write!(
+                f,
+                "local and remote files both changed; rerun with --direction push or --direction pull"
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<store::Error> for Error {
+    fn from(value: store::Error) -> Self {
+        Self::Store(value)
+    }
+}
+
+impl From<ureq::Error> for Error {
+    fn from(value: ureq::Error) -> Self {
+        Self::Http(Box::new(value))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Direction {
+    Push,
+    Pull,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    UpToDate,
+    Pushed,
+    Pulled,
+    /// Both sides changed since the last sync. The newer side was kept and the
+    /// older one was saved to `backup`.
+    Resolved {
+        pushed: bool,
+        backup: PathBuf,
+    },
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::UpToDate => write!(f, "already up to date"),
+            Outcome::Pushed => write!(f, "pushed local changes"),
+            Outcome::Pulled => write!(f, "pulled remote changes"),
+            Outcome::Resolved { pushed, backup } => write!(
+                f,
+                "conflict: kept the {} version, the other one was saved to {}",
+                if *pushed { "local" } else { "remote" },
+                backup.display()
+            ),
+        }
+    }
+}
+
+/// Content of the file as of the last successful sync.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+struct State {
+    /// `sync_hash` of the content.
+    hash: String,
+    synced_at: DateTime<Utc>,
+}
+
+struct Snapshot {
+    content: String,
+    modified: Option<DateTime<Utc>>,
+}
+
+pub fn sync(path: &Path, remote: &Remote, direction: Option<Direction>) -> Result<Outcome, Error> {
+    let local = Snapshot {
+        content: fs::read_to_string(path)?,
+        modified: fs::metadata(path)?.modified().ok().map(DateTime::from),
+    };
+    let fetched = fetch(remote)?;
+    tracing::debug!(remote_exists = fetched.is_some(), "fetched the remote file");
+
+    let outcome = match direction {
+        Some(Direction::Push) => {
+            push(remote, &local.content)?;
+            Outcome::Pushed
+        }
+        Some(Direction::Pull) => {
+            let Some(fetched) = fetched else {
+                return Err(Error::RemoteMissing);
+            };
+            store::write(path, "sync", &fetched.content)?;
+            Outcome::Pulled
+        }
+        None => match fetched {
+            None => {
+                push(remote, &local.content)?;
+                Outcome::Pushed
+            }
+            Some(fetched) if sync_hash(&fetched.content) == sync_hash(&local.content) => {
+                Outcome::UpToDate
+            }
+            Some(fetched) => {
+                let Some(base) = load_state(path)? else {
+                    return Err(Error::Conflict);
+                };
+                let local_changed = sync_hash(&local.content) != base.hash;
+                let remote_changed = sync_hash(&fetched.content) != base.hash;
+                tracing::debug!(local_changed, remote_changed, synced_at = %base.synced_at);
+
+                match (local_changed, remote_changed) {
+                    (true, false) => {
+                        push(remote, &local.content)?;
+                        Outcome::Pushed
+                    }
+                    (false, _) => {
+                        store::write(path, "sync", &fetched.content)?;
+                        Outcome::Pulled
+                    }
+                    (true, true) => resolve(path, remote, local, fetched)?,
+                }
+            }
+        },
+    };
+
+    let synced = fs::read_to_string(path)?;
+    save_state(path, &synced)?;
+
+    Ok(outcome)
+}
+
+/// Last writer wins; the losing side is kept next to the record file.
+fn resolve(
+    path: &Path,
+    remote: &Remote,
+    local: Snapshot,
+    fetched: Snapshot,
+) -> Result<Outcome, Error> {
+    let (Some(local_modified), Some(remote_modified)) = (local.modified, fetched.modified) else {
+        return Err(Error::Conflict);
+    };
+
+    let stamp = Local::now().format("%Y%m%d%H%M%S");
+    if local_modified >= remote_modified {
+        let backup = sibling(path, &format!("remote-{stamp}"));
+        fs::write(&backup, &fetched.content)?;
+        push(remote, &local.content)?;
+        Ok(Outcome::Resolved {
+            pushed: true,
+            backup,
+        })
+    } else {
+        let backup = sibling(path, &format!("local-{stamp}"));
+        fs::write(&backup, &local.content)?;
+        store::write(path, "sync", &fetched.content)?;
+        Ok(Outcome::Resolved {
+            pushed: false,
+            backup,
+        })
+    }
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{name}.{suffix}"))
+}
+
+fn load_state(path: &Path) -> Result<Option<State>, Error> {
+    match fs::read_to_string(store::sidecar(path, "sync.toml")) {
+        Ok(source) => toml::from_str(&source)
+            .map(Some)
+            .map_err(Error::InvalidState),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn save_state(path: &Path, content: &str) -> Result<(), Error> {
+    let state = State {
+        hash: sync_hash(content),
+        synced_at: Utc::now(),
+    };
+    let source = toml::to_string(&state).expect("sync state is always serializable");
+    fs::write(store::sidecar(path, "sync.toml"), source)?;
+
+    Ok(())
+}
+
+/// `hash` of `content` with LF line endings and without a byte order mark.
+/// `store::write` gives pulled content those of the local file, so both
+/// sides compare equal whichever they use.
+fn sync_hash(content: &str) -> String {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    hash(&content.replace("\r\n", "\n"))
+}
+
+/// SHA-256 of `content` in hex.
+pub fn hash(content: &str) -> String {
+    hex(&Sha256::digest(content.as_bytes()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn fetch(remote: &Remote) -> Result<Option<Snapshot>, Error> {
+    let request = match remote {
+        Remote::Webdav { url, username } => webdav_request("GET", url, username.as_deref())?,
+        Remote::S3 { .. } => s3_request("GET", remote, b"")?,
+    };
+
+    let response = match request.call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(404, _)) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    let modified = response
+        .header("Last-Modified")
+        .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+        .map(|date| date.with_timezone(&Utc));
+    let content = response.into_string()?;
+
+    Ok(Some(Snapshot { content, modified }))
+}
+
+fn push(remote: &Remote, content: &str) -> Result<(), Error> {
+    tracing::info!(bytes = content.len(), "uploading the record file");
+    let request = match remote {
+        Remote::Webdav { url, username } => webdav_request("PUT", url, username.as_deref())?,
+        Remote::S3 { .. } => s3_request("PUT", remote, content.as_bytes())?,
+    };
+    request.send_string(content)?;
+
+    Ok(())
+}
+
+fn webdav_request(method: &str, url: &str, username: Option<&str>) -> Result<ureq::Request, Error> {
+    let request = ureq::request(method, url);
+    let Some(username) = username else {
+        return Ok(request);
+    };
+
+    let password = env::var("LEARNING_RECORD_WEBDAV_PASSWORD")
+        .map_err(|_| Error::MissingCredentials("LEARNING_RECORD_WEBDAV_PASSWORD"))?;
+    let credentials =
+        base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"));
+
+    Ok(request.set("Authorization", &format!("Basic {credentials}")))
+}
+
+/// Builds a path-style S3 request signed with AWS Signature Version 4.
+fn s3_request(method: &str, remote: &Remote, body: &[u8]) -> Result<ureq::Request, Error> {
+    let Remote::S3 {
+        endpoint,
+        region,
+        bucket,
+        key,
+    } = remote
+    else {
+        unreachable!("s3_request called with a non-S3 remote");
+    };
+
+    let access_key = env::var("AWS_ACCESS_KEY_ID")
+        .map_err(|_| Error::MissingCredentials("AWS_ACCESS_KEY_ID"))?;
+    let secret_key = env::var("AWS_SECRET_ACCESS_KEY")
+        .map_err(|_| Error::MissingCredentials("AWS_SECRET_ACCESS_KEY"))?;
+    let session_token = env::var("AWS_SESSION_TOKEN").ok();
+
+    let endpoint = endpoint.trim_end_matches('/');
+    let host = endpoint
+        .split_once("://")
+        .map_or(endpoint, |(_, host)| host);
+    let uri = format!("/{}/{}", uri_encode(bucket), uri_encode(key));
+
+    let now = Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = hex(&Sha256::digest(body));
+
+    let mut headers = vec![
+        ("host", host.to_string()),
+        ("x-amz-content-sha256", payload_hash.clone()),
+        ("x-amz-date", amz_date.clone()),
+    ];
+    if let Some(token) = &session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{value}\n"))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request =
+        format!("{method}\n{uri}\n\n{canonical_headers}\n{signed_headers}\n{payload_hash}");
+
+    let scope = format!("{date}/{region}/s3/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let signing_key = [region.as_str(), "s3", "aws4_request"].iter().fold(
+        hmac_sha256(format!("AWS4{secret_key}").as_bytes(), date.as_bytes()),
+        |key, part| hmac_sha256(&key, part.as_bytes()),
+    );
+    let signature = hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={access_key}/{scope}, SignedHeaders={signed_headers}, Signature={signature}"
+    );
+
+    let mut request =
+        ureq::request(method, &format!("{endpoint}{uri}")).set("Authorization", &authorization);
+    for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+        request = request.set(name, value);
+    }
+
+    Ok(request)
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn uri_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDateTime, TimeDelta};
+use handlebars::Handlebars;
+
+use crate::{
+    processing::PeriodReport,
+    render::{self, Extras, Renderer},
+};
+
+/// Values available to report templates.
+///
+/// Durations are given both formatted for the locale and in minutes, dates
+/// both formatted with the display settings and in ISO 8601.
+#[derive(Debug, serde::Serialize)]
+struct Context<'a> {
+    heading: String,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    start_date: String,
+    end_date: String,
+    total: String,
+    total_minutes: i64,
+    goal: Option<String>,
+    goal_minutes: Option<i64>,
+    goal_reached: Option<bool>,
+    /// Pomodoros of the sessions written like `3p`.
+    pomodoros: u32,
+    /// Present when `[rates]` are configured.
+    earnings: Option<String>,
+    month_earnings: Option<String>,
+    tags: Vec<Tag<'a>>,
+    /// Planned and actual time per day when a `[plan]` is configured.
+    days: Vec<Day>,
+    /// Values set by the `report.script`, by name.
+    metrics: &'a BTreeMap<String, serde_json::Value>,
+    /// Sparkline and totals of the last weeks, oldest first, with `--trend`.
+    sparkline: Option<String>,
+    trend_minutes: Vec<i64>,
+    /// Mean total of the last completed weeks, up to four.
+    average: Option<String>,
+    average_minutes: Option<i64>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Day {
+    date: String,
+    duration: String,
+    planned: String,
+    variance: String,
+    variance_minutes: i64,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Tag<'a> {
+    name: &'a str,
+    duration: String,
+    minutes: i64,
+    /// Present when the tag has a goal in `[tag_goals]`.
+    goal: Option<String>,
+    goal_minutes: Option<i64>,
+    earnings: Option<String>,
+}
+
+/// Renders `report` with a Handlebars template.
+pub fn render(
+    template: &str,
+    renderer: &Renderer,
+    report: &PeriodReport,
+    extras: &Extras,
+) -> Result<String, handlebars::RenderError> {
+    let (goal, earnings) = (extras.goal, extras.earnings.as_ref());
+    let uncolored = renderer.clone().without_color();
+    let context = Context {
+        heading: renderer.period_heading(report),
+        start: report.start,
+        end: report.end,
+        start_date: renderer.date(report.start.date()),
+        end_date: renderer.date((report.end - TimeDelta::days(1)).date()),
+        total: renderer.duration(report.total),
+        total_minutes: report.total.num_minutes(),
+        goal: goal.map(|goal| renderer.duration(goal)),
+        goal_minutes: goal.map(|goal| goal.num_minutes()),
+        goal_reached: goal.map(|goal| report.total >= goal),
+        pomodoros: report.pomodoros,
+        earnings: earnings.map(|earnings| renderer.money(earnings.week)),
+        month_earnings: earnings.map(|earnings| renderer.money(earnings.month)),
+        tags: report
+            .tags
+            .iter()
+            .map(|(name, duration)| Tag {
+                name,
+                duration: renderer.duration(*duration),
+                minutes: duration.num_minutes(),
+                goal: render::tag_goal(extras, name).map(|goal| renderer.duration(goal)),
+                goal_minutes: render::tag_goal(extras, name).map(|goal| goal.num_minutes()),
+                earnings: earnings
+                    .and_then(|earnings| render::tag_earnings(earnings, name))
+                    .map(|amount| renderer.money(amount)),
+            })
+            .collect(),
+        days: extras
+            .schedule
+            .iter()
+            .map(|day| Day {
+                date: renderer.date(day.date),
+                duration: renderer.duration(day.actual),
+                planned: renderer.duration(day.planned),
+                variance: uncolored.variance(day.variance()),
+                variance_minutes: day.variance().num_minutes(),
+            })
+            .collect(),
+        metrics: &extras.metrics,
+        sparkline: (!extras.trend.is_empty()).then(|| renderer.sparkline(&extras.trend)),
+        trend_minutes: extras.trend.iter().map(TimeDelta::num_minutes).collect(),
+        average: extras
+            .average
+            .map(|(average, _)| renderer.duration(average)),
+        average_minutes: extras.average.map(|(average, _)| average.num_minutes()),
+    };
+
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars.render_template(template, &context)
+}
@@ -0,0 +1,101 @@
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, TimeDelta, Utc, Weekday};
+use chrono_tz::Tz;
+
+/// Current wall-clock time in `timezone`, or in the system time zone.
+///
+/// All processing works on wall-clock times, like the record file itself, so
+/// converting the current instant is the only time zone conversion needed and
+/// it is never ambiguous, even around DST transitions.
+pub fn now(timezone: Option<Tz>) -> NaiveDateTime {
+    match timezone {
+        Some(timezone) => Utc::now().with_timezone(&timezone).naive_local(),
+        None => Local::now().naive_local(),
+    }
+}
+
+/// Value of `--today`, resolved against the local clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Today {
+    /// `2024-09-15T21:00:00`
+    DateTime(NaiveDateTime),
+    /// `2024-09-15`, keeping the current time of day.
+    Date(NaiveDate),
+    /// `today`, `yesterday`, `tomorrow`, `-3d`, `+1w`
+    Offset(TimeDelta),
+    /// `monday`: the most recent such day, today included.
+    Weekday(Weekday),
+}
+
+/// Offsets of more days than this are rejected, which keeps the resolved
+/// day and the weeks around it far inside the range of `NaiveDateTime`.
+const MAX_OFFSET_DAYS: i64 = 10_000 * 366;
+
+impl Today {
+    /// `None` when an offset goes past the dates that can be represented.
+    pub fn resolve(self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        match self {
+            Today::DateTime(datetime) => Some(datetime),
+            Today::Date(date) => Some(date.and_time(now.time())),
+            Today::Offset(offset) => now.checked_add_signed(offset),
+            Today::Weekday(weekday) => {
+                let back = now.weekday().days_since(weekday);
+                now.checked_sub_signed(TimeDelta::days(back.into()))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Today {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Ok(datetime) = s.parse::<NaiveDateTime>() {
+            return Ok(Today::DateTime(datetime));
+        }
+        if let Ok(date) = s.parse::<NaiveDate>() {
+            return Ok(Today::Date(date));
+        }
+
+        match s.to_lowercase().as_str() {
+            "today" | "now" => return Ok(Today::Offset(TimeDelta::zero())),
+            "yesterday" => return Ok(Today::Offset(TimeDelta::days(-1))),
+            "tomorrow" => return Ok(Today::Offset(TimeDelta::days(1))),
+            _ => {}
+        }
+
+        if let Ok(weekday) = s.parse::<Weekday>() {
+            return Ok(Today::Weekday(weekday));
+        }
+
+        parse_offset(s).map(Today::Offset).ok_or_else(|| {
+            format!(
+                "`{s}` is not a date (2024-09-15), a date and time (2024-09-15T21:00:00), \
+                 a relative day (yesterday, -3d, +1w) or a weekday (monday)"
+            )
+        })
+    }
+}
+
+fn parse_offset(s: &str) -> Option<TimeDelta> {
+    let (sign, rest) = match s.as_bytes().first()? {
+        b'-' => (-1, &s[1..]),
+        b'+' => (1, &s[1..]),
+        _ => return None,
+    };
+    let unit = rest.chars().last()?;
+    let amount: i64 = rest[..rest.len() - unit.len_utf8()].parse().ok()?;
+
+    let days = match unit {
+        'd' => amount,
+        'w' => amount.checked_mul(7)?,
+        _ => return None,
+    };
+
+    if days > MAX_OFFSET_DAYS {
+        return None;
+    }
+
+    TimeDelta::try_days(sign * days)
+}
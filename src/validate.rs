@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, TimeDelta};
+
+use crate::{
+    ast,
+    doctor::Check,
+    format,
+    settings::{RequiredTags, ValidateSettings},
+};
+
+/// Checks every record against the `[validate]` rules in effect on its day,
+/// returning the problems found.
+pub fn validate(file: &ast::File) -> Vec<Check> {
+    let mut checks = Vec::new();
+    // Time and event locations of each day, over records of the same date.
+    let mut days: BTreeMap<NaiveDate, (TimeDelta, Vec<String>)> = BTreeMap::new();
+
+    for record in &file.records {
+        let location = |line: usize| match &record.origin {
+            Some(path) => format!("{}:{}", path.display(), line),
+            None => format!("line {}", line),
+        };
+
+        let day = days.entry(record.date).or_default();
+        for event in &record.events {
+            checks.extend(check_zero_duration(event, location));
+            if event.info.is_empty() {
+                continue;
+            }
+            day.0 += event
+                .info
+                .iter()
+                .map(|info| info.duration)
+                .sum::<TimeDelta>();
+            day.1.push(location(event.line));
+        }
+
+        let Some(rules) = file
+            .settings_at(record.date)
+            .and_then(|settings| settings.validate.as_ref())
+        else {
+            continue;
+        };
+        for event in &record.events {
+            checks.extend(check_required_tags(rules, event, location));
+        }
+    }
+
+    for (date, (total, locations)) in days {
+        let max = file
+            .settings_at(date)
+            .and_then(|settings| settings.validate.as_ref())
+            .and_then(|rules| rules.max_per_day)
+            .map_or(TimeDelta::days(1), |max| max.0);
+        if total > max {
+            checks.push(Check::error(
+                format!(
+                    "{date} has {} recorded, more than {}: {}",
+                    format::duration(total),
+                    format::duration(max),
+                    locations.join(", ")
+                ),
+                "look for a typo in the durations, e.g. 10h for 1h",
+            ));
+        }
+    }
+
+    checks
+}
+
+fn check_zero_duration(event: &ast::Event, location: impl Fn(usize) -> String) -> Option<Check> {
+    event
+        .info
+        .iter()
+        .any(|info| info.duration.is_zero())
+        .then(|| {
+            Check::warning(
+                format!("{}: event has a zero duration", location(event.line)),
+                "give it a duration or remove it",
+            )
+        })
+}
+
+fn check_required_tags(
+    rules: &ValidateSettings,
+    event: &ast::Event,
+    location: impl Fn(usize) -> String,
+) -> Option<Check> {
+    let mut tags = event.tags.iter().flat_map(|tags| &tags.tags);
+    match rules.require_tags.as_ref()? {
+        RequiredTags::Any(false) => None,
+        RequiredTags::Any(true) => tags.next().is_none().then(|| {
+            Check::error(
+                format!("{}: event has no tag", location(event.line)),
+                "tag it, e.g. [rust] 21:00 - 1h",
+            )
+        }),
+        RequiredTags::OneOf(required) => {
+            (!tags.any(|tag| required.contains(&tag.title))).then(|| {
+                Check::error(
+                    format!(
+                        "{}: event has none of the required tags",
+                        location(event.line)
+                    ),
+                    format!("tag it with one of {}", required.join(", ")),
+                )
+            })
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use chrono::{NaiveDateTime, TimeDelta};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    ast,
+    parser::Parser,
+    processing::{self, Filter},
+};
+
+/// Weekly report in the shape of `report --format json`, without the extras
+/// that depend on the plan or rates.
+#[derive(serde::Serialize)]
+struct Report {
+    start: NaiveDateTime,
+    total_minutes: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    goal_minutes: Option<i64>,
+    tags: Vec<Tag>,
+}
+
+#[derive(serde::Serialize)]
+struct Tag {
+    tag: String,
+    minutes: i64,
+}
+
+fn parse_file(source: &str) -> Result<ast::File, JsError> {
+    Parser::new(source.chars().collect())
+        .parse_file()
+        .map_err(|err| JsError::new(&err.to_string()))
+}
+
+/// Parses a record file into its syntax tree, as JSON like `ast --format json`.
+#[wasm_bindgen]
+pub fn parse(source: &str) -> Result<String, JsError> {
+    let file = parse_file(source)?;
+    Ok(serde_json::to_string(&file).expect("AST is serializable"))
+}
+
+/// Report of the week containing `today`, a date and time such as
+/// `2024-09-15T21:00:00`, as JSON.
+#[wasm_bindgen]
+pub fn weekly_report(source: &str, today: &str) -> Result<String, JsError> {
+    let mut file = parse_file(source)?;
+    let today: NaiveDateTime = today
+        .parse()
+        .map_err(|_| JsError::new(&format!("`{today}` is not a date and time")))?;
+    processing::normalize_tags(&mut file);
+
+    let settings = file.settings_at(today.date());
+    let filter = Filter::default().ignoring(settings);
+    let report = processing::calc_weekly_records(&file, today, &filter)
+        .map_err(|err| JsError::new(&format!("{err:?}")))?;
+    let goal = settings.and_then(|settings| {
+        let goal = settings.goal?.0;
+        Some(processing::prorate_goal(goal, &report, settings))
+    });
+
+    let json = Report {
+        start: report.start,
+        total_minutes: report.total.num_minutes(),
+        goal_minutes: goal.as_ref().map(TimeDelta::num_minutes),
+        tags: report
+            .tags
+            .iter()
+            .map(|(tag, duration)| Tag {
+                tag: tag.clone(),
+                minutes: duration.num_minutes(),
+            })
+            .collect(),
+    };
+
+    Ok(serde_json::to_string(&json).expect("report is always serializable"))
+}
@@ -0,0 +1,242 @@
+use std::fmt::Write as _;
+
+use crate::{
+    ast,
+    processing::{Filter, PeriodReport},
+};
+
+enum Cell {
+    Text(String),
+    Number(f64),
+}
+
+/// Workbook with a sheet of the sessions kept by `filter`, one per row, and
+/// a sheet of the hours per tag (rows) and week (columns) of `weeks`.
+pub fn workbook(file: &ast::File, filter: &Filter, weeks: &[PeriodReport]) -> Vec<u8> {
+    let sheets = [("Events", events(file, filter)), ("Weekly", pivot(weeks))];
+
+    let mut content_types = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+        r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+        r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+        r#"<Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#,
+    ));
+    let mut workbook = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" "#,
+        r#"xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>"#,
+    ));
+    let mut relationships = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    ));
+    let mut files = Vec::new();
+    for (index, (name, rows)) in sheets.iter().enumerate() {
+        let id = index + 1;
+        write!(
+            content_types,
+            r#"<Override PartName="/xl/worksheets/sheet{id}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#
+        )
+        .unwrap();
+        write!(
+            workbook,
+            r#"<sheet name="{name}" sheetId="{id}" r:id="rId{id}"/>"#
+        )
+        .unwrap();
+        write!(
+            relationships,
+            r#"<Relationship Id="rId{id}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{id}.xml"/>"#
+        )
+        .unwrap();
+        files.push((format!("xl/worksheets/sheet{id}.xml"), sheet(rows)));
+    }
+    content_types += "</Types>";
+    workbook += "</sheets></workbook>";
+    relationships += "</Relationships>";
+
+    let root = concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+        r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/>"#,
+        "</Relationships>",
+    );
+    let mut entries = vec![
+        ("[Content_Types].xml".to_string(), content_types),
+        ("_rels/.rels".to_string(), root.to_string()),
+        ("xl/workbook.xml".to_string(), workbook),
+        ("xl/_rels/workbook.xml.rels".to_string(), relationships),
+    ];
+    entries.extend(files);
+
+    zip(&entries)
+}
+
+fn events(file: &ast::File, filter: &Filter) -> Vec<Vec<Cell>> {
+    let header = ["date", "start", "minutes", "tags", "details", "line"];
+    let mut rows = vec![header.map(|title| Cell::Text(title.to_string())).into()];
+    for record in &file.records {
+        for event in &record.events {
+            let tags = event.tags.iter().flat_map(|tags| &tags.tags);
+            for info in &event.info {
+                if !filter.matches_session(record, event, info) {
+                    continue;
+                }
+                rows.push(vec![
+                    Cell::Text(record.date.to_string()),
+                    Cell::Text(
+                        info.time
+                            .map(|time| time.format("%H:%M:%S").to_string())
+                            .unwrap_or_default(),
+                    ),
+                    Cell::Number(info.duration.num_seconds() as f64 / 60.0),
+                    Cell::Text(
+                        tags.clone()
+                            .map(|tag| tag.title.as_str())
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                    ),
+                    Cell::Text(
+                        tags.clone()
+                            .filter_map(|tag| tag.detail.as_deref())
+                            .collect::<Vec<_>>()
+                            .join("; "),
+                    ),
+                    Cell::Number(event.line as f64),
+                ]);
+            }
+        }
+    }
+
+    rows
+}
+
+/// Like `pivot::csv`: tags in order of first appearance, hours per week.
+fn pivot(weeks: &[PeriodReport]) -> Vec<Vec<Cell>> {
+    let mut tags: Vec<&str> = Vec::new();
+    for (tag, _) in weeks.iter().flat_map(|week| &week.tags) {
+        if !tags.contains(&tag.as_str()) {
+            tags.push(tag);
+        }
+    }
+
+    let mut header = vec![Cell::Text("tag".to_string())];
+    header.extend(
+        weeks
+            .iter()
+            .map(|week| Cell::Text(week.start.date().to_string())),
+    );
+    let mut rows = vec![header];
+    for tag in tags {
+        let mut row = vec![Cell::Text(tag.to_string())];
+        row.extend(weeks.iter().map(|week| {
+            let minutes = week
+                .tags
+                .iter()
+                .find(|(title, _)| title == tag)
+                .map_or(0, |(_, duration)| duration.num_minutes());
+            Cell::Number(minutes as f64 / 60.0)
+        }));
+        rows.push(row);
+    }
+
+    rows
+}
+
+fn sheet(rows: &[Vec<Cell>]) -> String {
+    let mut out = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+    ));
+    for (index, row) in rows.iter().enumerate() {
+        let number = index + 1;
+        write!(out, r#"<row r="{number}">"#).unwrap();
+        for (column, cell) in row.iter().enumerate() {
+            let reference = format!("{}{number}", column_name(column));
+            match cell {
+                Cell::Text(text) => write!(
+                    out,
+                    r#"<c r="{reference}" t="inlineStr"><is><t>{}</t></is></c>"#,
+                    escape(text)
+                ),
+                Cell::Number(number) => write!(out, r#"<c r="{reference}"><v>{number}</v></c>"#),
+            }
+            .unwrap();
+        }
+        out += "</row>";
+    }
+    out += "</sheetData></worksheet>";
+
+    out
+}
+
+/// `A` for 0, `Z` for 25, `AA` for 26 and so on.
+fn column_name(mut column: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (column % 26) as u8);
+        if column < 26 {
+            break;
+        }
+        column = column / 26 - 1;
+    }
+    name.reverse();
+
+    String::from_utf8(name).unwrap()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// ZIP archive of `files`, stored without compression.
+fn zip(files: &[(String, String)]) -> Vec<u8> {
+    // 1980-01-01 00:00 in MS-DOS format.
+    const DATE: u16 = 0x0021;
+    let mut out = Vec::new();
+    let mut directory = Vec::new();
+    for (name, content) in files {
+        let (name, content) = (name.as_bytes(), content.as_bytes());
+        let crc = crc32fast::hash(content);
+        let size = content.len() as u32;
+        let offset = out.len() as u32;
+
+        out.extend(0x0403_4b50_u32.to_le_bytes());
+        out.extend([20, 0, 0, 0, 0, 0, 0, 0]);
+        out.extend(DATE.to_le_bytes());
+        out.extend(crc.to_le_bytes());
+        out.extend(size.to_le_bytes());
+        out.extend(size.to_le_bytes());
+        out.extend((name.len() as u16).to_le_bytes());
+        out.extend([0, 0]);
+        out.extend(name);
+        out.extend(content);
+
+        directory.extend(0x0201_4b50_u32.to_le_bytes());
+        directory.extend([20, 0, 20, 0, 0, 0, 0, 0, 0, 0]);
+        directory.extend(DATE.to_le_bytes());
+        directory.extend(crc.to_le_bytes());
+        directory.extend(size.to_le_bytes());
+        directory.extend(size.to_le_bytes());
+        directory.extend((name.len() as u16).to_le_bytes());
+        directory.extend([0; 12]);
+        directory.extend(offset.to_le_bytes());
+        directory.extend(name);
+    }
+
+    let offset = out.len() as u32;
+    let count = (files.len() as u16).to_le_bytes();
+    out.extend(&directory);
+    out.extend(0x0605_4b50_u32.to_le_bytes());
+    out.extend([0, 0, 0, 0]);
+    out.extend(count);
+    out.extend(count);
+    out.extend((directory.len() as u32).to_le_bytes());
+    out.extend(offset.to_le_bytes());
+    out.extend([0, 0]);
+
+    out
+}
@@ -0,0 +1,489 @@
+use std::{
+    fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+const RECORDS: &str = "\
+---
+goal = \"10h\"
+
+[start]
+weekday = \"Mon\"
+time = \"06:00:00\"
+---
+
+2024-09-09
+[rust] 21:00 - 1h30m
+";
+
+fn write_temp(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("learning-record-tests-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, content).unwrap();
+    path
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_learning-record"))
+        .args(args)
+        .current_dir(std::env::temp_dir())
+        .env_remove("LEARNING_RECORD_FILE")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn success_exits_zero() {
+    let path = write_temp("success.lr", RECORDS);
+    let output = run(&[
+        "-f",
+        path.to_str().unwrap(),
+        "report",
+        "--today",
+        "2024-09-10T12:00:00",
+    ]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn parse_error_goes_to_stderr() {
+    let path = write_temp("broken.lr", "2024-09-09\n[rust] 21:00 1h\n");
+    let output = run(&["-f", path.to_str().unwrap(), "report"]);
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("2:14"));
+}
+
+#[test]
+fn missing_file_is_an_io_error() {
+    let output = run(&["-f", "/nonexistent/records.lr", "report"]);
+
+    assert_eq!(output.status.code(), Some(4));
+    assert!(output.stdout.is_empty());
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn goal_miss_is_reported() {
+    let path = write_temp("goal.lr", RECORDS);
+    let args = [
+        "-f",
+        path.to_str().unwrap(),
+        "report",
+        "--today",
+        "2024-09-10T12:00:00",
+    ];
+
+    let output = run(&[&args[..], &["--fail-under-goal"]].concat());
+    assert_eq!(output.status.code(), Some(6));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1h30m"));
+
+    let output = run(&args);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn usage_error_exits_two() {
+    let output = run(&["no-such-command"]);
+
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn rename_replaces_the_title_as_written_under_normalize_input() {
+    let path = write_temp(
+        "normalized.lr",
+        "---\nnormalize_input = true\n\n[start]\nweekday = \"Mon\"\ntime = \"06:00:00\"\n---\n\n\
+         2024-09-09\n[ｶﾞｸ(x) rust] 21:00 - 1h\n",
+    );
+    let output = run(&[
+        "-f",
+        path.to_str().unwrap(),
+        "tag",
+        "rename",
+        "ガク",
+        "gaku",
+    ]);
+
+    assert_eq!(output.status.code(), Some(0), "{output:?}");
+    let content = fs::read_to_string(&path).unwrap();
+    assert!(
+        content.ends_with("2024-09-09\n[gaku(x) rust] 21:00 - 1h\n"),
+        "{content}"
+    );
+}
+
+#[test]
+fn holidays_do_not_break_streaks() {
+    let path = write_temp(
+        "holiday.lr",
+        "---\nholidays = [{ from = \"2024-09-11\" }]\n\n[start]\nweekday = \"Mon\"\n\
+         time = \"06:00:00\"\n---\n\n2024-09-09\n[rust] 1h\n\n2024-09-10\n[rust] 1h\n\n\
+         2024-09-12\n[rust] 1h\n",
+    );
+    let output = run(&[
+        "-f",
+        path.to_str().unwrap(),
+        "--plain",
+        "streak",
+        "--today",
+        "2024-09-12",
+    ]);
+
+    assert_eq!(output.status.code(), Some(0), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Current streak  3 days"), "{stdout}");
+}
+
+#[test]
+fn average_leaves_out_this_week_and_weeks_before_the_first_record() {
+    let path = write_temp("average.lr", RECORDS);
+    let report = |today: &str| {
+        let output = run(&[
+            "-f",
+            path.to_str().unwrap(),
+            "--plain",
+            "report",
+            "--today",
+            today,
+        ]);
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    assert!(!report("2024-09-10T12:00:00").contains("average"));
+    assert!(report("2024-09-17T12:00:00").contains("1-week average 1h30m"));
+    assert!(report("2024-09-24T12:00:00").contains("2-week average 45m"));
+}
+
+const OVERFULL: &str = "\
+---
+[start]
+weekday = \"Mon\"
+time = \"06:00:00\"
+---
+
+2024-09-09
+[rust] 20h
+[go] 5h
+[anki] 0m
+";
+
+fn doctor(name: &str) -> String {
+    let path = write_temp(name, OVERFULL);
+    let output = run(&[
+        "-f",
+        path.to_str().unwrap(),
+        "--plain",
+        "doctor",
+        "--today",
+        "2024-09-10",
+    ]);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn doctor_reports_an_overfull_day_once() {
+    let stdout = doctor("overfull.lr");
+
+    assert_eq!(
+        stdout.matches("2024-09-09 has 25h recorded").count(),
+        1,
+        "{stdout}"
+    );
+    assert!(!stdout.contains("in one day"), "{stdout}");
+}
+
+#[test]
+fn doctor_reports_a_zero_duration_once() {
+    let stdout = doctor("zero.lr");
+
+    assert_eq!(
+        stdout.matches("line 10: event has a zero duration").count(),
+        1,
+        "{stdout}"
+    );
+    assert!(!stdout.contains("lasts 0m"), "{stdout}");
+}
+
+#[test]
+fn positional_path_runs_the_report() {
+    let path = write_temp("positional.lr", RECORDS);
+    let positional = run(&[path.to_str().unwrap(), "--today", "2024-09-10T12:00:00"]);
+    let report = run(&[
+        "-f",
+        path.to_str().unwrap(),
+        "report",
+        "--today",
+        "2024-09-10T12:00:00",
+    ]);
+
+    assert_eq!(positional.status.code(), Some(0), "{positional:?}");
+    assert_eq!(positional.stdout, report.stdout);
+}
+
+/// Runs `args` on a record file holding `content` and returns the new
+/// content of the file.
+fn mutate(name: &str, content: &str, args: &[&str]) -> (PathBuf, String) {
+    let path = write_temp(name, content);
+    let mut all = vec!["-f", path.to_str().unwrap()];
+    all.extend(args);
+    let output = run(&all);
+    assert_eq!(output.status.code(), Some(0), "{output:?}");
+
+    let content = fs::read_to_string(&path).unwrap();
+    (path, content)
+}
+
+fn undo(path: &std::path::Path, force: bool) -> Output {
+    let mut args = vec!["-f", path.to_str().unwrap(), "undo"];
+    if force {
+        args.push("--force");
+    }
+    run(&args)
+}
+
+#[test]
+fn undo_restores_the_file() {
+    let (path, content) = mutate("undo.lr", RECORDS, &["tag", "rename", "rust", "rs"]);
+    assert!(content.contains("[rs] 21:00 - 1h30m"), "{content}");
+
+    assert_eq!(undo(&path, false).status.code(), Some(0));
+    assert_eq!(fs::read_to_string(&path).unwrap(), RECORDS);
+    assert_ne!(undo(&path, false).status.code(), Some(0));
+}
+
+#[test]
+fn undo_refuses_after_an_outside_edit_without_force() {
+    let (path, content) = mutate("edited.lr", RECORDS, &["tag", "rename", "rust", "rs"]);
+    let edited = content + "\n2024-09-10\n[go] 1h\n";
+    fs::write(&path, &edited).unwrap();
+
+    let refused = undo(&path, false);
+    assert_eq!(refused.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&refused.stderr).contains("--force"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), edited);
+
+    assert_eq!(undo(&path, true).status.code(), Some(0));
+    assert_eq!(fs::read_to_string(&path).unwrap(), RECORDS);
+}
+
+const TAGGED: &str = "\
+---
+[start]
+weekday = \"Mon\"
+time = \"06:00:00\"
+---
+
+2024-09-09
+[rs(ownership) book] 21:00 - 1h
+[rustlang rust] 22:00 - 30m
+
+2024-09-10
+[go] 10:00 - 1h
+";
+
+#[test]
+fn tag_rename_dry_run_leaves_the_file_alone() {
+    let path = write_temp("rename-dry.lr", TAGGED);
+    let output = run(&[
+        "-f",
+        path.to_str().unwrap(),
+        "tag",
+        "rename",
+        "rs",
+        "rust",
+        "--dry-run",
+    ]);
+
+    assert_eq!(output.status.code(), Some(0), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("8: [rs(ownership) book]"), "{stdout}");
+    assert!(stdout.contains("[rust(ownership) book]"), "{stdout}");
+    assert_eq!(fs::read_to_string(&path).unwrap(), TAGGED);
+}
+
+#[test]
+fn tag_merge_rewrites_and_undoes() {
+    let (path, content) = mutate(
+        "merge-tags.lr",
+        TAGGED,
+        &["tag", "merge", "rs", "rustlang", "--into", "rust"],
+    );
+
+    assert_eq!(
+        content,
+        TAGGED
+            .replace("[rs(ownership) book]", "[rust(ownership) book]")
+            .replace("[rustlang rust]", "[rust]")
+    );
+    assert_eq!(undo(&path, false).status.code(), Some(0));
+    assert_eq!(fs::read_to_string(&path).unwrap(), TAGGED);
+}
+
+#[test]
+fn dedupe_removes_repeated_events_and_undoes() {
+    let duplicated = TAGGED.replace("[go] 10:00 - 1h\n", "[go] 10:00 - 1h\n[go] 10:00 - 1h\n");
+    let (path, content) = mutate("dedupe.lr", &duplicated, &["dedupe"]);
+
+    assert_eq!(content, TAGGED);
+    assert_eq!(undo(&path, false).status.code(), Some(0));
+    assert_eq!(fs::read_to_string(&path).unwrap(), duplicated);
+}
+
+#[test]
+fn split_moves_records_into_included_files_and_undoes() {
+    let report = |path: &std::path::Path| {
+        run(&[
+            "-f",
+            path.to_str().unwrap(),
+            "report",
+            "--today",
+            "2024-09-10T12:00:00",
+        ])
+        .stdout
+    };
+    let path = write_temp("split-by-day.lr", TAGGED);
+    let before = report(&path);
+    let (path, content) = mutate("split-by-day.lr", TAGGED, &["split", "--by", "month"]);
+
+    assert!(!content.contains("2024-09-09"), "{content}");
+    assert!(
+        content.contains("@include split-by-day/2024-09.lr"),
+        "{content}"
+    );
+    let part = path.with_file_name("split-by-day").join("2024-09.lr");
+    let part = fs::read_to_string(part).unwrap();
+    assert!(
+        part.contains("2024-09-09\n[rs(ownership) book] 21:00 - 1h"),
+        "{part}"
+    );
+    assert_eq!(report(&path), before);
+
+    assert_eq!(undo(&path, false).status.code(), Some(0));
+    assert_eq!(fs::read_to_string(&path).unwrap(), TAGGED);
+}
+
+#[test]
+fn merge_writes_the_union_and_leaves_the_inputs_alone() {
+    let first = write_temp("merge-first.lr", TAGGED);
+    let second_content = "2024-09-10\n[go] 10:00 - 1h\n[anki] 20:00 - 20m\n\n2024-09-11\n[go] 1h\n";
+    let second = write_temp("merge-second.lr", second_content);
+    let output = first.with_file_name("merged.lr");
+    let result = run(&[
+        "merge",
+        first.to_str().unwrap(),
+        second.to_str().unwrap(),
+        "--output",
+        output.to_str().unwrap(),
+    ]);
+
+    assert_eq!(result.status.code(), Some(0), "{result:?}");
+    let merged = fs::read_to_string(&output).unwrap();
+    assert!(merged.starts_with(TAGGED.trim_end()), "{merged}");
+    assert!(merged.contains("[anki] 20:00 - 20m"), "{merged}");
+    assert!(merged.ends_with("2024-09-11\n[go] 1h\n"), "{merged}");
+    assert_eq!(merged.matches("[go] 10:00 - 1h").count(), 1, "{merged}");
+    assert_eq!(fs::read_to_string(&first).unwrap(), TAGGED);
+    assert_eq!(fs::read_to_string(&second).unwrap(), second_content);
+}
+
+/// Serves one file at the returned URL: GET reads it and PUT replaces it.
+fn webdav(content: &str) -> String {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/records.lr", server.server_addr());
+    let mut stored = content.to_string();
+    std::thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            if *request.method() == tiny_http::Method::Put {
+                stored.clear();
+                request.as_reader().read_to_string(&mut stored).unwrap();
+            }
+            let _ = request.respond(tiny_http::Response::from_string(stored.clone()));
+        }
+    });
+    url
+}
+
+fn synced(url: &str, days: &str) -> String {
+    RECORDS.replacen(
+        "---\n",
+        &format!("---\nsync = {{ kind = \"webdav\", url = \"{url}\" }}\n"),
+        1,
+    ) + days
+}
+
+#[test]
+fn sync_settles_after_pulling_into_a_crlf_file() {
+    let url = webdav("");
+    ureq::put(&url)
+        .send_string(&synced(&url, "\n2024-09-10\n[go] 21:00 - 1h\n"))
+        .unwrap();
+    let path = write_temp("sync-crlf.lr", &synced(&url, "").replace('\n', "\r\n"));
+    let path = path.to_str().unwrap();
+    let sync = |args: &[&str]| {
+        let output = run(&[&["--file", path, "sync"], args].concat());
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(sync(&["--direction", "pull"]), "pulled remote changes\n");
+    let content = fs::read_to_string(path).unwrap();
+    assert!(content.contains("[go] 21:00 - 1h\r\n"), "{content}");
+    assert_eq!(sync(&[]), "already up to date\n");
+
+    ureq::put(&url)
+        .send_string(&synced(&url, "\n2024-09-11\n[anki] 21:00 - 20m\n"))
+        .unwrap();
+    assert_eq!(sync(&[]), "pulled remote changes\n");
+    assert!(fs::read_to_string(path)
+        .unwrap()
+        .contains("[anki] 21:00 - 20m\r\n"));
+    assert_eq!(sync(&[]), "already up to date\n");
+}
+
+#[test]
+fn out_of_range_today_is_a_usage_error() {
+    let path = write_temp("far-today.lr", RECORDS);
+    for today in ["+99999999d", "-99999999w", "+9223372036854775807d"] {
+        let output = run(&["-f", path.to_str().unwrap(), "report", "--today", today]);
+        assert_eq!(output.status.code(), Some(2), "{today}: {output:?}");
+    }
+}
+
+#[test]
+fn log_lists_events_newest_first_with_their_start() {
+    let path = write_temp("log.lr", TAGGED);
+    let output = run(&["-f", path.to_str().unwrap(), "--plain", "log", "-n", "2"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Tue 2024-09-10 10:00  go                   1h\n\
+         Mon 2024-09-09 22:00  rustlang rust       30m\n"
+    );
+}
+
+#[test]
+fn tag_filters_are_normalized_under_normalize_tags() {
+    let path = write_temp(
+        "normalized-filter.lr",
+        &TAGGED.replacen("---\n", "---\nnormalize_tags = true\n", 1),
+    );
+    let output = run(&[
+        "-f",
+        path.to_str().unwrap(),
+        "--plain",
+        "log",
+        "--tag",
+        "GO",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Tue 2024-09-10 10:00  go        1h\n"
+    );
+}
@@ -0,0 +1,171 @@
+use std::{fs, path::PathBuf, process::Command};
+
+use learning_record::{ast::File, parser::Parser};
+
+const RECORDS: &str = "\
+---
+goal = \"10h\"
+
+[start]
+weekday = \"Mon\"
+time = \"06:00:00\"
+---
+
+2024-09-09
+[rust(ownership) book] 21:00 - 1h30m, 23:00 - 20m
+# a comment
+07:30 - 45m
+
+2024-09-10
+[go] 10:00 - 2h
+";
+
+fn parse(source: &str) -> File {
+    Parser::new(source.chars().collect())
+        .parse_file()
+        .unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Date, line and text of every event.
+fn events(file: &File) -> Vec<(String, usize, String)> {
+    file.records
+        .iter()
+        .flat_map(|record| {
+            record
+                .events
+                .iter()
+                .map(move |event| (record.date.to_string(), event.line, event.to_string()))
+        })
+        .collect()
+}
+
+#[test]
+fn crlf_file_reads_like_lf() {
+    let lf = parse(RECORDS);
+    let crlf = parse(&RECORDS.replace('\n', "\r\n"));
+
+    assert_eq!(crlf.settings, lf.settings);
+    assert_eq!(events(&crlf), events(&lf));
+    assert_eq!(events(&lf).len(), 3);
+}
+
+#[test]
+fn crlf_file_without_final_newline() {
+    let source = RECORDS.trim_end().replace('\n', "\r\n");
+
+    assert_eq!(events(&parse(&source)), events(&parse(RECORDS)));
+}
+
+#[test]
+fn crlf_yaml_front_matter() {
+    let source = "---\r\ngoal: 10h\r\nstart:\r\n  weekday: Mon\r\n  time: \"06:00:00\"\r\n---\r\n\r\n2024-09-09\r\n[rust] 21:00 - 1h\r\n";
+    let file = parse(source);
+
+    assert_eq!(file.settings, parse(RECORDS).settings);
+    assert_eq!(file.records.len(), 1);
+}
+
+#[test]
+fn byte_order_mark_is_skipped() {
+    let lf = parse(RECORDS);
+    let bom = parse(&format!("\u{feff}{RECORDS}"));
+    let both = parse(&format!("\u{feff}{}", RECORDS.replace('\n', "\r\n")));
+
+    assert_eq!(bom.settings, lf.settings);
+    assert_eq!(events(&bom), events(&lf));
+    assert_eq!(events(&both), events(&lf));
+}
+
+#[test]
+fn byte_order_mark_before_records_without_settings() {
+    let file = parse("\u{feff}2024-09-09\r\n[rust] 21:00 - 1h\r\n");
+
+    assert_eq!(file.records.len(), 1);
+    assert_eq!(file.records[0].line, 1);
+}
+
+#[test]
+fn tag_spans_point_into_the_source_as_given() {
+    let source = format!("\u{feff}{}", RECORDS.replace('\n', "\r\n"));
+    let chars: Vec<char> = source.chars().collect();
+    let file = parse(&source);
+
+    let tags = &file.records[0].events[0].tags.as_ref().unwrap().tags;
+    let spanned: Vec<String> = tags
+        .iter()
+        .map(|tag| chars[tag.span.clone()].iter().collect())
+        .collect();
+    assert_eq!(spanned, ["rust(ownership)", "book"]);
+    let tags = &file.records[1].events[0].tags.as_ref().unwrap().tags;
+    assert_eq!(chars[tags[0].span.clone()].iter().collect::<String>(), "go");
+}
+
+#[test]
+fn errors_count_crlf_lines_once() {
+    let source = "2024-09-09\r\n[rust] 21:00 - 1h\r\n[go] 25:00 - 1h\r\n";
+    let err = Parser::new(source.chars().collect())
+        .parse_file()
+        .unwrap_err();
+
+    assert_eq!(err.line, 3);
+}
+
+fn write_temp(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("learning-record-newlines-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, content).unwrap();
+    path
+}
+
+/// Imports one session on `date` into `path` and returns the new content.
+fn import(path: &PathBuf, date: &str) -> String {
+    let json = write_temp(
+        &format!("{date}.json"),
+        &format!(r#"[{{"start": "{date}T08:00:00", "duration": "30m", "tags": ["go"]}}]"#),
+    );
+    let output = Command::new(env!("CARGO_BIN_EXE_learning-record"))
+        .args(["-f", path.to_str().unwrap(), "import", "json"])
+        .arg(&json)
+        .env_remove("LEARNING_RECORD_FILE")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn writes_keep_crlf_and_byte_order_mark() {
+    let source = format!("\u{feff}{}", RECORDS.replace('\n', "\r\n"));
+    let path = write_temp("crlf.lr", &source);
+    let content = import(&path, "2024-09-11");
+
+    assert!(content.starts_with('\u{feff}'));
+    assert!(
+        content.starts_with(&source[..source.len() - 2]),
+        "{content:?}"
+    );
+    assert!(
+        content.ends_with("\r\n\r\n2024-09-11\r\n[go] 08:00 - 30m\r\n"),
+        "{content:?}"
+    );
+    assert_eq!(
+        content.matches('\n').count(),
+        content.matches("\r\n").count()
+    );
+}
+
+#[test]
+fn first_day_goes_inside_the_markdown_block() {
+    let path = write_temp(
+        "note.md",
+        "# Notes\n\n```learning-record\n```\n\nMore text.\n",
+    );
+    let content = import(&path, "2024-09-11");
+
+    assert_eq!(
+        content,
+        "# Notes\n\n```learning-record\n2024-09-11\n[go] 08:00 - 30m\n```\n\nMore text.\n"
+    );
+}
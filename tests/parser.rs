@@ -0,0 +1,134 @@
+use learning_record::parser::{self, ParseErrorKind, Parser};
+
+const RECORDS: &str = "\
+---
+goal = \"10h\"
+
+[start]
+weekday = \"Mon\"
+time = \"06:00:00\"
+---
+
+2024-09-09
+[rust(ownership) book] ~2h 21:00 - 1h30m, 23:00 - 20m
+# a comment
+[anki] 3p, !-10m
+9:30 pm - 45m
+
+2024/09/10
+[go] 10:00:30 - 2h5m10s
+";
+
+/// Characters that mean something to the parser, plus a few that do not.
+const ALPHABET: &[char] = &[
+    '0', '1', '2', '9', '-', '/', '.', ':', '[', ']', '(', ')', '~', '!', ',', 'h', 'm', 's', 'p',
+    'a', '#', '@', ' ', '\t', '\n', '\r', 'é', '３',
+];
+
+fn parse(source: &str) -> Result<(), parser::ParseError> {
+    Parser::new(source.chars().collect())
+        .parse_file()
+        .map(|_| ())
+}
+
+fn kind(source: &str) -> ParseErrorKind {
+    parse(source).unwrap_err().kind
+}
+
+/// xorshift64, so that failures can be replayed without a dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 as usize
+    }
+}
+
+#[test]
+fn overflowing_year_is_an_invalid_date() {
+    assert!(matches!(
+        kind("99999999999-01-01\n"),
+        ParseErrorKind::InvalidDate
+    ));
+}
+
+#[test]
+fn overflowing_time_is_invalid() {
+    assert!(matches!(
+        kind("2024-09-09\n[rust] 99999999999:00 - 1h\n"),
+        ParseErrorKind::InvalidTime
+    ));
+}
+
+#[test]
+fn overflowing_duration_is_invalid() {
+    for duration in [
+        "9999999999999h",
+        "99999999999999999m",
+        "9223372036854775807s",
+    ] {
+        assert!(
+            matches!(
+                kind(&format!("2024-09-09\n[rust] 21:00 - {duration}\n")),
+                ParseErrorKind::InvalidDurationFormat
+            ),
+            "{duration}"
+        );
+        assert!(parser::parse_duration(duration).is_err(), "{duration}");
+    }
+}
+
+#[test]
+fn truncated_records_never_panic() {
+    let chars: Vec<char> = RECORDS.chars().collect();
+    assert!(parse(RECORDS).is_ok());
+    for end in 0..chars.len() {
+        let source: String = chars[..end].iter().collect();
+        let _ = parse(&source);
+    }
+}
+
+#[test]
+fn mutated_records_never_panic() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..5000 {
+        let mut chars: Vec<char> = RECORDS.chars().collect();
+        for _ in 0..=rng.next() % 4 {
+            let index = rng.next() % chars.len();
+            let c = ALPHABET[rng.next() % ALPHABET.len()];
+            match rng.next() % 3 {
+                0 => chars[index] = c,
+                1 => chars.insert(index, c),
+                _ => {
+                    chars.remove(index);
+                }
+            }
+        }
+        let source: String = chars.into_iter().collect();
+        let _ = parse(&source);
+    }
+}
+
+#[test]
+fn arbitrary_input_never_panics() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..5000 {
+        let length = rng.next() % 64;
+        let source: String = (0..length)
+            .map(|_| ALPHABET[rng.next() % ALPHABET.len()])
+            .collect();
+        let _ = parse(&source);
+        let _ = parser::parse_duration(&source);
+    }
+}
+
+#[test]
+fn unclosed_tags_end_at_the_line_end() {
+    assert!(matches!(
+        kind("2024-09-09\n[rust 21:00 - 1h\n"),
+        ParseErrorKind::ExpectedChars { found: '\n', .. }
+    ));
+}
@@ -0,0 +1,149 @@
+use std::{
+    fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+const RECORDS: &str = "\
+---
+[start]
+weekday = \"Mon\"
+time = \"00:00:00\"
+---
+
+2024-09-09
+[rust(ownership) book] 07:00 - 45m, 21:00 - 1h30m
+[anki] 08:00 - 20m
+
+2024-09-14
+[go] 10:00 - 2h
+[rust(async)] 19:00 - 1h
+";
+
+fn write_temp(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("learning-record-query-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, content).unwrap();
+    path
+}
+
+fn run(args: &[&str]) -> Output {
+    let path = write_temp("records.lr", RECORDS);
+    Command::new(env!("CARGO_BIN_EXE_learning-record"))
+        .args(["-f", path.to_str().unwrap(), "--today", "2024-09-15"])
+        .args(args)
+        .env_remove("LEARNING_RECORD_FILE")
+        .output()
+        .unwrap()
+}
+
+/// Total minutes of the week reported with `--filter query`.
+fn filtered_minutes(query: &str) -> i64 {
+    let output = run(&["report", "--format", "json", "--filter", query]);
+    assert_eq!(
+        output.status.code(),
+        Some(0),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    json["total_minutes"].as_i64().unwrap()
+}
+
+#[test]
+fn tag_terms() {
+    assert_eq!(filtered_minutes("tag:rust"), 45 + 90 + 60);
+    assert_eq!(filtered_minutes("tag:RUST"), 45 + 90 + 60);
+    assert_eq!(filtered_minutes("tag:rus"), 0);
+}
+
+#[test]
+fn words_match_tags_and_details() {
+    assert_eq!(filtered_minutes("own"), 45 + 90);
+    assert_eq!(filtered_minutes("detail:async"), 60);
+    assert_eq!(filtered_minutes("\"async\""), 60);
+}
+
+#[test]
+fn terms_are_combined_with_and() {
+    assert_eq!(filtered_minutes("tag:rust dur>1h"), 90);
+    assert_eq!(filtered_minutes("tag:rust dur>=1h"), 90 + 60);
+    assert_eq!(filtered_minutes("tag:rust time<12:00"), 45);
+}
+
+#[test]
+fn or_and_negation() {
+    assert_eq!(filtered_minutes("tag:go or tag:anki"), 120 + 20);
+    assert_eq!(filtered_minutes("-tag:rust"), 20 + 120);
+    assert_eq!(filtered_minutes("tag:rust -ownership or tag:anki"), 60 + 20);
+}
+
+#[test]
+fn date_terms() {
+    assert_eq!(filtered_minutes("after:2024-09-14"), 120 + 60);
+    assert_eq!(filtered_minutes("before:2024-09-14"), 45 + 90 + 20);
+    assert_eq!(filtered_minutes("on:2024-09-09 dur<30m"), 20);
+    assert_eq!(filtered_minutes("day:sat"), 120 + 60);
+    assert_eq!(filtered_minutes("day:saturday tag:go"), 120);
+}
+
+#[test]
+fn invalid_queries_are_usage_errors() {
+    for (query, message) in [
+        ("", "empty query"),
+        ("tag:rust or", "empty query"),
+        ("or tag:rust", "empty query"),
+        ("colour:red", "unknown field `colour`"),
+        ("after:2024-13-01", "invalid date"),
+        ("dur>soon", "invalid duration"),
+        ("time>25:00", "invalid time"),
+        ("day:someday", "invalid weekday"),
+        ("tag>rust", "only `:` is supported"),
+        ("tag:", "missing value"),
+        ("-", "missing value"),
+        ("\"rust", "unterminated quote"),
+    ] {
+        let output = run(&["report", "--filter", query]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(2), "{query}: {stderr}");
+        assert!(stderr.contains(message), "{query}: {stderr}");
+    }
+}
+
+#[test]
+fn search_lists_matching_events() {
+    let output = run(&["search", "tag:rust dur>=1h", "--plain"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout.contains("[rust(ownership) book]"));
+    assert!(stdout.contains("[rust(async)]"));
+    assert!(!stdout.contains("[go]"));
+
+    let output = run(&["search", "tag:python"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn invoice_filter() {
+    let rates = RECORDS.replacen("---\n\n", "[rates]\nrust = 60\n---\n\n", 1);
+    let path = write_temp("invoice.lr", &rates);
+    let output = Command::new(env!("CARGO_BIN_EXE_learning-record"))
+        .args(["-f", path.to_str().unwrap(), "export", "invoice"])
+        .args([
+            "--tag",
+            "rust",
+            "--from",
+            "2024-09-01",
+            "--to",
+            "2024-09-30",
+        ])
+        .args(["--filter", "time>=12:00"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout.contains("**Total:** 2.50 h, 150.00"), "{stdout}");
+}